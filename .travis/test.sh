@@ -6,6 +6,8 @@ export PATH="$HOME/.cargo/bin:$PATH"
 if [[ "${RUST_TESTS}" == "true" ]]; then
     echo "Running rust tests..."
     cargo test --all
+    echo "Running rust tests with the native CRF implementation..."
+    cargo test -p snips-nlu-lib --no-default-features
     cargo check --benches
 fi
 
@@ -3,23 +3,157 @@
 extern crate ffi_utils;
 extern crate snips_nlu_ontology_ffi_macros;
 
+use std::cell::Cell;
 use std::ffi::{CStr, CString};
 use std::io::Cursor;
+use std::path::PathBuf;
 use std::slice;
 use std::sync::Mutex;
 
 use failure::{format_err, ResultExt};
 use ffi_utils::*;
-use snips_nlu_lib::SnipsNluEngine;
+use snips_nlu_lib::injection::{NluInjectionErrorKind, NluInjector};
+use snips_nlu_lib::{error_kind, SnipsNluEngine, SnipsNluErrorKind};
 use snips_nlu_ontology_ffi_macros::{CIntentClassifierResultArray, CIntentParserResult, CSlotList};
 
 type Result<T> = std::result::Result<T, failure::Error>;
 
-pub struct CSnipsNluEngine(std::sync::Mutex<SnipsNluEngine>);
+pub struct CSnipsNluEngine(SnipsNluEngine);
 
+fn _assert_sync<T: Send + Sync>() {}
+
+/// The engine is shared between threads without any lock, which only compiles if it is `Sync`
+fn _assert_engine_is_sync() {
+    _assert_sync::<SnipsNluEngine>();
+}
+
+pub struct CNluInjector(std::sync::Mutex<Option<NluInjector<PathBuf>>>);
+
+/// Kind of error which occurred during an injection
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SNIPS_INJECTION_ERROR_KIND {
+    /// No error occurred
+    SNIPS_INJECTION_ERROR_KIND_NONE = 0,
+    /// One of the entities cannot be injected, either because it is unknown or because it is not
+    /// a gazetteer entity
+    SNIPS_INJECTION_ERROR_KIND_ENTITY_NOT_INJECTABLE = 1,
+    /// The injection failed while loading or dumping the entity parsers
+    SNIPS_INJECTION_ERROR_KIND_INTERNAL = 2,
+    /// The injector could not be used, e.g. because it has already been run
+    SNIPS_INJECTION_ERROR_KIND_INVALID_INJECTOR = 3,
+}
+
+impl From<&NluInjectionErrorKind> for SNIPS_INJECTION_ERROR_KIND {
+    fn from(kind: &NluInjectionErrorKind) -> Self {
+        match kind {
+            NluInjectionErrorKind::EntityNotInjectable { .. } => {
+                SNIPS_INJECTION_ERROR_KIND::SNIPS_INJECTION_ERROR_KIND_ENTITY_NOT_INJECTABLE
+            }
+            NluInjectionErrorKind::InternalInjectionError { .. } => {
+                SNIPS_INJECTION_ERROR_KIND::SNIPS_INJECTION_ERROR_KIND_INTERNAL
+            }
+        }
+    }
+}
+
+/// Code of the last error which occurred, these codes are stable across versions
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SNIPS_NLU_ERROR_CODE {
+    /// No error occurred
+    SNIPS_NLU_ERROR_CODE_NONE = 0,
+    /// Any error which does not fit in the other categories
+    SNIPS_NLU_ERROR_CODE_INTERNAL = 1,
+    /// The model files cannot be read or deserialized
+    SNIPS_NLU_ERROR_CODE_MODEL_LOAD = 2,
+    /// The model was trained with an incompatible version
+    SNIPS_NLU_ERROR_CODE_WRONG_MODEL_VERSION = 3,
+    /// The model could be read but contains invalid values
+    SNIPS_NLU_ERROR_CODE_INVALID_MODEL = 4,
+    /// An unknown intent was used, for instance in an intents filter
+    SNIPS_NLU_ERROR_CODE_UNKNOWN_INTENT = 5,
+    /// An unknown slot was used
+    SNIPS_NLU_ERROR_CODE_UNKNOWN_SLOT = 6,
+    /// The builtin or custom entity parser failed
+    SNIPS_NLU_ERROR_CODE_ENTITY_PARSER = 7,
+    /// The injection of new entity values failed
+    SNIPS_NLU_ERROR_CODE_INJECTION = 8,
+    /// The input cannot be processed, e.g. because it is too long
+    SNIPS_NLU_ERROR_CODE_INVALID_INPUT = 9,
+    /// An unknown entity was used, for instance in an entities scope
+    SNIPS_NLU_ERROR_CODE_UNKNOWN_ENTITY = 10,
+}
+
+impl From<SnipsNluErrorKind> for SNIPS_NLU_ERROR_CODE {
+    fn from(kind: SnipsNluErrorKind) -> Self {
+        match kind {
+            SnipsNluErrorKind::Internal => SNIPS_NLU_ERROR_CODE::SNIPS_NLU_ERROR_CODE_INTERNAL,
+            SnipsNluErrorKind::ModelLoad => SNIPS_NLU_ERROR_CODE::SNIPS_NLU_ERROR_CODE_MODEL_LOAD,
+            SnipsNluErrorKind::WrongModelVersion => {
+                SNIPS_NLU_ERROR_CODE::SNIPS_NLU_ERROR_CODE_WRONG_MODEL_VERSION
+            }
+            SnipsNluErrorKind::InvalidModel => {
+                SNIPS_NLU_ERROR_CODE::SNIPS_NLU_ERROR_CODE_INVALID_MODEL
+            }
+            SnipsNluErrorKind::UnknownIntent => {
+                SNIPS_NLU_ERROR_CODE::SNIPS_NLU_ERROR_CODE_UNKNOWN_INTENT
+            }
+            SnipsNluErrorKind::UnknownSlot => {
+                SNIPS_NLU_ERROR_CODE::SNIPS_NLU_ERROR_CODE_UNKNOWN_SLOT
+            }
+            SnipsNluErrorKind::EntityParser => {
+                SNIPS_NLU_ERROR_CODE::SNIPS_NLU_ERROR_CODE_ENTITY_PARSER
+            }
+            SnipsNluErrorKind::Injection => SNIPS_NLU_ERROR_CODE::SNIPS_NLU_ERROR_CODE_INJECTION,
+            SnipsNluErrorKind::InvalidInput => {
+                SNIPS_NLU_ERROR_CODE::SNIPS_NLU_ERROR_CODE_INVALID_INPUT
+            }
+            SnipsNluErrorKind::UnknownEntity => {
+                SNIPS_NLU_ERROR_CODE::SNIPS_NLU_ERROR_CODE_UNKNOWN_ENTITY
+            }
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR_CODE: Cell<SNIPS_NLU_ERROR_CODE> =
+        Cell::new(SNIPS_NLU_ERROR_CODE::SNIPS_NLU_ERROR_CODE_NONE);
+}
+
+/// Same as `wrap!`, but also records the code of the error in the current thread, so that it
+/// can be retrieved with `snips_nlu_engine_get_last_error_code`, the code being reset to
+/// `SNIPS_NLU_ERROR_CODE_NONE` when the call succeeds
+///
+/// Only the API calls which can fail use it: the getters and the destructors use `wrap!`, so that
+/// the code survives the retrieval and destruction of the error message.
+macro_rules! wrap_with_error_code {
+    ($e:expr) => {{
+        let result: Result<_> = $e;
+        let code = match result {
+            Ok(_) => SNIPS_NLU_ERROR_CODE::SNIPS_NLU_ERROR_CODE_NONE,
+            Err(ref error) => error_kind(error).into(),
+        };
+        set_last_error_code(code);
+        wrap!(result)
+    }};
+}
+
+fn set_last_error_code(code: SNIPS_NLU_ERROR_CODE) {
+    LAST_ERROR_CODE.with(|last_error_code| last_error_code.set(code));
+}
+
+// The engine is not locked as it can safely be used concurrently
 macro_rules! get_nlu_engine {
     ($opaque:ident) => {{
-        unsafe { <CSnipsNluEngine as ffi_utils::RawBorrow<CSnipsNluEngine>>::raw_borrow($opaque) }?
+        &unsafe { <CSnipsNluEngine as ffi_utils::RawBorrow<CSnipsNluEngine>>::raw_borrow($opaque) }?
+            .0
+    }};
+}
+
+macro_rules! get_nlu_injector {
+    ($opaque:ident) => {{
+        unsafe { <CNluInjector as ffi_utils::RawBorrow<CNluInjector>>::raw_borrow($opaque) }?
             .0
             .lock()
             .map_err(|e| format_err!("poisoning pointer: {}", e))?
@@ -28,12 +162,25 @@ macro_rules! get_nlu_engine {
 
 generate_error_handling!(snips_nlu_engine_get_last_error);
 
+/// Retrieves the code of the last error that happened in this thread, whose message can be
+/// retrieved with `snips_nlu_engine_get_last_error`, or `SNIPS_NLU_ERROR_CODE_NONE` if the last
+/// API call succeeded
+///
+/// The getters and the `destroy` functions leave the code untouched, so it can be retrieved after
+/// the error message has been destroyed.
+#[no_mangle]
+pub extern "C" fn snips_nlu_engine_get_last_error_code(
+    code: *mut SNIPS_NLU_ERROR_CODE,
+) -> SNIPS_RESULT {
+    wrap!(get_last_error_code(code))
+}
+
 #[no_mangle]
 pub extern "C" fn snips_nlu_engine_create_from_dir(
     root_dir: *const libc::c_char,
     client: *mut *const CSnipsNluEngine,
 ) -> SNIPS_RESULT {
-    wrap!(create_from_dir(root_dir, client))
+    wrap_with_error_code!(create_from_dir(root_dir, client))
 }
 
 #[no_mangle]
@@ -42,7 +189,7 @@ pub extern "C" fn snips_nlu_engine_create_from_zip(
     zip_size: libc::c_uint,
     client: *mut *const CSnipsNluEngine,
 ) -> SNIPS_RESULT {
-    wrap!(create_from_zip(zip, zip_size, client))
+    wrap_with_error_code!(create_from_zip(zip, zip_size, client))
 }
 
 #[no_mangle]
@@ -53,7 +200,7 @@ pub extern "C" fn snips_nlu_engine_run_parse(
     intents_blacklist: *const CStringArray,
     result: *mut *const CIntentParserResult,
 ) -> SNIPS_RESULT {
-    wrap!(run_parse_with_alternatives(
+    wrap_with_error_code!(run_parse_with_alternatives(
         client,
         input,
         intents_whitelist,
@@ -74,7 +221,7 @@ pub extern "C" fn snips_nlu_engine_run_parse_with_alternatives(
     slots_alternatives: libc::c_uint,
     result: *mut *const CIntentParserResult,
 ) -> SNIPS_RESULT {
-    wrap!(run_parse_with_alternatives(
+    wrap_with_error_code!(run_parse_with_alternatives(
         client,
         input,
         intents_whitelist,
@@ -92,7 +239,7 @@ pub extern "C" fn snips_nlu_engine_run_get_slots(
     intent: *const libc::c_char,
     result: *mut *const CSlotList,
 ) -> SNIPS_RESULT {
-    wrap!(run_get_slots(client, input, intent, 0, result))
+    wrap_with_error_code!(run_get_slots(client, input, intent, 0, result))
 }
 
 #[no_mangle]
@@ -103,7 +250,7 @@ pub extern "C" fn snips_nlu_engine_run_get_slots_with_alternatives(
     slots_alternatives: libc::c_uint,
     result: *mut *const CSlotList,
 ) -> SNIPS_RESULT {
-    wrap!(run_get_slots(
+    wrap_with_error_code!(run_get_slots(
         client,
         input,
         intent,
@@ -118,7 +265,7 @@ pub extern "C" fn snips_nlu_engine_run_get_intents(
     input: *const libc::c_char,
     result: *mut *const CIntentClassifierResultArray,
 ) -> SNIPS_RESULT {
-    wrap!(run_get_intents(client, input, result))
+    wrap_with_error_code!(run_get_intents(client, input, result))
 }
 
 #[no_mangle]
@@ -129,7 +276,7 @@ pub extern "C" fn snips_nlu_engine_run_parse_into_json(
     intents_blacklist: *const CStringArray,
     result_json: *mut *const libc::c_char,
 ) -> SNIPS_RESULT {
-    wrap!(run_parse_with_alternatives_into_json(
+    wrap_with_error_code!(run_parse_with_alternatives_into_json(
         client,
         input,
         intents_whitelist,
@@ -150,7 +297,7 @@ pub extern "C" fn snips_nlu_engine_run_parse_with_alternatives_into_json(
     slots_alternatives: libc::c_uint,
     result_json: *mut *const libc::c_char,
 ) -> SNIPS_RESULT {
-    wrap!(run_parse_with_alternatives_into_json(
+    wrap_with_error_code!(run_parse_with_alternatives_into_json(
         client,
         input,
         intents_whitelist,
@@ -161,6 +308,63 @@ pub extern "C" fn snips_nlu_engine_run_parse_with_alternatives_into_json(
     ))
 }
 
+/// Parses the input like `snips_nlu_engine_run_parse_into_json`, but also returns the slots which
+/// are nested in other slots, in the `subSlots` attribute of their parent slot
+#[no_mangle]
+pub extern "C" fn snips_nlu_engine_run_parse_with_nested_slots_into_json(
+    client: *const CSnipsNluEngine,
+    input: *const libc::c_char,
+    intents_whitelist: *const CStringArray,
+    intents_blacklist: *const CStringArray,
+    result_json: *mut *const libc::c_char,
+) -> SNIPS_RESULT {
+    wrap_with_error_code!(run_parse_with_nested_slots_into_json(
+        client,
+        input,
+        intents_whitelist,
+        intents_blacklist,
+        result_json
+    ))
+}
+
+/// Parses the input like `snips_nlu_engine_run_parse_into_json`, but also returns the reason why
+/// the input has been considered out of scope, if it has
+#[no_mangle]
+pub extern "C" fn snips_nlu_engine_run_parse_with_out_of_scope_reason_into_json(
+    client: *const CSnipsNluEngine,
+    input: *const libc::c_char,
+    intents_whitelist: *const CStringArray,
+    intents_blacklist: *const CStringArray,
+    result_json: *mut *const libc::c_char,
+) -> SNIPS_RESULT {
+    wrap_with_error_code!(run_parse_with_out_of_scope_reason_into_json(
+        client,
+        input,
+        intents_whitelist,
+        intents_blacklist,
+        result_json
+    ))
+}
+
+/// Parses the input like `snips_nlu_engine_run_parse_into_json`, but also returns the slots which
+/// have been rejected by the slot post processors, and checks the slots against the intent schema
+#[no_mangle]
+pub extern "C" fn snips_nlu_engine_run_parse_with_slot_validation_into_json(
+    client: *const CSnipsNluEngine,
+    input: *const libc::c_char,
+    intents_whitelist: *const CStringArray,
+    intents_blacklist: *const CStringArray,
+    result_json: *mut *const libc::c_char,
+) -> SNIPS_RESULT {
+    wrap_with_error_code!(run_parse_with_slot_validation_into_json(
+        client,
+        input,
+        intents_whitelist,
+        intents_blacklist,
+        result_json
+    ))
+}
+
 #[no_mangle]
 pub extern "C" fn snips_nlu_engine_run_get_slots_into_json(
     client: *const CSnipsNluEngine,
@@ -168,7 +372,7 @@ pub extern "C" fn snips_nlu_engine_run_get_slots_into_json(
     intent: *const libc::c_char,
     result_json: *mut *const libc::c_char,
 ) -> SNIPS_RESULT {
-    wrap!(run_get_slots_into_json(
+    wrap_with_error_code!(run_get_slots_into_json(
         client,
         input,
         intent,
@@ -185,7 +389,7 @@ pub extern "C" fn snips_nlu_engine_run_get_slots_with_alternatives_into_json(
     slots_alternatives: libc::c_uint,
     result_json: *mut *const libc::c_char,
 ) -> SNIPS_RESULT {
-    wrap!(run_get_slots_into_json(
+    wrap_with_error_code!(run_get_slots_into_json(
         client,
         input,
         intent,
@@ -200,7 +404,44 @@ pub extern "C" fn snips_nlu_engine_run_get_intents_into_json(
     input: *const libc::c_char,
     result_json: *mut *const libc::c_char,
 ) -> SNIPS_RESULT {
-    wrap!(run_get_intents_into_json(client, input, result_json))
+    wrap_with_error_code!(run_get_intents_into_json(client, input, result_json))
+}
+
+/// Extracts the builtin and custom entities of the input, as a JSON list sorted by range
+///
+/// The scope (if not null) restricts the extraction to the provided builtin entity identifiers
+/// and custom entity names
+#[no_mangle]
+pub extern "C" fn snips_nlu_engine_run_extract_entities_into_json(
+    client: *const CSnipsNluEngine,
+    input: *const libc::c_char,
+    scope: *const CStringArray,
+    result_json: *mut *const libc::c_char,
+) -> SNIPS_RESULT {
+    wrap_with_error_code!(run_extract_entities_into_json(
+        client,
+        input,
+        scope,
+        0,
+        result_json
+    ))
+}
+
+#[no_mangle]
+pub extern "C" fn snips_nlu_engine_run_extract_entities_with_alternatives_into_json(
+    client: *const CSnipsNluEngine,
+    input: *const libc::c_char,
+    scope: *const CStringArray,
+    entities_alternatives: libc::c_uint,
+    result_json: *mut *const libc::c_char,
+) -> SNIPS_RESULT {
+    wrap_with_error_code!(run_extract_entities_into_json(
+        client,
+        input,
+        scope,
+        entities_alternatives,
+        result_json
+    ))
 }
 
 #[no_mangle]
@@ -232,6 +473,48 @@ pub extern "C" fn snips_nlu_engine_destroy_intent_classifier_results(
     wrap!(unsafe { CIntentClassifierResultArray::from_raw_pointer(result) })
 }
 
+#[no_mangle]
+pub extern "C" fn snips_nlu_injector_create(
+    engine_dir: *const libc::c_char,
+    injector: *mut *const CNluInjector,
+) -> SNIPS_RESULT {
+    wrap_with_error_code!(create_injector(engine_dir, injector))
+}
+
+#[no_mangle]
+pub extern "C" fn snips_nlu_injector_add_value(
+    injector: *const CNluInjector,
+    entity: *const libc::c_char,
+    value: *const libc::c_char,
+) -> SNIPS_RESULT {
+    wrap_with_error_code!(injector_add_value(injector, entity, value))
+}
+
+#[no_mangle]
+pub extern "C" fn snips_nlu_injector_set_from_vanilla(
+    injector: *const CNluInjector,
+    from_vanilla: libc::c_uchar,
+) -> SNIPS_RESULT {
+    wrap_with_error_code!(injector_set_from_vanilla(injector, from_vanilla))
+}
+
+/// Runs the injection, the injector cannot be reused afterwards and must be destroyed.
+///
+/// When the injection fails, `error_kind` (if not null) is set to the kind of the error and the
+/// details can be retrieved with `snips_nlu_engine_get_last_error`
+#[no_mangle]
+pub extern "C" fn snips_nlu_injector_run(
+    injector: *const CNluInjector,
+    error_kind: *mut SNIPS_INJECTION_ERROR_KIND,
+) -> SNIPS_RESULT {
+    wrap_with_error_code!(run_injection(injector, error_kind))
+}
+
+#[no_mangle]
+pub extern "C" fn snips_nlu_injector_destroy(injector: *mut CNluInjector) -> SNIPS_RESULT {
+    wrap!(unsafe { CNluInjector::from_raw_pointer(injector) })
+}
+
 #[no_mangle]
 pub extern "C" fn snips_nlu_engine_get_model_version(
     version: *mut *const libc::c_char,
@@ -247,7 +530,7 @@ fn create_from_dir(
 
     let nlu_engine = SnipsNluEngine::from_path(root_dir)?;
 
-    let raw_pointer = CSnipsNluEngine(Mutex::new(nlu_engine)).into_raw_pointer();
+    let raw_pointer = CSnipsNluEngine(nlu_engine).into_raw_pointer();
     unsafe { *client = raw_pointer };
 
     Ok(())
@@ -261,7 +544,7 @@ fn create_from_zip(
     let slice = unsafe { slice::from_raw_parts(zip, zip_size as usize) };
     let reader = Cursor::new(slice.to_owned());
     let nlu_engine = SnipsNluEngine::from_zip(reader)?;
-    let raw_pointer = CSnipsNluEngine(Mutex::new(nlu_engine)).into_raw_pointer();
+    let raw_pointer = CSnipsNluEngine(nlu_engine).into_raw_pointer();
     unsafe { *client = raw_pointer };
 
     Ok(())
@@ -373,6 +656,81 @@ fn run_parse_with_alternatives_into_json(
     point_to_string(result_json, serde_json::to_string(&results)?)
 }
 
+fn run_parse_with_nested_slots_into_json(
+    client: *const CSnipsNluEngine,
+    input: *const libc::c_char,
+    intents_whitelist: *const CStringArray,
+    intents_blacklist: *const CStringArray,
+    result_json: *mut *const libc::c_char,
+) -> Result<()> {
+    let input = create_rust_string_from!(input);
+    let nlu_engine = get_nlu_engine!(client);
+
+    let opt_whitelist: Option<Vec<_>> = if !intents_whitelist.is_null() {
+        Some(unsafe { convert_to_rust_vec(intents_whitelist)? })
+    } else {
+        None
+    };
+    let opt_blacklist: Option<Vec<_>> = if !intents_blacklist.is_null() {
+        Some(unsafe { convert_to_rust_vec(intents_blacklist)? })
+    } else {
+        None
+    };
+    let result = nlu_engine.parse_with_nested_slots(&input, opt_whitelist, opt_blacklist)?;
+
+    point_to_string(result_json, serde_json::to_string(&result)?)
+}
+
+fn run_parse_with_out_of_scope_reason_into_json(
+    client: *const CSnipsNluEngine,
+    input: *const libc::c_char,
+    intents_whitelist: *const CStringArray,
+    intents_blacklist: *const CStringArray,
+    result_json: *mut *const libc::c_char,
+) -> Result<()> {
+    let input = create_rust_string_from!(input);
+    let nlu_engine = get_nlu_engine!(client);
+
+    let opt_whitelist: Option<Vec<_>> = if !intents_whitelist.is_null() {
+        Some(unsafe { convert_to_rust_vec(intents_whitelist)? })
+    } else {
+        None
+    };
+    let opt_blacklist: Option<Vec<_>> = if !intents_blacklist.is_null() {
+        Some(unsafe { convert_to_rust_vec(intents_blacklist)? })
+    } else {
+        None
+    };
+    let result = nlu_engine.parse_with_out_of_scope_reason(&input, opt_whitelist, opt_blacklist)?;
+
+    point_to_string(result_json, serde_json::to_string(&result)?)
+}
+
+fn run_parse_with_slot_validation_into_json(
+    client: *const CSnipsNluEngine,
+    input: *const libc::c_char,
+    intents_whitelist: *const CStringArray,
+    intents_blacklist: *const CStringArray,
+    result_json: *mut *const libc::c_char,
+) -> Result<()> {
+    let input = create_rust_string_from!(input);
+    let nlu_engine = get_nlu_engine!(client);
+
+    let opt_whitelist: Option<Vec<_>> = if !intents_whitelist.is_null() {
+        Some(unsafe { convert_to_rust_vec(intents_whitelist)? })
+    } else {
+        None
+    };
+    let opt_blacklist: Option<Vec<_>> = if !intents_blacklist.is_null() {
+        Some(unsafe { convert_to_rust_vec(intents_blacklist)? })
+    } else {
+        None
+    };
+    let result = nlu_engine.parse_with_slot_validation(&input, opt_whitelist, opt_blacklist)?;
+
+    point_to_string(result_json, serde_json::to_string(&result)?)
+}
+
 fn run_get_slots_into_json(
     client: *const CSnipsNluEngine,
     input: *const libc::c_char,
@@ -402,6 +760,107 @@ fn run_get_intents_into_json(
     point_to_string(result_json, serde_json::to_string(&intents)?)
 }
 
+fn run_extract_entities_into_json(
+    client: *const CSnipsNluEngine,
+    input: *const libc::c_char,
+    scope: *const CStringArray,
+    entities_alternatives: libc::c_uint,
+    result_json: *mut *const libc::c_char,
+) -> Result<()> {
+    let input = create_rust_string_from!(input);
+    let nlu_engine = get_nlu_engine!(client);
+
+    let opt_scope: Option<Vec<_>> = if !scope.is_null() {
+        Some(unsafe { convert_to_rust_vec(scope)? })
+    } else {
+        None
+    };
+    let entities = nlu_engine.extract_entities_with_alternatives(
+        &input,
+        opt_scope,
+        entities_alternatives as usize,
+    )?;
+
+    point_to_string(result_json, serde_json::to_string(&entities)?)
+}
+
+fn create_injector(
+    engine_dir: *const libc::c_char,
+    injector: *mut *const CNluInjector,
+) -> Result<()> {
+    let engine_dir = create_rust_string_from!(engine_dir);
+    let nlu_injector = NluInjector::new(PathBuf::from(engine_dir));
+    let raw_pointer = CNluInjector(Mutex::new(Some(nlu_injector))).into_raw_pointer();
+    unsafe { *injector = raw_pointer };
+
+    Ok(())
+}
+
+fn injector_add_value(
+    injector: *const CNluInjector,
+    entity: *const libc::c_char,
+    value: *const libc::c_char,
+) -> Result<()> {
+    let entity = create_rust_string_from!(entity);
+    let value = create_rust_string_from!(value);
+    let mut nlu_injector = get_nlu_injector!(injector);
+    let updated_injector = nlu_injector
+        .take()
+        .ok_or_else(|| format_err!("Injector has already been run"))?
+        .add_value(entity, value);
+    *nlu_injector = Some(updated_injector);
+
+    Ok(())
+}
+
+fn injector_set_from_vanilla(
+    injector: *const CNluInjector,
+    from_vanilla: libc::c_uchar,
+) -> Result<()> {
+    let mut nlu_injector = get_nlu_injector!(injector);
+    let updated_injector = nlu_injector
+        .take()
+        .ok_or_else(|| format_err!("Injector has already been run"))?
+        .from_vanilla(from_vanilla != 0);
+    *nlu_injector = Some(updated_injector);
+
+    Ok(())
+}
+
+fn run_injection(
+    injector: *const CNluInjector,
+    error_kind: *mut SNIPS_INJECTION_ERROR_KIND,
+) -> Result<()> {
+    let set_error_kind = |kind: SNIPS_INJECTION_ERROR_KIND| {
+        if !error_kind.is_null() {
+            unsafe { *error_kind = kind };
+        }
+    };
+    set_error_kind(SNIPS_INJECTION_ERROR_KIND::SNIPS_INJECTION_ERROR_KIND_INVALID_INJECTOR);
+    let nlu_injector = get_nlu_injector!(injector)
+        .take()
+        .ok_or_else(|| format_err!("Injector has already been run"))?;
+    match nlu_injector.inject() {
+        Ok(()) => {
+            set_error_kind(SNIPS_INJECTION_ERROR_KIND::SNIPS_INJECTION_ERROR_KIND_NONE);
+            Ok(())
+        }
+        Err(error) => {
+            set_error_kind(error.kind().into());
+            Err(error.into())
+        }
+    }
+}
+
+fn get_last_error_code(code: *mut SNIPS_NLU_ERROR_CODE) -> Result<()> {
+    if code.is_null() {
+        return Err(format_err!("Error code pointer is null"));
+    }
+    let last_error_code = LAST_ERROR_CODE.with(|last_error_code| last_error_code.get());
+    unsafe { *code = last_error_code };
+    Ok(())
+}
+
 fn get_model_version(version: *mut *const libc::c_char) -> Result<()> {
     point_to_string(version, snips_nlu_lib::MODEL_VERSION.to_string())
 }
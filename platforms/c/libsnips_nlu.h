@@ -46,6 +46,79 @@ typedef enum {
   SNIPS_GRAIN_SECOND = 7,
 } SNIPS_GRAIN;
 
+/**
+ * Kind of error which occurred during an injection
+ */
+typedef enum {
+  /**
+   * No error occurred
+   */
+  SNIPS_INJECTION_ERROR_KIND_NONE = 0,
+  /**
+   * One of the entities cannot be injected, either because it is unknown or because it is not
+   * a gazetteer entity
+   */
+  SNIPS_INJECTION_ERROR_KIND_ENTITY_NOT_INJECTABLE = 1,
+  /**
+   * The injection failed while loading or dumping the entity parsers
+   */
+  SNIPS_INJECTION_ERROR_KIND_INTERNAL = 2,
+  /**
+   * The injector could not be used, e.g. because it has already been run
+   */
+  SNIPS_INJECTION_ERROR_KIND_INVALID_INJECTOR = 3,
+} SNIPS_INJECTION_ERROR_KIND;
+
+/**
+ * Code of the last error which occurred, these codes are stable across versions
+ */
+typedef enum {
+  /**
+   * No error occurred
+   */
+  SNIPS_NLU_ERROR_CODE_NONE = 0,
+  /**
+   * Any error which does not fit in the other categories
+   */
+  SNIPS_NLU_ERROR_CODE_INTERNAL = 1,
+  /**
+   * The model files cannot be read or deserialized
+   */
+  SNIPS_NLU_ERROR_CODE_MODEL_LOAD = 2,
+  /**
+   * The model was trained with an incompatible version
+   */
+  SNIPS_NLU_ERROR_CODE_WRONG_MODEL_VERSION = 3,
+  /**
+   * The model could be read but contains invalid values
+   */
+  SNIPS_NLU_ERROR_CODE_INVALID_MODEL = 4,
+  /**
+   * An unknown intent was used, for instance in an intents filter
+   */
+  SNIPS_NLU_ERROR_CODE_UNKNOWN_INTENT = 5,
+  /**
+   * An unknown slot was used
+   */
+  SNIPS_NLU_ERROR_CODE_UNKNOWN_SLOT = 6,
+  /**
+   * The builtin or custom entity parser failed
+   */
+  SNIPS_NLU_ERROR_CODE_ENTITY_PARSER = 7,
+  /**
+   * The injection of new entity values failed
+   */
+  SNIPS_NLU_ERROR_CODE_INJECTION = 8,
+  /**
+   * The input cannot be processed, e.g. because it is too long
+   */
+  SNIPS_NLU_ERROR_CODE_INVALID_INPUT = 9,
+  /**
+   * An unknown entity was used, for instance in an entities scope
+   */
+  SNIPS_NLU_ERROR_CODE_UNKNOWN_ENTITY = 10,
+} SNIPS_NLU_ERROR_CODE;
+
 /**
  * Enum describing the precision of a resolved value
  */
@@ -140,6 +213,8 @@ typedef enum {
   SNIPS_SLOT_VALUE_TYPE_REGION = 15,
 } SNIPS_SLOT_VALUE_TYPE;
 
+typedef struct CNluInjector CNluInjector;
+
 typedef struct CSnipsNluEngine CSnipsNluEngine;
 
 /**
@@ -459,8 +534,35 @@ SNIPS_RESULT snips_nlu_engine_destroy_string(char *string);
  */
 SNIPS_RESULT snips_nlu_engine_get_last_error(const char **error);
 
+/**
+ * Retrieves the code of the last error that happened in this thread, whose message can be
+ * retrieved with `snips_nlu_engine_get_last_error`, or `SNIPS_NLU_ERROR_CODE_NONE` if the last
+ * API call succeeded
+ *
+ * The getters and the `destroy` functions leave the code untouched, so it can be retrieved after
+ * the error message has been destroyed.
+ */
+SNIPS_RESULT snips_nlu_engine_get_last_error_code(SNIPS_NLU_ERROR_CODE *code);
+
 SNIPS_RESULT snips_nlu_engine_get_model_version(const char **version);
 
+/**
+ * Extracts the builtin and custom entities of the input, as a JSON list sorted by range
+ *
+ * The scope (if not null) restricts the extraction to the provided builtin entity identifiers
+ * and custom entity names
+ */
+SNIPS_RESULT snips_nlu_engine_run_extract_entities_into_json(const CSnipsNluEngine *client,
+                                                            const char *input,
+                                                            const CStringArray *scope,
+                                                            const char **result_json);
+
+SNIPS_RESULT snips_nlu_engine_run_extract_entities_with_alternatives_into_json(const CSnipsNluEngine *client,
+                                                                              const char *input,
+                                                                              const CStringArray *scope,
+                                                                              unsigned int entities_alternatives,
+                                                                              const char **result_json);
+
 SNIPS_RESULT snips_nlu_engine_run_get_intents(const CSnipsNluEngine *client,
                                               const char *input,
                                               const CIntentClassifierResultArray **result);
@@ -519,4 +621,54 @@ SNIPS_RESULT snips_nlu_engine_run_parse_with_alternatives_into_json(const CSnips
                                                                     unsigned int slots_alternatives,
                                                                     const char **result_json);
 
+/**
+ * Parses the input like `snips_nlu_engine_run_parse_into_json`, but also returns the slots which
+ * are nested in other slots, in the `subSlots` attribute of their parent slot
+ */
+SNIPS_RESULT snips_nlu_engine_run_parse_with_nested_slots_into_json(const CSnipsNluEngine *client,
+                                                                   const char *input,
+                                                                   const CStringArray *intents_whitelist,
+                                                                   const CStringArray *intents_blacklist,
+                                                                   const char **result_json);
+
+/**
+ * Parses the input like `snips_nlu_engine_run_parse_into_json`, but also returns the reason why
+ * the input has been considered out of scope, if it has
+ */
+SNIPS_RESULT snips_nlu_engine_run_parse_with_out_of_scope_reason_into_json(const CSnipsNluEngine *client,
+                                                                          const char *input,
+                                                                          const CStringArray *intents_whitelist,
+                                                                          const CStringArray *intents_blacklist,
+                                                                          const char **result_json);
+
+/**
+ * Parses the input like `snips_nlu_engine_run_parse_into_json`, but also returns the slots which
+ * have been rejected by the slot post processors, and checks the slots against the intent schema
+ */
+SNIPS_RESULT snips_nlu_engine_run_parse_with_slot_validation_into_json(const CSnipsNluEngine *client,
+                                                                      const char *input,
+                                                                      const CStringArray *intents_whitelist,
+                                                                      const CStringArray *intents_blacklist,
+                                                                      const char **result_json);
+
+SNIPS_RESULT snips_nlu_injector_add_value(const CNluInjector *injector,
+                                          const char *entity,
+                                          const char *value);
+
+SNIPS_RESULT snips_nlu_injector_create(const char *engine_dir, const CNluInjector **injector);
+
+SNIPS_RESULT snips_nlu_injector_destroy(CNluInjector *injector);
+
+/**
+ * Runs the injection, the injector cannot be reused afterwards and must be destroyed.
+ *
+ * When the injection fails, `error_kind` (if not null) is set to the kind of the error and the
+ * details can be retrieved with `snips_nlu_engine_get_last_error`
+ */
+SNIPS_RESULT snips_nlu_injector_run(const CNluInjector *injector,
+                                    SNIPS_INJECTION_ERROR_KIND *error_kind);
+
+SNIPS_RESULT snips_nlu_injector_set_from_vanilla(const CNluInjector *injector,
+                                                 unsigned char from_vanilla);
+
 #endif /* LIBSNIPS_NLU_H_ */
@@ -12,6 +12,7 @@ import com.sun.jna.Library
 import com.sun.jna.Memory
 import com.sun.jna.Native
 import com.sun.jna.Pointer
+import com.sun.jna.ptr.IntByReference
 import com.sun.jna.ptr.PointerByReference
 import com.sun.jna.Structure
 import com.sun.jna.toJnaPointer
@@ -52,6 +53,59 @@ class CStringArray(p: Pointer?) : Structure(p), Structure.ByReference {
     } else listOf<String>()
 }
 
+enum class NluInjectionErrorKind(val code: Int) {
+    NONE(0),
+    ENTITY_NOT_INJECTABLE(1),
+    INTERNAL(2),
+    INVALID_INJECTOR(3);
+
+    companion object {
+        @JvmStatic
+        fun fromCode(code: Int) = values().firstOrNull { it.code == code } ?: INTERNAL
+    }
+}
+
+class NluInjectionException(val kind: NluInjectionErrorKind, message: String) : RuntimeException(message)
+
+class NluInjector(engineDir: File) : Closeable {
+
+    companion object {
+        private fun parseError(returnCode: Int, kind: NluInjectionErrorKind = NluInjectionErrorKind.INVALID_INJECTOR) {
+            if (returnCode != 0) {
+                PointerByReference().apply {
+                    LIB.snips_nlu_engine_get_last_error(this)
+                    throw NluInjectionException(kind, value.getString(0).apply {
+                        LIB.snips_nlu_engine_destroy_string(value)
+                    })
+                }
+            }
+        }
+    }
+
+    val injector: Pointer = PointerByReference().apply {
+        parseError(LIB.snips_nlu_injector_create(engineDir.absolutePath.toPointer(), this))
+    }.value
+
+    override fun close() {
+        LIB.snips_nlu_injector_destroy(injector)
+    }
+
+    fun addValue(entity: String, value: String): NluInjector = apply {
+        parseError(LIB.snips_nlu_injector_add_value(injector, entity.toPointer(), value.toPointer()))
+    }
+
+    fun fromVanilla(fromVanilla: Boolean): NluInjector = apply {
+        parseError(LIB.snips_nlu_injector_set_from_vanilla(injector, (if (fromVanilla) 1 else 0).toByte()))
+    }
+
+    fun inject() {
+        IntByReference(NluInjectionErrorKind.NONE.code).let {
+            val returnCode = LIB.snips_nlu_injector_run(injector, it)
+            parseError(returnCode, NluInjectionErrorKind.fromCode(it.value))
+        }
+    }
+}
+
 class NluEngine private constructor(clientBuilder: () -> Pointer) : Closeable {
 
     companion object {
@@ -245,5 +299,10 @@ class NluEngine private constructor(clientBuilder: () -> Pointer) : Closeable {
         fun snips_nlu_engine_destroy_slots(result: CSlots): Int
         fun snips_nlu_engine_destroy_intent_classifier_results(result: CIntentClassifierResultArray): Int
         fun snips_nlu_engine_destroy_string(string: Pointer): Int
+        fun snips_nlu_injector_create(engine_dir: Pointer, pointer: PointerByReference): Int
+        fun snips_nlu_injector_add_value(injector: Pointer, entity: Pointer, value: Pointer): Int
+        fun snips_nlu_injector_set_from_vanilla(injector: Pointer, from_vanilla: Byte): Int
+        fun snips_nlu_injector_run(injector: Pointer, error_kind: IntByReference): Int
+        fun snips_nlu_injector_destroy(injector: Pointer): Int
     }
 }
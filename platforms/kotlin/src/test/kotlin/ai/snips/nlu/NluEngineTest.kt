@@ -3,6 +3,7 @@ package ai.snips.nlu
 import ai.snips.nlu.ontology.SlotValue.CustomValue
 import ai.snips.nlu.ontology.SlotValue.NumberValue
 import com.google.common.truth.Truth.assertThat
+import org.junit.Assert.fail
 import org.junit.Test
 import java.io.File
 
@@ -237,4 +238,44 @@ class NluEngineTest {
             }
         }
     }
+
+    @Test
+    fun injectionWorks() {
+        val engineDir = createTempDir().resolve("nlu_engine_music")
+        File("../../data/tests/models/nlu_engine_music").copyRecursively(engineDir)
+        try {
+            NluInjector(engineDir).use {
+                it.addValue("snips/musicAlbum", "Thisisthebestalbum")
+                        .fromVanilla(true)
+                        .inject()
+            }
+            NluEngine(engineDir).use {
+                it.parse("je souhaiterais écouter l'album thisisthebestalbum").apply {
+                    assertThat(intent.intentName).isEqualTo("adri:PlayMusic")
+                    assertThat(slots.map { it.rawValue }).isEqualTo(listOf("thisisthebestalbum"))
+                }
+            }
+        } finally {
+            engineDir.parentFile.deleteRecursively()
+        }
+    }
+
+    @Test
+    fun injectionOfUnknownEntityFails() {
+        val engineDir = createTempDir().resolve("nlu_engine_music")
+        File("../../data/tests/models/nlu_engine_music").copyRecursively(engineDir)
+        try {
+            NluInjector(engineDir).use {
+                it.addValue("unknown_entity", "foo")
+                try {
+                    it.inject()
+                    fail("injection of an unknown entity should fail")
+                } catch (e: NluInjectionException) {
+                    assertThat(e.kind).isEqualTo(NluInjectionErrorKind.ENTITY_NOT_INJECTABLE)
+                }
+            }
+        } finally {
+            engineDir.parentFile.deleteRecursively()
+        }
+    }
 }
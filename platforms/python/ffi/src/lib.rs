@@ -3,7 +3,9 @@ extern crate libc;
 extern crate snips_nlu_ffi;
 
 use ffi_utils::{CStringArray, SNIPS_RESULT};
-use snips_nlu_ffi::CSnipsNluEngine;
+use snips_nlu_ffi::{
+    CNluInjector, CSnipsNluEngine, SNIPS_INJECTION_ERROR_KIND, SNIPS_NLU_ERROR_CODE,
+};
 
 #[doc(hidden)]
 #[macro_export]
@@ -23,10 +25,21 @@ export_c_symbol!(ffi_snips_nlu_engine_create_from_dir, fn snips_nlu_engine_creat
 export_c_symbol!(ffi_snips_nlu_engine_create_from_zip, fn snips_nlu_engine_create_from_zip(zip: *const libc::c_uchar, zip_size: libc::c_uint, client: *mut *const CSnipsNluEngine) -> SNIPS_RESULT);
 export_c_symbol!(ffi_snips_nlu_engine_run_parse_into_json, fn snips_nlu_engine_run_parse_into_json(client: *const CSnipsNluEngine, input: *const libc::c_char, intents_whitelist: *const CStringArray, intents_blacklist: *const CStringArray, result_json: *mut *const libc::c_char) -> SNIPS_RESULT);
 export_c_symbol!(ffi_snips_nlu_engine_run_parse_with_alternatives_into_json, fn snips_nlu_engine_run_parse_with_alternatives_into_json(client: *const CSnipsNluEngine, input: *const libc::c_char, intents_whitelist: *const CStringArray, intents_blacklist: *const CStringArray, intents_alternatives: libc::c_uint, slots_alternatives: libc::c_uint, result_json: *mut *const libc::c_char) -> SNIPS_RESULT);
+export_c_symbol!(ffi_snips_nlu_engine_run_parse_with_nested_slots_into_json, fn snips_nlu_engine_run_parse_with_nested_slots_into_json(client: *const CSnipsNluEngine, input: *const libc::c_char, intents_whitelist: *const CStringArray, intents_blacklist: *const CStringArray, result_json: *mut *const libc::c_char) -> SNIPS_RESULT);
+export_c_symbol!(ffi_snips_nlu_engine_run_parse_with_out_of_scope_reason_into_json, fn snips_nlu_engine_run_parse_with_out_of_scope_reason_into_json(client: *const CSnipsNluEngine, input: *const libc::c_char, intents_whitelist: *const CStringArray, intents_blacklist: *const CStringArray, result_json: *mut *const libc::c_char) -> SNIPS_RESULT);
+export_c_symbol!(ffi_snips_nlu_engine_run_parse_with_slot_validation_into_json, fn snips_nlu_engine_run_parse_with_slot_validation_into_json(client: *const CSnipsNluEngine, input: *const libc::c_char, intents_whitelist: *const CStringArray, intents_blacklist: *const CStringArray, result_json: *mut *const libc::c_char) -> SNIPS_RESULT);
 export_c_symbol!(ffi_snips_nlu_engine_run_get_slots_into_json, fn snips_nlu_engine_run_get_slots_into_json(client: *const CSnipsNluEngine, input: *const libc::c_char, intent: *const libc::c_char, result_json: *mut *const libc::c_char) -> SNIPS_RESULT);
 export_c_symbol!(ffi_snips_nlu_engine_run_get_slots_with_alternatives_into_json, fn snips_nlu_engine_run_get_slots_with_alternatives_into_json(client: *const CSnipsNluEngine, input: *const libc::c_char, intent: *const libc::c_char, slots_alternatives: libc::c_uint, result_json: *mut *const libc::c_char) -> SNIPS_RESULT);
 export_c_symbol!(ffi_snips_nlu_engine_run_get_intents_into_json, fn snips_nlu_engine_run_get_intents_into_json(client: *const CSnipsNluEngine, input: *const libc::c_char, result_json: *mut *const libc::c_char) -> SNIPS_RESULT);
+export_c_symbol!(ffi_snips_nlu_engine_run_extract_entities_into_json, fn snips_nlu_engine_run_extract_entities_into_json(client: *const CSnipsNluEngine, input: *const libc::c_char, scope: *const CStringArray, result_json: *mut *const libc::c_char) -> SNIPS_RESULT);
+export_c_symbol!(ffi_snips_nlu_engine_run_extract_entities_with_alternatives_into_json, fn snips_nlu_engine_run_extract_entities_with_alternatives_into_json(client: *const CSnipsNluEngine, input: *const libc::c_char, scope: *const CStringArray, entities_alternatives: libc::c_uint, result_json: *mut *const libc::c_char) -> SNIPS_RESULT);
 export_c_symbol!(ffi_snips_nlu_engine_get_last_error, fn snips_nlu_engine_get_last_error(error: *mut *const libc::c_char) -> SNIPS_RESULT);
+export_c_symbol!(ffi_snips_nlu_engine_get_last_error_code, fn snips_nlu_engine_get_last_error_code(code: *mut SNIPS_NLU_ERROR_CODE) -> SNIPS_RESULT);
 export_c_symbol!(ffi_snips_nlu_engine_destroy_string, fn snips_nlu_engine_destroy_string(string: *mut libc::c_char) -> SNIPS_RESULT);
 export_c_symbol!(ffi_snips_nlu_engine_destroy_client, fn snips_nlu_engine_destroy_client(client: *mut CSnipsNluEngine) -> SNIPS_RESULT);
 export_c_symbol!(ffi_snips_nlu_engine_get_model_version, fn snips_nlu_engine_get_model_version(version: *mut *const libc::c_char) -> SNIPS_RESULT);
+export_c_symbol!(ffi_snips_nlu_injector_create, fn snips_nlu_injector_create(engine_dir: *const libc::c_char, injector: *mut *const CNluInjector) -> SNIPS_RESULT);
+export_c_symbol!(ffi_snips_nlu_injector_add_value, fn snips_nlu_injector_add_value(injector: *const CNluInjector, entity: *const libc::c_char, value: *const libc::c_char) -> SNIPS_RESULT);
+export_c_symbol!(ffi_snips_nlu_injector_set_from_vanilla, fn snips_nlu_injector_set_from_vanilla(injector: *const CNluInjector, from_vanilla: libc::c_uchar) -> SNIPS_RESULT);
+export_c_symbol!(ffi_snips_nlu_injector_run, fn snips_nlu_injector_run(injector: *const CNluInjector, error_kind: *mut SNIPS_INJECTION_ERROR_KIND) -> SNIPS_RESULT);
+export_c_symbol!(ffi_snips_nlu_injector_destroy, fn snips_nlu_injector_destroy(injector: *mut CNluInjector) -> SNIPS_RESULT);
@@ -1,3 +1,4 @@
 from __future__ import absolute_import
 
+from snips_nlu_rust.injection import NLUInjectionError, NLUInjector
 from snips_nlu_rust.nlu_engine import NLUEngine
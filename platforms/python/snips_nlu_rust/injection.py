@@ -0,0 +1,102 @@
+# coding=utf-8
+from __future__ import (absolute_import, division, print_function,
+                        unicode_literals)
+
+from builtins import object, str
+from ctypes import byref, c_int, c_ubyte, c_void_p
+from pathlib import Path
+
+from snips_nlu_rust.utils import check_ffi_error, lib
+
+INJECTION_ERROR_KIND_NONE = 0
+INJECTION_ERROR_KIND_ENTITY_NOT_INJECTABLE = 1
+INJECTION_ERROR_KIND_INTERNAL = 2
+INJECTION_ERROR_KIND_INVALID_INJECTOR = 3
+
+
+class NLUInjectionError(ValueError):
+    """Error raised when an injection fails
+
+    Attributes:
+        kind (int): one of the ``INJECTION_ERROR_KIND_*`` constants defined
+            in this module
+    """
+
+    def __init__(self, message, kind):
+        super(NLUInjectionError, self).__init__(message)
+        self.kind = kind
+
+
+class NLUInjector(object):
+    """Python wrapper of the Rust NLU injector
+
+    The injector adds new entity values to the gazetteer entity parsers of
+    a persisted NLU engine, so that the engine loaded afterwards from the same
+    directory is able to extract them.
+
+    Examples:
+
+        >>> from snips_nlu_rust import NLUEngine, NLUInjector
+        >>> injector = NLUInjector(engine_dir="/path/to/nlu_engine")
+        >>> injector.add_value("contact", "Jane Doe")
+        >>> injector.inject()
+        >>> engine = NLUEngine(engine_dir="/path/to/nlu_engine")
+    """
+
+    def __init__(self, engine_dir):
+        self._injector = c_void_p()
+        engine_dir = Path(engine_dir)
+        exit_code = lib.ffi_snips_nlu_injector_create(
+            str(engine_dir).encode("utf8"), byref(self._injector))
+        _check_injection_error(
+            exit_code, INJECTION_ERROR_KIND_INVALID_INJECTOR,
+            "Something went wrong when creating the injector")
+
+    def add_value(self, entity, value):
+        """Adds a value to inject for the provided entity
+
+        Args:
+            entity (str): name of a custom entity or of a builtin gazetteer
+                entity, like "snips/musicArtist"
+            value (str): value to inject
+        """
+        exit_code = lib.ffi_snips_nlu_injector_add_value(
+            self._injector, entity.encode("utf8"), value.encode("utf8"))
+        _check_injection_error(
+            exit_code, INJECTION_ERROR_KIND_INVALID_INJECTOR,
+            "Something went wrong when adding value '%s' for entity '%s'"
+            % (value, entity))
+
+    def from_vanilla(self, from_vanilla):
+        """Whether previously injected values must be discarded before
+        injecting the new ones"""
+        exit_code = lib.ffi_snips_nlu_injector_set_from_vanilla(
+            self._injector, c_ubyte(1 if from_vanilla else 0))
+        _check_injection_error(
+            exit_code, INJECTION_ERROR_KIND_INVALID_INJECTOR,
+            "Something went wrong when setting the from_vanilla flag")
+
+    def inject(self):
+        """Runs the injection and updates the engine directory in place
+
+        The injector cannot be reused once this method has been called.
+
+        Raises:
+            NLUInjectionError: when the injection fails
+        """
+        error_kind = c_int(INJECTION_ERROR_KIND_NONE)
+        exit_code = lib.ffi_snips_nlu_injector_run(
+            self._injector, byref(error_kind))
+        _check_injection_error(exit_code, error_kind.value,
+                               "Something went wrong during injection")
+
+    def __del__(self):
+        if self._injector is not None and lib is not None:
+            lib.ffi_snips_nlu_injector_destroy(self._injector)
+
+
+def _check_injection_error(exit_code, error_kind, error_context_msg):
+    try:
+        check_ffi_error(exit_code, error_context_msg)
+    except ValueError as error:
+        raise NLUInjectionError(str(error), error_kind)
@@ -2,10 +2,14 @@
 from __future__ import unicode_literals
 
 import unittest
+from ctypes import byref, c_int
 
 from snips_nlu_rust import NLUEngine
 from snips_nlu_rust.tests.utils import (
     BEVERAGE_ENGINE_DIR, BEVERAGE_ENGINE_ZIP_BYTES, GAME_ENGINE_DIR)
+from snips_nlu_rust.utils import lib
+
+SNIPS_NLU_ERROR_CODE_UNKNOWN_INTENT = 5
 
 
 class TestNLUEngineWrapper(unittest.TestCase):
@@ -191,6 +195,22 @@ class TestNLUEngineWrapper(unittest.TestCase):
                 "Make me two cups of coffee please", intent="my_intent")
         self.assertTrue("Unknown intent" in str(cm.exception))
 
+    def test_error_code_should_survive_error_message_cleanup(self):
+        # Given
+        engine = NLUEngine(engine_bytes=BEVERAGE_ENGINE_ZIP_BYTES)
+
+        # When
+        with self.assertRaises(ValueError):
+            engine.get_slots(
+                "Make me two cups of coffee please", intent="my_intent")
+        error_code = c_int()
+        exit_code = lib.ffi_snips_nlu_engine_get_last_error_code(
+            byref(error_code))
+
+        # Then
+        self.assertEqual(0, exit_code)
+        self.assertEqual(SNIPS_NLU_ERROR_CODE_UNKNOWN_INTENT, error_code.value)
+
     def test_should_get_intents(self):
         # Given
         engine = NLUEngine(engine_bytes=BEVERAGE_ENGINE_ZIP_BYTES)
@@ -0,0 +1,69 @@
+# coding=utf-8
+from __future__ import unicode_literals
+
+import shutil
+import tempfile
+import unittest
+from pathlib import Path
+
+from snips_nlu_rust import NLUEngine, NLUInjectionError, NLUInjector
+from snips_nlu_rust.injection import (
+    INJECTION_ERROR_KIND_ENTITY_NOT_INJECTABLE,
+    INJECTION_ERROR_KIND_INVALID_INJECTOR)
+from snips_nlu_rust.tests.utils import MUSIC_ENGINE_DIR
+
+
+class TestNLUInjectorWrapper(unittest.TestCase):
+    def setUp(self):
+        self.tmp_dir = tempfile.mkdtemp()
+        self.engine_dir = Path(self.tmp_dir) / "nlu_engine_music"
+        shutil.copytree(MUSIC_ENGINE_DIR, str(self.engine_dir))
+
+    def tearDown(self):
+        shutil.rmtree(self.tmp_dir)
+
+    def test_should_inject_values(self):
+        # Given
+        injector = NLUInjector(engine_dir=self.engine_dir)
+        injector.add_value("snips/musicAlbum", "Thisisthebestalbum")
+        injector.from_vanilla(True)
+
+        # When
+        injector.inject()
+        engine = NLUEngine(engine_dir=self.engine_dir)
+        res = engine.parse(
+            "je souhaiterais écouter l'album thisisthebestalbum")
+
+        # Then
+        self.assertEqual("adri:PlayMusic", res["intent"]["intentName"])
+        self.assertEqual(1, len(res["slots"]))
+        self.assertEqual("Thisisthebestalbum",
+                         res["slots"][0]["value"]["value"])
+
+    def test_inject_should_fail_with_unknown_entity(self):
+        # Given
+        injector = NLUInjector(engine_dir=self.engine_dir)
+        injector.add_value("unknown_entity", "foo")
+
+        # When
+        with self.assertRaises(NLUInjectionError) as cm:
+            injector.inject()
+
+        # Then
+        self.assertEqual(INJECTION_ERROR_KIND_ENTITY_NOT_INJECTABLE,
+                         cm.exception.kind)
+        self.assertTrue("Unknown entity" in str(cm.exception))
+
+    def test_injector_should_not_be_reusable(self):
+        # Given
+        injector = NLUInjector(engine_dir=self.engine_dir)
+        injector.add_value("snips/musicAlbum", "Thisisthebestalbum")
+        injector.inject()
+
+        # When
+        with self.assertRaises(NLUInjectionError) as cm:
+            injector.inject()
+
+        # Then
+        self.assertEqual(INJECTION_ERROR_KIND_INVALID_INJECTOR,
+                         cm.exception.kind)
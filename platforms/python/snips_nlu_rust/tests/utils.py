@@ -14,3 +14,5 @@ BEVERAGE_ENGINE_ZIP_PATH = os.path.join(TEST_DATA_PATH, "models",
 
 with io.open(BEVERAGE_ENGINE_ZIP_PATH, mode='rb') as f:
     BEVERAGE_ENGINE_ZIP_BYTES = bytearray(f.read())
+
+MUSIC_ENGINE_DIR = os.path.join(TEST_DATA_PATH, "models", "nlu_engine_music")
@@ -26,7 +26,7 @@ class CStringArray(Structure):
 def check_ffi_error(exit_code, error_context_msg):
     if exit_code != 0:
         with string_pointer(c_char_p()) as ptr:
-            if lib.snips_nlu_engine_get_last_error(byref(ptr)) == 0:
+            if lib.ffi_snips_nlu_engine_get_last_error(byref(ptr)) == 0:
                 ffi_error_message = string_at(ptr).decode("utf8")
             else:
                 ffi_error_message = "see stderr"
@@ -0,0 +1,139 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::Path;
+
+use fs2::FileExt;
+
+const LOCK_FILE_NAME: &str = ".nlu_engine.lock";
+
+/// Advisory lock on a trained engine directory
+///
+/// Writers, like the `NluInjector`, take an exclusive lock while they modify the directory, and
+/// readers take a shared lock while they load it. Only the writers create the lock file, so that
+/// loading an engine never modifies its directory. The lock is released when dropped.
+pub struct EngineDirLock {
+    file: File,
+}
+
+impl EngineDirLock {
+    /// Blocks until no other process reads or writes the engine directory
+    pub fn exclusive<P: AsRef<Path>>(engine_dir: P) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(engine_dir.as_ref().join(LOCK_FILE_NAME))?;
+        file.lock_exclusive()?;
+        Ok(Self { file })
+    }
+
+    /// Blocks until no other process writes in the engine directory
+    ///
+    /// `None` is returned when the lock file does not exist, which means that no injection has
+    /// ever been run on the engine directory.
+    pub fn shared<P: AsRef<Path>>(engine_dir: P) -> io::Result<Option<Self>> {
+        let file = match File::open(engine_dir.as_ref().join(LOCK_FILE_NAME)) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        file.lock_shared()?;
+        Ok(Some(Self { file }))
+    }
+}
+
+impl Drop for EngineDirLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    use fs_extra::dir;
+    use tempfile::tempdir;
+
+    use crate::SnipsNluEngine;
+
+    #[test]
+    fn test_exclusive_lock_prevents_shared_lock() {
+        // Given
+        let engine_dir = tempdir().unwrap();
+        let exclusive_lock = EngineDirLock::exclusive(engine_dir.as_ref()).unwrap();
+        let other_file = File::open(engine_dir.as_ref().join(LOCK_FILE_NAME)).unwrap();
+
+        // When
+        let locked_result = other_file.try_lock_shared();
+        drop(exclusive_lock);
+        let unlocked_result = other_file.try_lock_shared();
+
+        // Then
+        assert!(locked_result.is_err());
+        assert!(unlocked_result.is_ok());
+    }
+
+    #[test]
+    fn test_shared_locks_do_not_conflict() {
+        // Given
+        let engine_dir = tempdir().unwrap();
+        File::create(engine_dir.as_ref().join(LOCK_FILE_NAME)).unwrap();
+        let _shared_lock = EngineDirLock::shared(engine_dir.as_ref()).unwrap();
+        let other_file = File::open(engine_dir.as_ref().join(LOCK_FILE_NAME)).unwrap();
+
+        // When
+        let shared_result = other_file.try_lock_shared();
+        let exclusive_result = other_file.try_lock_exclusive();
+
+        // Then
+        assert!(shared_result.is_ok());
+        assert!(exclusive_result.is_err());
+    }
+
+    #[test]
+    fn test_shared_lock_does_not_create_lock_file() {
+        // Given
+        let engine_dir = tempdir().unwrap();
+
+        // When
+        let shared_lock = EngineDirLock::shared(engine_dir.as_ref()).unwrap();
+
+        // Then
+        assert!(shared_lock.is_none());
+        assert!(!engine_dir.as_ref().join(LOCK_FILE_NAME).exists());
+    }
+
+    #[test]
+    fn test_loading_waits_for_exclusive_lock() {
+        // Given
+        let path = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage");
+        let tdir = tempdir().unwrap();
+        dir::copy(&path, tdir.as_ref(), &dir::CopyOptions::new()).unwrap();
+        let engine_dir = tdir.as_ref().join("nlu_engine_beverage");
+        let exclusive_lock = EngineDirLock::exclusive(&engine_dir).unwrap();
+
+        // When
+        let (sender, receiver) = mpsc::channel();
+        let loading_dir = engine_dir.clone();
+        let loading_thread = thread::spawn(move || {
+            let loading_result = SnipsNluEngine::from_path(loading_dir);
+            sender.send(loading_result.is_ok()).unwrap();
+        });
+        let locked_result = receiver.recv_timeout(Duration::from_millis(500));
+        drop(exclusive_lock);
+        let unlocked_result = receiver.recv_timeout(Duration::from_secs(60));
+        loading_thread.join().unwrap();
+
+        // Then
+        assert!(locked_result.is_err());
+        assert_eq!(Ok(true), unlocked_result);
+    }
+}
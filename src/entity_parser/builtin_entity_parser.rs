@@ -1,11 +1,11 @@
 use std::path::Path;
-use std::sync::Mutex;
 
+use failure::ResultExt;
 use log::info;
 use snips_nlu_ontology::{BuiltinEntity, BuiltinEntityKind};
 use snips_nlu_parsers::BuiltinEntityParser as _BuiltinEntityParser;
 
-use super::utils::Cache;
+use super::utils::{Cache, CacheStatistics};
 use crate::errors::*;
 
 pub trait BuiltinEntityParser: Send + Sync {
@@ -16,11 +16,16 @@ pub trait BuiltinEntityParser: Send + Sync {
         use_cache: bool,
         max_alternative_resolved_values: usize,
     ) -> Result<Vec<BuiltinEntity>>;
+
+    /// Returns the statistics of the underlying cache, if any
+    fn cache_statistics(&self) -> Option<CacheStatistics> {
+        None
+    }
 }
 
 pub struct CachingBuiltinEntityParser {
     parser: _BuiltinEntityParser,
-    cache: Mutex<Cache<CacheKey, Vec<BuiltinEntity>>>,
+    cache: Cache<CacheKey, Vec<BuiltinEntity>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -39,29 +44,33 @@ impl BuiltinEntityParser for CachingBuiltinEntityParser {
         max_alternative_resolved_values: usize,
     ) -> Result<Vec<BuiltinEntity>> {
         let lowercased_sentence = sentence.to_lowercase();
-        if !use_cache {
-            return self.parser.extract_entities(
+        let entities = if !use_cache {
+            self.parser.extract_entities(
                 &lowercased_sentence,
                 filter_entity_kinds,
                 max_alternative_resolved_values,
-            );
-        }
-        let cache_key = CacheKey {
-            input: lowercased_sentence,
-            kinds: filter_entity_kinds.map(|entity_kinds| entity_kinds.to_vec()),
-            max_alternative_resolved_values,
-        };
-
-        self.cache
-            .lock()
-            .unwrap()
-            .try_cache(&cache_key, |cache_key| {
+            )
+        } else {
+            let cache_key = CacheKey {
+                input: lowercased_sentence,
+                kinds: filter_entity_kinds.map(|entity_kinds| entity_kinds.to_vec()),
+                max_alternative_resolved_values,
+            };
+            self.cache.try_cache(&cache_key, |cache_key| {
                 self.parser.extract_entities(
                     &cache_key.input,
                     filter_entity_kinds,
                     max_alternative_resolved_values,
                 )
             })
+        };
+        Ok(entities.with_context(|_| {
+            SnipsNluError::EntityParser("cannot extract builtin entities".to_string())
+        })?)
+    }
+
+    fn cache_statistics(&self) -> Option<CacheStatistics> {
+        Some(self.cache.statistics())
     }
 }
 
@@ -69,7 +78,7 @@ impl CachingBuiltinEntityParser {
     pub fn from_path<P: AsRef<Path>>(path: P, cache_capacity: usize) -> Result<Self> {
         info!("Loading builtin entity parser ({:?}) ...", path.as_ref());
         let parser = _BuiltinEntityParser::from_path(path)?;
-        let cache = Mutex::new(Cache::new(cache_capacity));
+        let cache = Cache::new(cache_capacity);
         info!("Builtin entity parser loaded");
         Ok(Self { parser, cache })
     }
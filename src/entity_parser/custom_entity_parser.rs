@@ -1,8 +1,9 @@
+use std::collections::HashMap;
 use std::fs::File;
+use std::ops::Range;
 use std::path::Path;
 use std::result::Result as StdResult;
 use std::str::FromStr;
-use std::sync::Mutex;
 
 use failure::ResultExt;
 use itertools::Itertools;
@@ -12,14 +13,39 @@ use serde::{Deserialize, Deserializer};
 use snips_nlu_ontology::Language;
 use snips_nlu_parsers::{GazetteerEntityMatch, GazetteerParser};
 use snips_nlu_utils::language::Language as NluUtilsLanguage;
+use snips_nlu_utils::range::ranges_overlap;
 use snips_nlu_utils::token::*;
 
-use crate::entity_parser::utils::Cache;
+use crate::entity_parser::fuzzy_matching::{FuzzyEntityMatcher, FuzzyMatchingConfig};
+use crate::entity_parser::regex_matching::{RegexEntityConfig, RegexEntityMatcher};
+use crate::entity_parser::utils::{Cache, CacheStatistics};
 use crate::errors::*;
 use crate::language::FromLanguage;
-use crate::utils::EntityName;
+use crate::utils::{deduplicate_overlapping_items, EntityName};
 
-pub type CustomEntity = GazetteerEntityMatch<String>;
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomEntity {
+    pub value: String,
+    pub resolved_value: String,
+    pub alternative_resolved_values: Vec<String>,
+    pub range: Range<usize>,
+    pub entity_identifier: String,
+    /// Similarity score between 0 and 1 of fuzzy matches, exact matches have no score
+    pub match_score: Option<f32>,
+}
+
+impl From<GazetteerEntityMatch<String>> for CustomEntity {
+    fn from(entity_match: GazetteerEntityMatch<String>) -> Self {
+        Self {
+            value: entity_match.value,
+            resolved_value: entity_match.resolved_value,
+            alternative_resolved_values: entity_match.alternative_resolved_values,
+            range: entity_match.range,
+            entity_identifier: entity_match.entity_identifier,
+            match_score: None,
+        }
+    }
+}
 
 pub trait CustomEntityParser: Send + Sync {
     fn extract_entities(
@@ -28,6 +54,17 @@ pub trait CustomEntityParser: Send + Sync {
         filter_entity_kinds: Option<&[String]>,
         max_alternative_resolved_values: usize,
     ) -> Result<Vec<CustomEntity>>;
+
+    /// Returns the statistics of the underlying cache, if any
+    fn cache_statistics(&self) -> Option<CacheStatistics> {
+        None
+    }
+
+    /// Values of the custom entities which can be listed, used to correct the spelling of the
+    /// inputs
+    fn entity_values(&self) -> Vec<String> {
+        vec![]
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -55,7 +92,9 @@ impl<'de> Deserialize<'de> for CustomEntityParserUsage {
 pub struct CachingCustomEntityParser {
     language: NluUtilsLanguage,
     parser: GazetteerParser<String>,
-    cache: Mutex<Cache<CacheKey, Vec<CustomEntity>>>,
+    fuzzy_matchers: Vec<FuzzyEntityMatcher>,
+    regex_matchers: Vec<RegexEntityMatcher>,
+    cache: Cache<CacheKey, Vec<CustomEntity>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -79,9 +118,8 @@ impl CustomEntityParser for CachingCustomEntityParser {
             max_alternative_resolved_values,
         };
 
-        self.cache
-            .lock()
-            .unwrap()
+        let entities = self
+            .cache
             .try_cache(&cache_key, |cache_key| {
                 self._extract_entities(
                     &cache_key.input,
@@ -89,6 +127,25 @@ impl CustomEntityParser for CachingCustomEntityParser {
                     max_alternative_resolved_values,
                 )
             })
+            .with_context(|_| {
+                SnipsNluError::EntityParser("cannot extract custom entities".to_string())
+            })?;
+        Ok(self.add_regex_entities(sentence, filter_entity_kinds, entities))
+    }
+
+    fn cache_statistics(&self) -> Option<CacheStatistics> {
+        Some(self.cache.statistics())
+    }
+
+    /// Only the values of the fuzzy matched entities can be listed, as the gazetteer parser does
+    /// not expose its values
+    fn entity_values(&self) -> Vec<String> {
+        self.fuzzy_matchers
+            .iter()
+            .flat_map(|fuzzy_matcher| fuzzy_matcher.resolved_values())
+            .unique()
+            .map(|value| value.to_string())
+            .collect()
     }
 }
 
@@ -101,8 +158,8 @@ impl CachingCustomEntityParser {
     ) -> Result<Vec<CustomEntity>> {
         let tokens = tokenize(sentence, self.language);
         let shifts = compute_char_shifts(&tokens);
-        let cleaned_input = tokens.into_iter().map(|token| token.value).join(" ");
-        Ok(self
+        let cleaned_input = tokens.iter().map(|token| &*token.value).join(" ");
+        let exact_entities: Vec<CustomEntity> = self
             .parser
             .extract_entities(
                 &cleaned_input,
@@ -116,12 +173,84 @@ impl CachingCustomEntityParser {
                 let remapped_range_start = (range_start as i32 - shifts[range_start]) as usize;
                 let remapped_range_end = (range_end as i32 - shifts[range_end - 1]) as usize;
                 entity_match.range = remapped_range_start..remapped_range_end;
-                entity_match
+                entity_match.into()
             })
-            .collect())
+            .collect();
+
+        let fuzzy_entities: Vec<CustomEntity> = self
+            .fuzzy_matchers
+            .iter()
+            .filter(|matcher| is_in_scope(filter_entity_kinds, matcher.entity()))
+            .flat_map(|matcher| {
+                matcher.extract_entities(sentence, &tokens, max_alternative_resolved_values)
+            })
+            .collect();
+        if fuzzy_entities.is_empty() {
+            return Ok(exact_entities);
+        }
+        Ok(merge_overlapping_entities(exact_entities, fuzzy_entities))
+    }
+
+    /// Adds the regex entities to the entities extracted from the lowercased sentence
+    ///
+    /// Regex entities are matched on the sentence as provided rather than on its lowercased
+    /// version, which is the one cached, so that their resolved values keep the case of the input.
+    fn add_regex_entities(
+        &self,
+        sentence: &str,
+        filter_entity_kinds: Option<&[String]>,
+        entities: Vec<CustomEntity>,
+    ) -> Vec<CustomEntity> {
+        let regex_matchers: Vec<&RegexEntityMatcher> = self
+            .regex_matchers
+            .iter()
+            .filter(|matcher| is_in_scope(filter_entity_kinds, matcher.entity()))
+            .collect();
+        if regex_matchers.is_empty() {
+            return entities;
+        }
+        let tokens = tokenize(sentence, self.language);
+        let regex_entities: Vec<CustomEntity> = regex_matchers
+            .into_iter()
+            .flat_map(|matcher| matcher.extract_entities(sentence, &tokens))
+            .collect();
+        if regex_entities.is_empty() {
+            return entities;
+        }
+        merge_overlapping_entities(entities, regex_entities)
     }
 }
 
+fn is_in_scope(filter_entity_kinds: Option<&[String]>, entity: &str) -> bool {
+    filter_entity_kinds
+        .map(|kinds| kinds.iter().any(|kind| kind == entity))
+        .unwrap_or(true)
+}
+
+/// Removes overlapping matches by keeping the longest ones first, and then the ones with the
+/// highest score, matches without score having a score of 1
+fn merge_overlapping_entities(
+    gazetteer_entities: Vec<CustomEntity>,
+    additional_entities: Vec<CustomEntity>,
+) -> Vec<CustomEntity> {
+    let mut entities = gazetteer_entities;
+    entities.extend(additional_entities);
+    let entities_overlap = |lhs_entity: &CustomEntity, rhs_entity: &CustomEntity| {
+        ranges_overlap(&lhs_entity.range, &rhs_entity.range)
+    };
+    let entity_sort_key = |entity: &CustomEntity| {
+        let score = entity.match_score.unwrap_or(1.0);
+        (
+            -(entity.range.clone().count() as i64),
+            -((score * 1_000_000.0) as i64),
+        )
+    };
+    let mut merged_entities =
+        deduplicate_overlapping_items(entities, entities_overlap, entity_sort_key);
+    merged_entities.sort_by_key(|entity| entity.range.start);
+    merged_entities
+}
+
 /// Compute the shifts in characters that occur when comparing the tokens string
 /// with the string consisting of all tokens separated with a space
 ///
@@ -157,6 +286,10 @@ pub struct CustomEntityParserMetadata {
     pub language: String,
     pub parser_directory: String,
     pub parser_usage: CustomEntityParserUsage,
+    #[serde(default)]
+    pub fuzzy_matching: HashMap<EntityName, FuzzyMatchingConfig>,
+    #[serde(default)]
+    pub regex_entities: HashMap<EntityName, RegexEntityConfig>,
 }
 
 impl CachingCustomEntityParser {
@@ -174,11 +307,25 @@ impl CachingCustomEntityParser {
         let language = NluUtilsLanguage::from_language(Language::from_str(&metadata.language)?);
         let gazetteer_parser_path = path.as_ref().join(&metadata.parser_directory);
         let parser = GazetteerParser::from_path(gazetteer_parser_path)?;
-        let cache = Mutex::new(Cache::new(cache_capacity));
+        let fuzzy_matchers = metadata
+            .fuzzy_matching
+            .into_iter()
+            .sorted_by(|(entity_a, _), (entity_b, _)| entity_a.cmp(entity_b))
+            .map(|(entity, config)| FuzzyEntityMatcher::from_path(path.as_ref(), entity, config))
+            .collect::<Result<Vec<_>>>()?;
+        let regex_matchers = metadata
+            .regex_entities
+            .iter()
+            .sorted_by(|(entity_a, _), (entity_b, _)| entity_a.cmp(entity_b))
+            .map(|(entity, config)| RegexEntityMatcher::new(entity.clone(), config))
+            .collect::<Result<Vec<_>>>()?;
+        let cache = Cache::new(cache_capacity);
         info!("Custom entity parser loaded");
         Ok(Self {
             language,
             parser,
+            fuzzy_matchers,
+            regex_matchers,
             cache,
         })
     }
@@ -186,6 +333,13 @@ impl CachingCustomEntityParser {
 
 #[cfg(test)]
 mod tests {
+    extern crate fs_extra;
+
+    use std::fs;
+
+    use self::fs_extra::dir;
+    use tempfile::tempdir;
+
     use super::*;
 
     #[test]
@@ -228,6 +382,7 @@ mod tests {
             alternative_resolved_values: vec![],
             range: 12..15,
             entity_identifier: "Temperature".to_string(),
+            match_score: None,
         }];
 
         assert_eq!(expected_entities, entities);
@@ -263,6 +418,7 @@ mod tests {
             alternative_resolved_values: vec![],
             range: 15..22,
             entity_identifier: "game".to_string(),
+            match_score: None,
         }];
         let expected_entities_with_alternatives = vec![CustomEntity {
             value: "invader".to_string(),
@@ -270,9 +426,117 @@ mod tests {
             alternative_resolved_values: vec!["Invader War Demo".to_string()],
             range: 15..22,
             entity_identifier: "game".to_string(),
+            match_score: None,
         }];
         assert_eq!(Vec::<CustomEntity>::new(), entities_empty_scope);
         assert_eq!(expected_entities_no_scope, entities_no_scope);
         assert_eq!(expected_entities_with_alternatives, entities_with_alternatives);
     }
+
+    #[test]
+    fn test_custom_entity_parser_with_fuzzy_matching() {
+        // Given
+        let parser_path = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_game")
+            .join("custom_entity_parser");
+        let tdir = tempdir().unwrap();
+        dir::copy(&parser_path, tdir.as_ref(), &dir::CopyOptions::new()).unwrap();
+        let fuzzy_parser_path = tdir.as_ref().join("custom_entity_parser");
+        let metadata = r#"{
+            "language": "en",
+            "parser_directory": "parser",
+            "parser_usage": 2,
+            "fuzzy_matching": {
+                "game": {"values_file": "game_values.json", "max_edit_distance": 2}
+            }
+        }"#;
+        let values = r#"[
+            {"raw_value": "invader attack 3", "resolved_value": "Invader Attack 3"},
+            {"raw_value": "invader war demo", "resolved_value": "Invader War Demo"}
+        ]"#;
+        fs::write(fuzzy_parser_path.join("metadata.json"), metadata).unwrap();
+        fs::write(fuzzy_parser_path.join("game_values.json"), values).unwrap();
+
+        let custom_entity_parser =
+            CachingCustomEntityParser::from_path(fuzzy_parser_path, 1000).unwrap();
+        let input = "I want to play invader atack 3";
+
+        // When
+        let entities = custom_entity_parser
+            .extract_entities(input, None, 0)
+            .unwrap();
+        let entity_values = custom_entity_parser.entity_values();
+
+        // Then
+        let expected_entity_values = vec![
+            "Invader Attack 3".to_string(),
+            "Invader War Demo".to_string(),
+        ];
+        assert_eq!(expected_entity_values, entity_values);
+        let expected_entities = vec![CustomEntity {
+            value: "invader atack 3".to_string(),
+            resolved_value: "Invader Attack 3".to_string(),
+            alternative_resolved_values: vec![],
+            range: 15..30,
+            entity_identifier: "game".to_string(),
+            match_score: Some(0.9375),
+        }];
+        assert_eq!(expected_entities, entities);
+    }
+
+    #[test]
+    fn test_custom_entity_parser_with_regex_entities() {
+        // Given
+        let parser_path = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_game")
+            .join("custom_entity_parser");
+        let tdir = tempdir().unwrap();
+        dir::copy(&parser_path, tdir.as_ref(), &dir::CopyOptions::new()).unwrap();
+        let regex_parser_path = tdir.as_ref().join("custom_entity_parser");
+        let metadata = r#"{
+            "language": "en",
+            "parser_directory": "parser",
+            "parser_usage": 2,
+            "regex_entities": {
+                "order_number": {"pattern": "\\d{3}-\\d{4}"}
+            }
+        }"#;
+        fs::write(regex_parser_path.join("metadata.json"), metadata).unwrap();
+
+        let custom_entity_parser =
+            CachingCustomEntityParser::from_path(regex_parser_path, 1000).unwrap();
+        let input = "I want to play invader, order 123-4567";
+
+        // When
+        let entities = custom_entity_parser
+            .extract_entities(input, None, 0)
+            .unwrap();
+        let scoped_entities = custom_entity_parser
+            .extract_entities(input, Some(&["game".to_string()]), 0)
+            .unwrap();
+
+        // Then
+        let game_entity = CustomEntity {
+            value: "invader".to_string(),
+            resolved_value: "Invader Attack 3".to_string(),
+            alternative_resolved_values: vec![],
+            range: 15..22,
+            entity_identifier: "game".to_string(),
+            match_score: None,
+        };
+        let order_number_entity = CustomEntity {
+            value: "123-4567".to_string(),
+            resolved_value: "123-4567".to_string(),
+            alternative_resolved_values: vec![],
+            range: 30..38,
+            entity_identifier: "order_number".to_string(),
+            match_score: None,
+        };
+        assert_eq!(vec![game_entity.clone(), order_number_entity], entities);
+        assert_eq!(vec![game_entity], scoped_entities);
+    }
 }
@@ -0,0 +1,52 @@
+use std::ops::Range;
+
+use serde::Serialize;
+use snips_nlu_ontology::{BuiltinEntity, SlotValue};
+
+use crate::entity_parser::CustomEntity;
+use crate::utils::EntityName;
+
+/// Builtin or custom entity found in an input by the entity parsers of the engine
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntityMatch {
+    /// Matched text of the input
+    pub value: String,
+    pub range: Range<usize>,
+    /// Identifier of the builtin entity, e.g. `snips/number`, or name of the custom entity
+    pub entity: EntityName,
+    pub resolved_value: SlotValue,
+    pub alternatives: Vec<SlotValue>,
+    /// Similarity score between 0 and 1 of fuzzy custom entity matches
+    pub match_score: Option<f32>,
+}
+
+impl From<BuiltinEntity> for EntityMatch {
+    fn from(builtin_entity: BuiltinEntity) -> Self {
+        Self {
+            value: builtin_entity.value,
+            range: builtin_entity.range,
+            entity: builtin_entity.entity_kind.identifier().to_string(),
+            resolved_value: builtin_entity.entity,
+            alternatives: builtin_entity.alternatives,
+            match_score: None,
+        }
+    }
+}
+
+impl From<CustomEntity> for EntityMatch {
+    fn from(custom_entity: CustomEntity) -> Self {
+        Self {
+            value: custom_entity.value,
+            range: custom_entity.range,
+            entity: custom_entity.entity_identifier,
+            resolved_value: SlotValue::Custom(custom_entity.resolved_value.into()),
+            alternatives: custom_entity
+                .alternative_resolved_values
+                .into_iter()
+                .map(|value| SlotValue::Custom(value.into()))
+                .collect(),
+            match_score: custom_entity.match_score,
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::Path;
+
+use failure::ResultExt;
+use itertools::Itertools;
+use serde::Deserialize;
+use snips_nlu_utils::token::Token;
+
+use crate::entity_parser::custom_entity_parser::CustomEntity;
+use crate::errors::*;
+use crate::utils::{cmp_scores, EntityName};
+
+/// Configuration of the fuzzy matching of a custom entity, as declared in the custom entity
+/// parser metadata
+///
+/// Only the values listed in `values_file` are matched approximately, values added afterwards
+/// with the `NluInjector` are only matched exactly.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FuzzyMatchingConfig {
+    /// Name of the json file, relative to the custom entity parser directory, which contains the
+    /// list of entity values to match against
+    pub values_file: String,
+    /// Maximum number of character edits between a chunk of the input and an entity value
+    pub max_edit_distance: usize,
+    /// Minimum similarity score, between 0 and 1, for a fuzzy match to be reported
+    #[serde(default)]
+    pub min_score: f32,
+    /// Compare phonetic keys instead of raw characters
+    #[serde(default)]
+    pub phonetic: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct FuzzyEntityValue {
+    raw_value: String,
+    resolved_value: String,
+}
+
+struct IndexedValue {
+    key: Vec<char>,
+    resolved_value: String,
+}
+
+pub struct FuzzyEntityMatcher {
+    entity: EntityName,
+    config: FuzzyMatchingConfig,
+    /// Values indexed by the length of their key, as the edit distance between two keys is at
+    /// least the difference of their lengths
+    values_by_key_length: BTreeMap<usize, Vec<IndexedValue>>,
+    max_nb_tokens: usize,
+}
+
+impl FuzzyEntityMatcher {
+    pub fn from_path<P: AsRef<Path>>(
+        parser_dir: P,
+        entity: EntityName,
+        config: FuzzyMatchingConfig,
+    ) -> Result<Self> {
+        let values_path = parser_dir.as_ref().join(&config.values_file);
+        let values_file = File::open(&values_path).with_context(|_| {
+            format!(
+                "Cannot open fuzzy matching values of entity '{}' at path: {:?}",
+                entity, values_path
+            )
+        })?;
+        let values: Vec<FuzzyEntityValue> = serde_json::from_reader(values_file)
+            .with_context(|_| format!("Invalid fuzzy matching values file {:?}", values_path))?;
+        let max_nb_tokens = values
+            .iter()
+            .map(|value| value.raw_value.split_whitespace().count())
+            .max()
+            .unwrap_or(0);
+        let mut values_by_key_length = BTreeMap::new();
+        for value in values {
+            let key = matching_key(&value.raw_value.to_lowercase(), config.phonetic);
+            values_by_key_length
+                .entry(key.len())
+                .or_insert_with(Vec::new)
+                .push(IndexedValue {
+                    key,
+                    resolved_value: value.resolved_value,
+                });
+        }
+        Ok(Self {
+            entity,
+            config,
+            values_by_key_length,
+            max_nb_tokens,
+        })
+    }
+
+    pub fn entity(&self) -> &str {
+        &self.entity
+    }
+
+    pub fn resolved_values(&self) -> impl Iterator<Item = &str> {
+        self.values_by_key_length
+            .values()
+            .flatten()
+            .map(|value| &*value.resolved_value)
+    }
+
+    /// Extracts the chunks of tokens which approximately match an entity value
+    ///
+    /// Overlapping matches are all returned, along with their similarity score. `sentence` must
+    /// be the string from which `tokens` were extracted.
+    pub fn extract_entities(
+        &self,
+        sentence: &str,
+        tokens: &[Token],
+        max_alternative_resolved_values: usize,
+    ) -> Vec<CustomEntity> {
+        let mut entities = vec![];
+        let max_distance = self.config.max_edit_distance;
+        for start in 0..tokens.len() {
+            let max_end = (start + self.max_nb_tokens).min(tokens.len());
+            for end in start + 1..=max_end {
+                let chunk = tokens[start..end]
+                    .iter()
+                    .map(|token| &token.value)
+                    .join(" ");
+                let chunk_key = matching_key(&chunk, self.config.phonetic);
+                let min_key_length = chunk_key.len().saturating_sub(max_distance);
+                let candidates: Vec<(f32, &str)> = self
+                    .values_by_key_length
+                    .range(min_key_length..=chunk_key.len() + max_distance)
+                    .flat_map(|(_, values)| values)
+                    .filter_map(|value| {
+                        let distance = levenshtein_distance(&chunk_key, &value.key);
+                        if distance > max_distance {
+                            return None;
+                        }
+                        let max_len = chunk_key.len().max(value.key.len()).max(1);
+                        let score = 1.0 - distance as f32 / max_len as f32;
+                        if score < self.config.min_score {
+                            None
+                        } else {
+                            Some((score, &*value.resolved_value))
+                        }
+                    })
+                    .sorted_by(|(score_a, _), (score_b, _)| cmp_scores(*score_b, *score_a))
+                    .unique_by(|(_, resolved_value)| *resolved_value)
+                    .collect();
+                if let Some((best_score, best_value)) = candidates.first() {
+                    let range = tokens[start].char_range.start..tokens[end - 1].char_range.end;
+                    entities.push(CustomEntity {
+                        value: sentence
+                            .chars()
+                            .skip(range.start)
+                            .take(range.end - range.start)
+                            .collect(),
+                        resolved_value: best_value.to_string(),
+                        alternative_resolved_values: candidates
+                            .iter()
+                            .skip(1)
+                            .take(max_alternative_resolved_values)
+                            .map(|(_, resolved_value)| resolved_value.to_string())
+                            .collect(),
+                        range,
+                        entity_identifier: self.entity.clone(),
+                        match_score: Some(*best_score),
+                    });
+                }
+            }
+        }
+        entities
+    }
+}
+
+fn matching_key(value: &str, phonetic: bool) -> Vec<char> {
+    if phonetic {
+        phonetic_key(value)
+    } else {
+        value.chars().collect()
+    }
+}
+
+/// Computes a simple, language agnostic, phonetic key
+///
+/// Non alphanumeric characters are removed, some letters that usually sound the same are merged
+/// and repeated letters are collapsed, so that for instance "capucino" and "cappuccino" get close
+/// keys.
+fn phonetic_key(value: &str) -> Vec<char> {
+    let chars: Vec<char> = value
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect();
+    let mut key: Vec<char> = Vec::with_capacity(chars.len());
+    let mut index = 0;
+    while index < chars.len() {
+        let next = chars.get(index + 1).cloned();
+        let phonetic_char = match (chars[index], next) {
+            ('p', Some('h')) => {
+                index += 1;
+                'f'
+            }
+            ('c', Some('k')) => {
+                index += 1;
+                'k'
+            }
+            ('c', Some('e')) | ('c', Some('i')) | ('c', Some('y')) => 's',
+            ('c', _) | ('q', _) => 'k',
+            ('z', _) => 's',
+            ('y', _) => 'i',
+            (c, _) => c,
+        };
+        if key.last() != Some(&phonetic_char) {
+            key.push(phonetic_char);
+        }
+        index += 1;
+    }
+    key
+}
+
+fn levenshtein_distance(lhs: &[char], rhs: &[char]) -> usize {
+    if lhs.is_empty() {
+        return rhs.len();
+    }
+    let mut previous_row: Vec<usize> = (0..=rhs.len()).collect();
+    let mut current_row: Vec<usize> = vec![0; rhs.len() + 1];
+    for (i, lhs_char) in lhs.iter().enumerate() {
+        current_row[0] = i + 1;
+        for (j, rhs_char) in rhs.iter().enumerate() {
+            let substitution_cost = if lhs_char == rhs_char { 0 } else { 1 };
+            current_row[j + 1] = (previous_row[j] + substitution_cost)
+                .min(previous_row[j + 1] + 1)
+                .min(current_row[j] + 1);
+        }
+        std::mem::swap(&mut previous_row, &mut current_row);
+    }
+    previous_row[rhs.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levenshtein_distance() {
+        let distance = |a: &str, b: &str| {
+            levenshtein_distance(
+                &a.chars().collect::<Vec<_>>(),
+                &b.chars().collect::<Vec<_>>(),
+            )
+        };
+        assert_eq!(0, distance("coffee", "coffee"));
+        assert_eq!(2, distance("capucino", "cappuccino"));
+        assert_eq!(3, distance("kitten", "sitting"));
+        assert_eq!(4, distance("", "thé!"));
+    }
+
+    #[test]
+    fn test_phonetic_key() {
+        assert_eq!(phonetic_key("cappuccino"), phonetic_key("kapuccino"));
+        assert_eq!(phonetic_key("philip"), phonetic_key("fillip"));
+        assert_eq!(
+            1,
+            levenshtein_distance(&phonetic_key("capucino"), &phonetic_key("cappuccino"))
+        );
+    }
+}
@@ -1,6 +1,11 @@
 pub mod builtin_entity_parser;
 pub mod custom_entity_parser;
-mod utils;
+mod entity_match;
+mod fuzzy_matching;
+mod regex_matching;
+pub(crate) mod utils;
 
 pub use self::builtin_entity_parser::*;
 pub use self::custom_entity_parser::*;
+pub use self::entity_match::EntityMatch;
+pub use self::utils::CacheStatistics;
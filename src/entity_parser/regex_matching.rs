@@ -0,0 +1,223 @@
+use failure::format_err;
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use snips_nlu_utils::token::Token;
+
+use crate::entity_parser::custom_entity_parser::CustomEntity;
+use crate::errors::*;
+use crate::utils::EntityName;
+
+/// Configuration of a custom entity defined by a regular expression, as declared in the custom
+/// entity parser metadata
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RegexEntityConfig {
+    /// Regular expression matching the entity values, applied case-insensitively
+    pub pattern: String,
+}
+
+pub struct RegexEntityMatcher {
+    entity: EntityName,
+    regex: Regex,
+    /// Same regex anchored at both ends, which is matched against spans of tokens when a match
+    /// of `regex` is not aligned on tokens
+    anchored_regex: Regex,
+}
+
+impl RegexEntityMatcher {
+    pub fn new(entity: EntityName, config: &RegexEntityConfig) -> Result<Self> {
+        let build_regex = |pattern: &str| {
+            RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| {
+                    format_err!(
+                        "Invalid pattern for regex entity '{}': {}",
+                        entity,
+                        e.to_string()
+                    )
+                })
+        };
+        let regex = build_regex(&config.pattern)?;
+        let anchored_regex = build_regex(&format!("^(?:{})$", config.pattern))?;
+        Ok(Self {
+            entity,
+            regex,
+            anchored_regex,
+        })
+    }
+
+    pub fn entity(&self) -> &str {
+        &self.entity
+    }
+
+    /// Extracts the non-overlapping spans of tokens which are matched by the regex
+    ///
+    /// The regex is run once on the sentence, and its matches which start and end on token
+    /// boundaries are kept. A match which is not aligned on tokens could hide an aligned one, so
+    /// the tokens it overlaps are then searched for the longest matching span starting at each of
+    /// them. The text of the span, sliced from `sentence`, is used as resolved value. `sentence`
+    /// must be the string from which `tokens` were extracted.
+    pub fn extract_entities(&self, sentence: &str, tokens: &[Token]) -> Vec<CustomEntity> {
+        let mut entities = vec![];
+        let mut next_token_index = 0;
+        for regex_match in self.regex.find_iter(sentence) {
+            let first_token_index = match tokens[next_token_index..]
+                .iter()
+                .position(|token| token.range.end > regex_match.start())
+            {
+                Some(position) => next_token_index + position,
+                None => break,
+            };
+            if tokens[first_token_index].range.start == regex_match.start() {
+                let aligned_end = tokens[first_token_index..]
+                    .iter()
+                    .position(|token| token.range.end == regex_match.end());
+                if let Some(offset) = aligned_end {
+                    let end_token_index = first_token_index + offset;
+                    entities.push(self.build_entity(
+                        sentence,
+                        &tokens[first_token_index],
+                        &tokens[end_token_index],
+                    ));
+                    next_token_index = end_token_index + 1;
+                    continue;
+                }
+            }
+            for start_index in first_token_index..tokens.len() {
+                if tokens[start_index].range.start >= regex_match.end() {
+                    break;
+                }
+                if start_index < next_token_index {
+                    continue;
+                }
+                if let Some(end_index) = self.longest_span_end(sentence, tokens, start_index) {
+                    entities.push(self.build_entity(
+                        sentence,
+                        &tokens[start_index],
+                        &tokens[end_index],
+                    ));
+                    next_token_index = end_index + 1;
+                }
+            }
+        }
+        entities
+    }
+
+    /// Index of the last token of the longest span starting at `start_index` which is matched by
+    /// the anchored regex
+    fn longest_span_end(
+        &self,
+        sentence: &str,
+        tokens: &[Token],
+        start_index: usize,
+    ) -> Option<usize> {
+        let start = tokens[start_index].range.start;
+        (start_index..tokens.len()).rev().find(|end_index| {
+            self.anchored_regex
+                .is_match(&sentence[start..tokens[*end_index].range.end])
+        })
+    }
+
+    fn build_entity(&self, sentence: &str, start_token: &Token, end_token: &Token) -> CustomEntity {
+        let value = sentence[start_token.range.start..end_token.range.end].to_string();
+        CustomEntity {
+            value: value.clone(),
+            resolved_value: value,
+            alternative_resolved_values: vec![],
+            range: start_token.char_range.start..end_token.char_range.end,
+            entity_identifier: self.entity.clone(),
+            match_score: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snips_nlu_utils::language::Language;
+    use snips_nlu_utils::token::tokenize;
+
+    #[test]
+    fn test_regex_entity_matcher() {
+        // Given
+        let config = RegexEntityConfig {
+            pattern: r"[a-z]{2}\d{3,4}".to_string(),
+        };
+        let matcher = RegexEntityMatcher::new("flight_code".to_string(), &config).unwrap();
+        let sentence = "is flight af1234 delayed or xaf5678 or ba987";
+        let tokens = tokenize(sentence, Language::EN);
+
+        // When
+        let entities = matcher.extract_entities(sentence, &tokens);
+
+        // Then
+        let expected_entities = vec![
+            CustomEntity {
+                value: "af1234".to_string(),
+                resolved_value: "af1234".to_string(),
+                alternative_resolved_values: vec![],
+                range: 10..16,
+                entity_identifier: "flight_code".to_string(),
+                match_score: None,
+            },
+            CustomEntity {
+                value: "ba987".to_string(),
+                resolved_value: "ba987".to_string(),
+                alternative_resolved_values: vec![],
+                range: 39..44,
+                entity_identifier: "flight_code".to_string(),
+                match_score: None,
+            },
+        ];
+        assert_eq!(expected_entities, entities);
+    }
+
+    #[test]
+    fn test_regex_entity_matcher_keeps_case_and_finds_overlapped_matches() {
+        // Given
+        let config = RegexEntityConfig {
+            pattern: r"\d{2}-\d{2}|[a-z]{2}\d{3,4}".to_string(),
+        };
+        let matcher = RegexEntityMatcher::new("code".to_string(), &config).unwrap();
+        let sentence = "codes 1234-56-78 and AF1234";
+        let tokens = tokenize(sentence, Language::EN);
+
+        // When
+        let entities = matcher.extract_entities(sentence, &tokens);
+
+        // Then
+        let expected_entities = vec![
+            CustomEntity {
+                value: "56-78".to_string(),
+                resolved_value: "56-78".to_string(),
+                alternative_resolved_values: vec![],
+                range: 11..16,
+                entity_identifier: "code".to_string(),
+                match_score: None,
+            },
+            CustomEntity {
+                value: "AF1234".to_string(),
+                resolved_value: "AF1234".to_string(),
+                alternative_resolved_values: vec![],
+                range: 21..27,
+                entity_identifier: "code".to_string(),
+                match_score: None,
+            },
+        ];
+        assert_eq!(expected_entities, entities);
+    }
+
+    #[test]
+    fn test_regex_entity_matcher_fails_with_invalid_pattern() {
+        // Given
+        let config = RegexEntityConfig {
+            pattern: r"[a-z".to_string(),
+        };
+
+        // When
+        let matcher = RegexEntityMatcher::new("flight_code".to_string(), &config);
+
+        // Then
+        assert!(matcher.is_err());
+    }
+}
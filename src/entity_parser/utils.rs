@@ -1,30 +1,163 @@
-use std::hash::Hash;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 
 use lru_cache::LruCache;
 
 use crate::errors::*;
 
-pub struct Cache<K, V>(LruCache<K, V>)
+const MAX_NB_SHARDS: usize = 16;
+
+/// Usage statistics of an entity parser cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStatistics {
+    /// Maximum number of cached entries, 0 when the cache is disabled
+    pub capacity: usize,
+    /// Current number of cached entries
+    pub size: usize,
+    pub hits: usize,
+    pub misses: usize,
+    pub evictions: usize,
+}
+
+/// LRU cache split into several independently locked shards, so that concurrent lookups of
+/// different keys rarely wait for each other
+pub struct Cache<K, V>
 where
     K: Eq + Hash + Clone,
-    V: Clone;
+    V: Clone,
+{
+    shards: Vec<Mutex<LruCache<K, V>>>,
+    capacity: usize,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    evictions: AtomicUsize,
+}
 
 impl<K, V> Cache<K, V>
 where
     K: Eq + Hash + Clone,
     V: Clone,
 {
+    /// Creates a cache holding at most `capacity` entries, a capacity of 0 disables caching
     pub fn new(capacity: usize) -> Self {
-        Cache(LruCache::new(capacity))
+        let nb_shards = capacity.min(MAX_NB_SHARDS);
+        let shards = (0..nb_shards)
+            .map(|shard_index| {
+                let extra_entry = if shard_index < capacity % nb_shards {
+                    1
+                } else {
+                    0
+                };
+                Mutex::new(LruCache::new(capacity / nb_shards + extra_entry))
+            })
+            .collect();
+        Cache {
+            shards,
+            capacity,
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+            evictions: AtomicUsize::new(0),
+        }
     }
 
-    pub fn try_cache<F: Fn(&K) -> Result<V>>(&mut self, key: &K, producer: F) -> Result<V> {
-        let cached_value = self.0.get_mut(key).cloned();
+    pub fn try_cache<F: Fn(&K) -> Result<V>>(&self, key: &K, producer: F) -> Result<V> {
+        if self.shards.is_empty() {
+            return producer(key);
+        }
+        let shard = &self.shards[self.shard_index(key)];
+        let cached_value = shard.lock().unwrap().get_mut(key).cloned();
         if let Some(value) = cached_value {
+            self.hits.fetch_add(1, Ordering::Relaxed);
             return Ok(value);
         }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        // The value is computed without holding the lock so that other lookups in the same shard
+        // are not blocked
         let value = producer(key)?;
-        self.0.insert(key.clone(), value.clone());
+        let mut locked_shard = shard.lock().unwrap();
+        if !locked_shard.contains_key(key) && locked_shard.len() >= locked_shard.capacity() {
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+        locked_shard.insert(key.clone(), value.clone());
         Ok(value)
     }
+
+    pub fn statistics(&self) -> CacheStatistics {
+        CacheStatistics {
+            capacity: self.capacity,
+            size: self
+                .shards
+                .iter()
+                .map(|shard| shard.lock().unwrap().len())
+                .sum(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    fn shard_index(&self, key: &K) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % self.shards.len() as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_statistics() {
+        // Given
+        let cache: Cache<usize, usize> = Cache::new(1);
+
+        // When
+        for key in &[1, 1, 2, 1] {
+            cache.try_cache(key, |k| Ok(k * 10)).unwrap();
+        }
+
+        // Then
+        let expected_statistics = CacheStatistics {
+            capacity: 1,
+            size: 1,
+            hits: 1,
+            misses: 3,
+            evictions: 2,
+        };
+        assert_eq!(expected_statistics, cache.statistics());
+    }
+
+    #[test]
+    fn test_disabled_cache_always_calls_producer() {
+        // Given
+        let cache: Cache<usize, usize> = Cache::new(0);
+        let nb_calls = AtomicUsize::new(0);
+
+        // When
+        for _ in 0..3 {
+            let value = cache
+                .try_cache(&1, |k| {
+                    nb_calls.fetch_add(1, Ordering::Relaxed);
+                    Ok(k + 1)
+                })
+                .unwrap();
+            assert_eq!(2, value);
+        }
+
+        // Then
+        assert_eq!(3, nb_calls.load(Ordering::Relaxed));
+        assert_eq!(
+            CacheStatistics {
+                capacity: 0,
+                size: 0,
+                hits: 0,
+                misses: 0,
+                evictions: 0,
+            },
+            cache.statistics()
+        );
+    }
 }
@@ -1,15 +1,91 @@
-use failure::Fail;
+use failure::{Context, Fail};
+
+use crate::injection::NluInjectionError;
 
 #[derive(Debug, Fail)]
 pub enum SnipsNluError {
-    #[fail(display = "Unable to read file '{}'", _0)]
+    #[fail(display = "Unable to load model '{}'", _0)]
     ModelLoad(String),
     #[fail(display = "Mismatched model version: model is {} but runner is {}", model, runner)]
     WrongModelVersion{ model: String, runner: &'static str},
+    #[fail(display = "Invalid model: found non finite values in '{}'", _0)]
+    NonFiniteModelValues(String),
+    #[fail(display = "Invalid model: {}", _0)]
+    InvalidModel(String),
     #[fail(display = "Unknown intent: '{}'", _0)]
     UnknownIntent(String),
+    #[fail(display = "Unknown slot: '{}'", _0)]
+    UnknownSlot(String),
+    #[fail(display = "Unknown entity: '{}'", _0)]
+    UnknownEntity(String),
+    #[fail(display = "Entity parser error: {}", _0)]
+    EntityParser(String),
+    #[fail(display = "Input is too long: {} characters while the maximum is {}", length, max_length)]
+    InputTooLong{ length: usize, max_length: usize },
     #[fail(display = "Internal error: {}", _0)]
     InternalError(String),
 }
 
+/// Category of the errors returned by the public API
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnipsNluErrorKind {
+    /// The model files cannot be read or deserialized
+    ModelLoad,
+    /// The model was trained with an incompatible version
+    WrongModelVersion,
+    /// The model could be read but contains invalid values
+    InvalidModel,
+    /// An unknown intent was used, for instance in an intents filter
+    UnknownIntent,
+    /// An unknown slot was used
+    UnknownSlot,
+    /// An unknown entity was used, for instance in an entities scope
+    UnknownEntity,
+    /// The builtin or custom entity parser failed
+    EntityParser,
+    /// The injection of new entity values failed
+    Injection,
+    /// The input cannot be processed
+    InvalidInput,
+    /// Any other error
+    Internal,
+}
+
+impl SnipsNluError {
+    pub fn kind(&self) -> SnipsNluErrorKind {
+        match self {
+            SnipsNluError::ModelLoad(_) => SnipsNluErrorKind::ModelLoad,
+            SnipsNluError::WrongModelVersion { .. } => SnipsNluErrorKind::WrongModelVersion,
+            SnipsNluError::NonFiniteModelValues(_) => SnipsNluErrorKind::InvalidModel,
+            SnipsNluError::InvalidModel(_) => SnipsNluErrorKind::InvalidModel,
+            SnipsNluError::UnknownIntent(_) => SnipsNluErrorKind::UnknownIntent,
+            SnipsNluError::UnknownSlot(_) => SnipsNluErrorKind::UnknownSlot,
+            SnipsNluError::UnknownEntity(_) => SnipsNluErrorKind::UnknownEntity,
+            SnipsNluError::EntityParser(_) => SnipsNluErrorKind::EntityParser,
+            SnipsNluError::InputTooLong { .. } => SnipsNluErrorKind::InvalidInput,
+            SnipsNluError::InternalError(_) => SnipsNluErrorKind::Internal,
+        }
+    }
+}
+
+/// Returns the kind of an error, which is given by the deepest `SnipsNluError` in its chain of
+/// causes, as it is the most specific one
+pub fn error_kind(error: &failure::Error) -> SnipsNluErrorKind {
+    error
+        .iter_chain()
+        .filter_map(|cause| {
+            if let Some(nlu_error) = cause.downcast_ref::<SnipsNluError>() {
+                Some(nlu_error.kind())
+            } else if let Some(context) = cause.downcast_ref::<Context<SnipsNluError>>() {
+                Some(context.get_context().kind())
+            } else if cause.downcast_ref::<NluInjectionError>().is_some() {
+                Some(SnipsNluErrorKind::Injection)
+            } else {
+                None
+            }
+        })
+        .last()
+        .unwrap_or(SnipsNluErrorKind::Internal)
+}
+
 pub type Result<T> = ::std::result::Result<T, ::failure::Error>;
@@ -15,6 +15,12 @@ pub enum NluInjectionErrorKind {
     InternalInjectionError { msg: String },
 }
 
+impl NluInjectionError {
+    pub fn kind(&self) -> &NluInjectionErrorKind {
+        self.inner.get_context()
+    }
+}
+
 //  Boilerplate
 impl Fail for NluInjectionError {
     fn cause(&self) -> Option<&dyn Fail> {
@@ -16,6 +16,7 @@ use snips_nlu_parsers::{BuiltinParserMetadata, GazetteerParserMetadata};
 use snips_nlu_utils::language::Language as NluUtilsLanguage;
 use snips_nlu_utils::token::tokenize_light;
 
+use crate::engine_lock::EngineDirLock;
 use crate::entity_parser::custom_entity_parser::CustomEntityParserMetadata;
 use crate::entity_parser::custom_entity_parser::CustomEntityParserUsage;
 use crate::models::nlu_engine::NluEngineModel;
@@ -89,6 +90,16 @@ impl<P: AsRef<Path>> NluInjector<P> {
     pub fn inject(self) -> Result<(), NluInjectionError> {
         info!("Starting injection...");
 
+        // Prevents concurrent injections and loadings of the engine while it is modified
+        let _lock = EngineDirLock::exclusive(self.nlu_engine_dir.as_ref()).with_context(|_| {
+            NluInjectionErrorKind::InternalInjectionError {
+                msg: format!(
+                    "could not lock nlu engine directory {:?}",
+                    self.nlu_engine_dir.as_ref()
+                ),
+            }
+        })?;
+
         info!("Retrieving parsers paths...");
         let engine_info = get_nlu_engine_info(self.nlu_engine_dir.as_ref())?;
         let builtin_parser_info = get_builtin_parser_info(&engine_info.builtin_entity_parser_dir)?;
@@ -428,6 +439,7 @@ mod tests {
     use self::tempfile::tempdir;
     use snips_nlu_ontology::*;
 
+    use crate::slot_filler::FeatureRegistry;
     use crate::SharedResources;
     use crate::SnipsNluEngine;
 
@@ -471,6 +483,7 @@ mod tests {
             stemmer: Some(Arc::new(stemmer.clone())),
             word_clusterers: HashMap::new(),
             stop_words: HashSet::new(),
+            feature_registry: FeatureRegistry::default(),
         });
 
         // Behaviour before injection
@@ -526,6 +539,7 @@ mod tests {
             stemmer: Some(Arc::new(stemmer)),
             word_clusterers: injected_resources.word_clusterers.clone(),
             stop_words: HashSet::new(),
+            feature_registry: FeatureRegistry::default(),
         };
 
         let nlu_engine = SnipsNluEngine::from_path_with_resources(
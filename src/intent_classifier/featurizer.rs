@@ -1,5 +1,5 @@
 use std::cmp::min;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::File;
 use std::path::Path;
 use std::str::FromStr;
@@ -19,10 +19,14 @@ use crate::models::{CooccurrenceVectorizerModel, FeaturizerModel, TfidfVectorize
 use crate::resources::stemmer::Stemmer;
 use crate::resources::word_clusterer::WordClusterer;
 use crate::resources::SharedResources;
-use crate::utils::{replace_entities, MatchedEntity};
+use crate::spelling_correction::Vocabulary;
+use crate::utils::{check_finite_values, replace_entities, MatchedEntity};
 
 type WordPair = (String, String);
 
+const BUILTIN_ENTITY_FEATURE_PREFIX: &str = "builtinentityfeature";
+const CUSTOM_ENTITY_FEATURE_PREFIX: &str = "entityfeature";
+
 pub struct Featurizer {
     tfidf_vectorizer: TfidfVectorizer,
     cooccurrence_vectorizer: Option<CooccurrenceVectorizer>,
@@ -77,19 +81,121 @@ impl Featurizer {
         }
     }
 
-    pub fn transform(&self, input: &str) -> Result<Array1<f32>> {
+    /// Ratio of the input words which are missing from the tf-idf vocabulary
+    pub fn unknown_words_ratio(&self, input: &str) -> f32 {
+        self.tfidf_vectorizer.unknown_words_ratio(input)
+    }
+
+    /// Words of the tf-idf vocabulary, see `TfidfVectorizer::vocabulary`
+    pub fn vocabulary(&self) -> Vocabulary {
+        self.tfidf_vectorizer.vocabulary()
+    }
+
+    /// Returns the kind and the human readable name of the feature at the provided index
+    pub fn feature_name(&self, index: usize) -> Option<(FeatureKind, &str)> {
+        let tfidf_size = self.tfidf_vectorizer.vocabulary_size;
+        if index < tfidf_size {
+            return Some((
+                FeatureKind::Tfidf,
+                &self.tfidf_vectorizer.feature_names[index],
+            ));
+        }
+        self.cooccurrence_vectorizer
+            .as_ref()
+            .and_then(|vectorizer| {
+                vectorizer
+                    .word_pair_names
+                    .get(index - tfidf_size)
+                    .map(|name| (FeatureKind::Cooccurrence, &**name))
+            })
+    }
+
+    pub fn transform(&self, input: &str) -> Result<SparseFeatures> {
         let mut features = self.tfidf_vectorizer.transform(input)?;
         if let Some(vectorizer) = self.cooccurrence_vectorizer.as_ref() {
-            let cooccurrence_features: Vec<f32> = vectorizer.transform(input)?;
+            let cooccurrence_features = vectorizer.transform(input)?;
             features.extend(cooccurrence_features)
         };
-        Ok(Array::from_iter(features))
+        Ok(features)
+    }
+}
+
+/// Vectorizer from which a feature of the `Featurizer` comes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeatureKind {
+    /// Vocabulary word, builtin or custom entity, or word cluster
+    Tfidf,
+    /// Pair of words, separated by a space in the feature name
+    Cooccurrence,
+}
+
+/// Feature vector in which only the non-zero features are stored, sorted by index
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseFeatures {
+    dim: usize,
+    indices: Vec<usize>,
+    values: Vec<f32>,
+}
+
+impl SparseFeatures {
+    fn from_btree_map(dim: usize, features: BTreeMap<usize, f32>) -> Self {
+        let (indices, values) = features
+            .into_iter()
+            .filter(|(_, value)| *value != 0.)
+            .unzip();
+        Self {
+            dim,
+            indices,
+            values,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn from_dense(features: &ArrayView1<f32>) -> Self {
+        let dim = features.dim();
+        let features = features
+            .indexed_iter()
+            .map(|(index, value)| (index, *value))
+            .collect();
+        Self::from_btree_map(dim, features)
+    }
+
+    /// Total number of features, including the null ones
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Iterates over the non-zero features as `(index, value)` pairs, by increasing index
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (usize, f32)> + 'a {
+        self.indices
+            .iter()
+            .cloned()
+            .zip(self.values.iter().cloned())
+    }
+
+    /// Appends the features of `other` after the current ones
+    pub fn extend(&mut self, other: SparseFeatures) {
+        let offset = self.dim;
+        self.indices
+            .extend(other.indices.into_iter().map(|index| index + offset));
+        self.values.extend(other.values);
+        self.dim += other.dim;
+    }
+
+    pub fn to_dense(&self) -> Array1<f32> {
+        let mut features = Array1::zeros(self.dim);
+        for (index, value) in self.iter() {
+            features[index] = value;
+        }
+        features
     }
 }
 
 pub struct TfidfVectorizer {
     builtin_entity_scope: Vec<BuiltinEntityKind>,
     vocabulary: HashMap<String, usize>,
+    vocabulary_size: usize,
+    feature_names: Vec<String>,
     idf_diag: Vec<f32>,
     word_clusterer: Option<Arc<dyn WordClusterer>>,
     stemmer: Option<Arc<dyn Stemmer>>,
@@ -122,6 +228,7 @@ impl TfidfVectorizer {
     ) -> Result<Self> {
         let vocabulary = model.vectorizer.vocab;
         let idf_diag = model.vectorizer.idf_diag;
+        check_finite_values(idf_diag.iter(), "idf_diag")?;
 
         let ontology_language = Language::from_str(model.language_code.as_ref())?;
         let language = NluUtilsLanguage::from_language(ontology_language);
@@ -164,9 +271,17 @@ impl TfidfVectorizer {
             None
         };
 
+        let vocabulary_size = vocabulary.values().max().map(|max| max + 1).unwrap_or(0);
+        let mut feature_names = vec![String::new(); vocabulary_size];
+        for (word, index) in vocabulary.iter() {
+            feature_names[*index] = word.clone();
+        }
+
         Ok(Self {
             builtin_entity_scope,
             vocabulary,
+            vocabulary_size,
+            feature_names,
             idf_diag,
             word_clusterer: opt_word_clusterer,
             stemmer,
@@ -175,7 +290,52 @@ impl TfidfVectorizer {
         })
     }
 
-    pub fn transform(&self, utterance: &str) -> Result<Vec<f32>> {
+    /// Words of the vocabulary, without the entity and word cluster features
+    ///
+    /// The vocabulary only contains stems when the vectorizer uses stemming.
+    pub fn vocabulary(&self) -> Vocabulary {
+        let words = self
+            .vocabulary
+            .keys()
+            .filter(|word| {
+                !word.starts_with(BUILTIN_ENTITY_FEATURE_PREFIX)
+                    && !word.starts_with(CUSTOM_ENTITY_FEATURE_PREFIX)
+                    && word.chars().all(char::is_alphabetic)
+            })
+            .cloned()
+            .sorted()
+            .collect();
+        if self.stemmer.is_some() {
+            Vocabulary {
+                words: vec![],
+                stems: words,
+            }
+        } else {
+            Vocabulary {
+                words,
+                stems: vec![],
+            }
+        }
+    }
+
+    /// Ratio of the utterance words which are missing from the vocabulary, 0 when the utterance
+    /// has no words
+    ///
+    /// Words are normalized and stemmed as in `transform`, but entity and word cluster features
+    /// are not taken into account.
+    pub fn unknown_words_ratio(&self, utterance: &str) -> f32 {
+        let tokens = tokenize_light(utterance, self.language);
+        if tokens.is_empty() {
+            return 0.;
+        }
+        let nb_unknown_words = normalize_stem(&tokens, self.stemmer.clone())
+            .iter()
+            .filter(|token| !self.vocabulary.contains_key(*token))
+            .count();
+        nb_unknown_words as f32 / tokens.len() as f32
+    }
+
+    pub fn transform(&self, utterance: &str) -> Result<SparseFeatures> {
         let tokens = tokenize_light(utterance, self.language);
         let normalized_tokens = normalize_stem(&tokens, self.stemmer.clone());
 
@@ -217,27 +377,29 @@ impl TfidfVectorizer {
             word_clusters,
         ];
 
-        let vocabulary_size = self.vocabulary.values().max().unwrap() + 1;
-        let mut features: Vec<f32> = vec![0.; vocabulary_size];
-        let mut match_idx: HashSet<usize> = HashSet::new();
+        let mut features: BTreeMap<usize, f32> = BTreeMap::new();
         for extracted_features in features_it.iter() {
             for word in extracted_features {
                 if let Some(word_idx) = self.vocabulary.get(word) {
-                    features[*word_idx] += 1.;
-                    match_idx.insert(*word_idx);
+                    *features.entry(*word_idx).or_insert(0.) += 1.;
                 }
             }
         }
 
-        for ix in match_idx {
-            features[ix] *= self.idf_diag[ix]
+        for (ix, value) in features.iter_mut() {
+            *value *= self.idf_diag[*ix]
         }
 
         // Normalize tf-idf
-        let l2_norm: f32 = features.iter().fold(0., |norm, v| norm + v * v).sqrt();
+        let l2_norm: f32 = features.values().fold(0., |norm, v| norm + v * v).sqrt();
         let safe_l2_norm = if l2_norm > 0. { l2_norm } else { 1. };
-        features = features.iter().map(|c| *c / safe_l2_norm).collect_vec();
-        Ok(features)
+        for value in features.values_mut() {
+            *value /= safe_l2_norm
+        }
+        Ok(SparseFeatures::from_btree_map(
+            self.vocabulary_size,
+            features,
+        ))
     }
 }
 
@@ -245,6 +407,7 @@ pub struct CooccurrenceVectorizer {
     language: NluUtilsLanguage,
     builtin_entity_scope: Vec<BuiltinEntityKind>,
     word_pairs: HashMap<WordPair, usize>,
+    word_pair_names: Vec<String>,
     filter_stop_words: bool,
     window_size: Option<usize>,
     keep_order: bool,
@@ -284,6 +447,13 @@ impl CooccurrenceVectorizer {
             })
             .collect::<Result<Vec<BuiltinEntityKind>>>()?;
 
+        let mut word_pair_names = vec![String::new(); model.word_pairs.len()];
+        for (index, (first_word, second_word)) in model.word_pairs.iter() {
+            if let Some(name) = word_pair_names.get_mut(*index) {
+                *name = format!("{} {}", first_word, second_word);
+            }
+        }
+
         let word_pairs = model
             .word_pairs
             .into_iter()
@@ -302,6 +472,7 @@ impl CooccurrenceVectorizer {
             language,
             builtin_entity_scope,
             word_pairs,
+            word_pair_names,
             filter_stop_words,
             window_size,
             keep_order,
@@ -310,7 +481,7 @@ impl CooccurrenceVectorizer {
         })
     }
 
-    fn transform(&self, utterance: &str) -> Result<Vec<f32>> {
+    fn transform(&self, utterance: &str) -> Result<SparseFeatures> {
         // Extract builtin entities on the raw utterance
         let builtin_entities = self
             .shared_resources
@@ -338,13 +509,19 @@ impl CooccurrenceVectorizer {
 
         let tokens = tokenize_light(&*enriched_utterance, self.language);
 
-        let mut features: Vec<f32> = vec![0.; self.word_pairs.len()];
-        for pair in self.extract_word_pairs(tokens) {
-            if let Some(pair_index) = self.word_pairs.get(&pair) {
-                features[*pair_index] = 1.0;
-            }
-        }
-        Ok(features)
+        let features = self
+            .extract_word_pairs(tokens)
+            .into_iter()
+            .filter_map(|pair| {
+                self.word_pairs
+                    .get(&pair)
+                    .map(|pair_index| (*pair_index, 1.0))
+            })
+            .collect();
+        Ok(SparseFeatures::from_btree_map(
+            self.word_pairs.len(),
+            features,
+        ))
     }
 
     fn placeholder_fn(&self, entity_kind: &str) -> String {
@@ -386,12 +563,12 @@ fn get_builtin_entity_feature_name(
     language: NluUtilsLanguage,
 ) -> String {
     let e = tokenize_light(&entity_kind.identifier().to_lowercase(), language).join("");
-    format!("builtinentityfeature{}", e)
+    format!("{}{}", BUILTIN_ENTITY_FEATURE_PREFIX, e)
 }
 
 fn get_custom_entity_feature_name(entity_name: &str, language: NluUtilsLanguage) -> String {
     let e = tokenize_light(&entity_name.to_lowercase(), language).join("");
-    format!("entityfeature{}", e)
+    format!("{}{}", CUSTOM_ENTITY_FEATURE_PREFIX, e)
 }
 
 fn get_word_clusters(
@@ -434,6 +611,7 @@ mod tests {
     use crate::resources::stemmer::HashMapStemmer;
     use crate::resources::word_clusterer::HashMapWordClusterer;
     use crate::resources::SharedResources;
+    use crate::slot_filler::FeatureRegistry;
     use crate::testutils::assert_epsilon_eq_array1;
     use crate::testutils::MockedBuiltinEntityParser;
     use crate::testutils::MockedCustomEntityParser;
@@ -452,6 +630,7 @@ mod tests {
                     alternative_resolved_values: vec![],
                     range: 0..5,
                     entity_identifier: "greeting".to_string(),
+                    match_score: None,
                 },
                 CustomEntity {
                     value: "hello".to_string(),
@@ -459,6 +638,7 @@ mod tests {
                     alternative_resolved_values: vec![],
                     range: 0..5,
                     entity_identifier: "word".to_string(),
+                    match_score: None,
                 },
                 CustomEntity {
                     value: "bird".to_string(),
@@ -466,6 +646,7 @@ mod tests {
                     alternative_resolved_values: vec![],
                     range: 11..15,
                     entity_identifier: "animal".to_string(),
+                    match_score: None,
                 },
                 CustomEntity {
                     value: "bird".to_string(),
@@ -473,6 +654,7 @@ mod tests {
                     alternative_resolved_values: vec![],
                     range: 31..35,
                     entity_identifier: "animal".to_string(),
+                    match_score: None,
                 },
             ],
         )]);
@@ -496,6 +678,7 @@ mod tests {
             word_clusterers: HashMap::new(),
             gazetteers: HashMap::new(),
             stop_words: HashSet::new(),
+            feature_registry: FeatureRegistry::default(),
         };
 
         let vocab = hashmap![
@@ -571,7 +754,25 @@ mod tests {
             0.17857981108262563
         ];
 
-        assert_epsilon_eq_array1(&expected_features, &features, 1e-6);
+        assert_epsilon_eq_array1(&expected_features, &features.to_dense(), 1e-6);
+    }
+
+    #[test]
+    fn test_sparse_features() {
+        // Given
+        let mut features = SparseFeatures::from_dense(&array![0.0, 0.5, 0.0, 1.5].view());
+        let other_features = SparseFeatures::from_dense(&array![2.0, 0.0].view());
+
+        // When
+        features.extend(other_features);
+
+        // Then
+        assert_eq!(6, features.dim());
+        assert_eq!(
+            vec![(1, 0.5), (3, 1.5), (4, 2.0)],
+            features.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(array![0.0, 0.5, 0.0, 1.5, 2.0, 0.0], features.to_dense());
     }
 
     #[test]
@@ -587,6 +788,7 @@ mod tests {
                         alternative_resolved_values: vec![],
                         range: 0..5,
                         entity_identifier: "greeting".to_string(),
+                        match_score: None,
                     },
                     CustomEntity {
                         value: "hello".to_string(),
@@ -594,6 +796,7 @@ mod tests {
                         alternative_resolved_values: vec![],
                         range: 0..5,
                         entity_identifier: "word".to_string(),
+                        match_score: None,
                     },
                     CustomEntity {
                         value: "bird".to_string(),
@@ -601,6 +804,7 @@ mod tests {
                         alternative_resolved_values: vec![],
                         range: 11..15,
                         entity_identifier: "animal".to_string(),
+                        match_score: None,
                     },
                     CustomEntity {
                         value: "bird".to_string(),
@@ -608,6 +812,7 @@ mod tests {
                         alternative_resolved_values: vec![],
                         range: 31..35,
                         entity_identifier: "animal".to_string(),
+                        match_score: None,
                     },
                 ],
             ),
@@ -620,6 +825,7 @@ mod tests {
                         alternative_resolved_values: vec![],
                         range: 0..5,
                         entity_identifier: "greeting".to_string(),
+                        match_score: None,
                     },
                     CustomEntity {
                         value: "hello".to_string(),
@@ -627,6 +833,7 @@ mod tests {
                         alternative_resolved_values: vec![],
                         range: 0..5,
                         entity_identifier: "word".to_string(),
+                        match_score: None,
                     },
                     CustomEntity {
                         value: "bird".to_string(),
@@ -634,6 +841,7 @@ mod tests {
                         alternative_resolved_values: vec![],
                         range: 11..15,
                         entity_identifier: "animal".to_string(),
+                        match_score: None,
                     },
                     CustomEntity {
                         value: "bird".to_string(),
@@ -641,6 +849,7 @@ mod tests {
                         alternative_resolved_values: vec![],
                         range: 28..32,
                         entity_identifier: "animal".to_string(),
+                        match_score: None,
                     },
                 ],
             ),
@@ -667,6 +876,7 @@ mod tests {
             word_clusterers: HashMap::new(),
             gazetteers: HashMap::new(),
             stop_words,
+            feature_registry: FeatureRegistry::default(),
         });
 
         let vocab = hashmap![
@@ -772,7 +982,16 @@ mod tests {
             0.0
         ];
 
-        assert_epsilon_eq_array1(&expected_features, &features, 1e-6);
+        assert_epsilon_eq_array1(&expected_features, &features.to_dense(), 1e-6);
+        assert_eq!(
+            Some((FeatureKind::Tfidf, "beauti")),
+            featurizer.feature_name(1)
+        );
+        assert_eq!(
+            Some((FeatureKind::Cooccurrence, "hello ANIMAL")),
+            featurizer.feature_name(12)
+        );
+        assert_eq!(None, featurizer.feature_name(17));
     }
 
     #[test]
@@ -815,6 +1034,7 @@ house	cluster_house
             word_clusterers: HashMap::new(),
             gazetteers: HashMap::new(),
             stop_words: hashset!(),
+            feature_registry: FeatureRegistry::default(),
         });
         let config = CooccurrenceVectorizerConfiguration {
             window_size: None,
@@ -875,6 +1095,7 @@ house	cluster_house
             word_clusterers: HashMap::new(),
             gazetteers: HashMap::new(),
             stop_words: hashset!(),
+            feature_registry: FeatureRegistry::default(),
         });
         let config = CooccurrenceVectorizerConfiguration {
             window_size: None,
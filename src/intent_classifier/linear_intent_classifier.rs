@@ -0,0 +1,285 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::iter::FromIterator;
+use std::path::Path;
+use std::sync::Arc;
+
+use failure::{bail, ResultExt};
+use itertools::Itertools;
+use log::{debug, info};
+use ndarray::prelude::*;
+use snips_nlu_ontology::IntentClassifierResult;
+
+use crate::errors::*;
+use crate::intent_classifier::{Featurizer, IntentClassifier};
+use crate::models::LinearIntentClassifierModel;
+use crate::resources::SharedResources;
+use crate::spelling_correction::Vocabulary;
+use crate::utils::{cmp_scores, IntentName};
+
+use super::linear_model::MulticlassLinearModel;
+use super::logreg::transpose_coeffs;
+
+/// Intent classifier relying on a multinomial softmax model or on a Platt-scaled linear SVM
+///
+/// It uses the same featurizer as the `LogRegIntentClassifier`, which makes it possible to deploy
+/// linear models trained with other toolkits.
+pub struct LinearIntentClassifier {
+    intent_list: Vec<Option<IntentName>>,
+    featurizer: Option<Featurizer>,
+    model: Option<MulticlassLinearModel>,
+}
+
+impl LinearIntentClassifier {
+    pub fn from_path<P: AsRef<Path>>(
+        path: P,
+        shared_resources: Arc<SharedResources>,
+    ) -> Result<Self> {
+        info!("Loading linear intent classifier ({:?}) ...", path.as_ref());
+        let classifier_model_path = path.as_ref().join("intent_classifier.json");
+        let model_file = File::open(&classifier_model_path).with_context(|_| {
+            format!(
+                "Cannot open LinearIntentClassifier file '{:?}'",
+                &classifier_model_path
+            )
+        })?;
+        let model: LinearIntentClassifierModel = serde_json::from_reader(model_file)
+            .with_context(|_| "Cannot deserialize LinearIntentClassifier json data")?;
+
+        let featurizer: Option<Featurizer> = if let Some(featurizer_name) = model.featurizer {
+            let featurizer_path = path.as_ref().join(&featurizer_name);
+            Some(Featurizer::from_path(&featurizer_path, shared_resources)?)
+        } else {
+            None
+        };
+
+        let linear_model = if let (Some(intercept), Some(coeffs)) = (model.intercept, model.coeffs)
+        {
+            let arr_intercept = Array::from_vec(intercept);
+            let nb_classes = arr_intercept.dim();
+            if nb_classes != model.intent_list.len() {
+                bail!(
+                    "Invalid LinearIntentClassifier: found {} classes for {} intents",
+                    nb_classes,
+                    model.intent_list.len()
+                );
+            }
+            let arr_weights = transpose_coeffs(&coeffs, nb_classes)?;
+            MulticlassLinearModel::new(arr_intercept, arr_weights, model.output).map(Some)
+        } else {
+            Ok(None)
+        }?;
+
+        info!("Linear intent classifier loaded");
+
+        Ok(Self {
+            intent_list: model.intent_list,
+            featurizer,
+            model: linear_model,
+        })
+    }
+}
+
+impl IntentClassifier for LinearIntentClassifier {
+    fn get_intent(
+        &self,
+        input: &str,
+        intents_whitelist: Option<&[&str]>,
+    ) -> Result<IntentClassifierResult> {
+        debug!("Classifying intent...");
+        let intents_results = self.get_intents_with_whitelist(input, intents_whitelist)?;
+        let intent_result = if intents_results.is_empty() {
+            IntentClassifierResult {
+                intent_name: None,
+                confidence_score: 1.0,
+            }
+        } else {
+            intents_results.into_iter().next().unwrap()
+        };
+        debug!("Intent found: '{:?}'", intent_result.intent_name);
+        Ok(intent_result)
+    }
+
+    fn get_intents(&self, input: &str) -> Result<Vec<IntentClassifierResult>> {
+        self.get_intents_with_whitelist(input, None)
+    }
+
+    fn vocabulary(&self) -> Vocabulary {
+        self.featurizer
+            .as_ref()
+            .map(|featurizer| featurizer.vocabulary())
+            .unwrap_or_default()
+    }
+}
+
+impl LinearIntentClassifier {
+    fn get_intents_with_whitelist(
+        &self,
+        input: &str,
+        intents_whitelist: Option<&[&str]>,
+    ) -> Result<Vec<IntentClassifierResult>> {
+        if self.intent_list.len() <= 1 {
+            return Ok(vec![IntentClassifierResult {
+                intent_name: self.intent_list.first().cloned().unwrap_or(None),
+                confidence_score: 1.0,
+            }]);
+        }
+
+        if input.is_empty() || self.featurizer.is_none() || self.model.is_none() {
+            return Ok(self
+                .intent_list
+                .iter()
+                .map(|intent_name| IntentClassifierResult {
+                    intent_name: intent_name.clone(),
+                    confidence_score: if intent_name.is_none() { 1.0 } else { 0.0 },
+                })
+                .sorted_by(|a, b| cmp_scores(b.confidence_score, a.confidence_score))
+                .collect());
+        }
+
+        let opt_intents_set: Option<HashSet<&str>> =
+            intents_whitelist.map(|intent_list| HashSet::from_iter(intent_list.iter().cloned()));
+
+        let featurizer = self.featurizer.as_ref().unwrap(); // Checked above
+        let model = self.model.as_ref().unwrap(); // Checked above
+
+        let features = featurizer.transform(input)?;
+        let probabilities = model.run(&features)?;
+
+        Ok(self
+            .intent_list
+            .iter()
+            .zip(probabilities.into_iter())
+            .map(|(intent_name, probability)| IntentClassifierResult {
+                intent_name: intent_name.clone(),
+                confidence_score: *probability,
+            })
+            .filter(|res| {
+                if let Some(intent) = res.intent_name.as_ref() {
+                    opt_intents_set
+                        .as_ref()
+                        .map(|intents| intents.contains(&**intent))
+                        .unwrap_or(true)
+                } else {
+                    true
+                }
+            })
+            .sorted_by(|a, b| cmp_scores(b.confidence_score, a.confidence_score))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use fs_extra::dir;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    use crate::intent_classifier::build_intent_classifier;
+    use crate::resources::loading::load_engine_shared_resources;
+
+    fn write_linear_classifier(output: serde_json::Value) -> tempfile::TempDir {
+        let trained_engine_dir = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage");
+        let log_reg_classifier_path = trained_engine_dir
+            .join("probabilistic_intent_parser")
+            .join("intent_classifier");
+
+        let classifier_dir = tempdir().unwrap();
+        dir::copy(
+            log_reg_classifier_path.join("featurizer"),
+            classifier_dir.path(),
+            &dir::CopyOptions::new(),
+        )
+        .unwrap();
+
+        // The log reg weights are reused, the probabilities are simply computed differently
+        let log_reg_model_file =
+            File::open(log_reg_classifier_path.join("intent_classifier.json")).unwrap();
+        let mut model: serde_json::Value = serde_json::from_reader(log_reg_model_file).unwrap();
+        model["output"] = output;
+        serde_json::to_writer(
+            File::create(classifier_dir.path().join("intent_classifier.json")).unwrap(),
+            &model,
+        )
+        .unwrap();
+        serde_json::to_writer(
+            File::create(classifier_dir.path().join("metadata.json")).unwrap(),
+            &json!({"unit_name": "linear_intent_classifier"}),
+        )
+        .unwrap();
+        classifier_dir
+    }
+
+    #[test]
+    fn test_load_softmax_classifier_from_path() {
+        // Given
+        let trained_engine_dir = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage");
+        let resources = load_engine_shared_resources(trained_engine_dir).unwrap();
+        let classifier_dir = write_linear_classifier(json!({"type": "softmax"}));
+
+        // When
+        let intent_classifier = build_intent_classifier(classifier_dir.path(), resources).unwrap();
+        let intents = intent_classifier
+            .get_intents("Make me one cup of tea please")
+            .unwrap();
+
+        // Then
+        assert_eq!(Some("MakeTea".to_string()), intents[0].intent_name);
+        let total_probability: f32 = intents.iter().map(|res| res.confidence_score).sum();
+        assert!((total_probability - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_load_platt_scaled_classifier_from_path() {
+        // Given
+        let trained_engine_dir = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage");
+        let resources = load_engine_shared_resources(trained_engine_dir).unwrap();
+        let classifier_dir = write_linear_classifier(json!({
+            "type": "platt_scaling",
+            "a": [-1.5, -1.5, -1.5],
+            "b": [0.1, 0.1, 0.1]
+        }));
+
+        // When
+        let intent_classifier =
+            LinearIntentClassifier::from_path(classifier_dir.path(), resources).unwrap();
+        let intent_result = intent_classifier
+            .get_intent("Make me one cup of tea please", Some(&["MakeCoffee"][..]))
+            .unwrap();
+
+        // Then
+        assert_eq!(Some("MakeCoffee".to_string()), intent_result.intent_name);
+    }
+
+    #[test]
+    fn test_load_classifier_with_invalid_platt_parameters_fails() {
+        // Given
+        let trained_engine_dir = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage");
+        let resources = load_engine_shared_resources(trained_engine_dir).unwrap();
+        let classifier_dir = write_linear_classifier(json!({
+            "type": "platt_scaling",
+            "a": [-1.5],
+            "b": [0.1]
+        }));
+
+        // When
+        let intent_classifier = LinearIntentClassifier::from_path(classifier_dir.path(), resources);
+
+        // Then
+        assert!(intent_classifier.is_err());
+    }
+}
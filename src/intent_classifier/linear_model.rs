@@ -0,0 +1,168 @@
+use failure::bail;
+use ndarray::prelude::*;
+
+use super::featurizer::SparseFeatures;
+use super::logreg::{decision_function, stack_intercept};
+use crate::errors::*;
+use crate::models::LinearClassifierOutput;
+use crate::utils::check_finite_values;
+
+/// Multiclass linear model, such as a multinomial logistic regression or a linear SVM, whose
+/// scores are turned into probabilities according to its `LinearClassifierOutput`
+pub struct MulticlassLinearModel {
+    /// matrix with shape (f + 1, c), the first row being the intercept
+    /// ----------------------------------------------------------------
+    ///
+    /// - f = number of features
+    /// - c = number of classes
+    weights: Array2<f32>,
+    output: LinearClassifierOutput,
+}
+
+impl MulticlassLinearModel {
+    pub fn new(
+        intercept: Array1<f32>,
+        weights: Array2<f32>,
+        output: LinearClassifierOutput,
+    ) -> Result<Self> {
+        let nb_classes = intercept.dim();
+        if let LinearClassifierOutput::PlattScaling { ref a, ref b } = output {
+            check_finite_values(a.iter(), "a")?;
+            check_finite_values(b.iter(), "b")?;
+            if a.len() != nb_classes || b.len() != nb_classes {
+                bail!(
+                    "Invalid Platt scaling parameters: expected {} values but found {} and {}",
+                    nb_classes,
+                    a.len(),
+                    b.len()
+                );
+            }
+        }
+        Ok(Self {
+            weights: stack_intercept(intercept, weights)?,
+            output,
+        })
+    }
+
+    pub fn nb_classes(&self) -> usize {
+        self.weights.dim().1
+    }
+
+    pub fn run(&self, features: &SparseFeatures) -> Result<Array1<f32>> {
+        let scores = decision_function(&self.weights, features)?;
+        let probabilities = match self.output {
+            LinearClassifierOutput::Softmax => softmax(scores),
+            LinearClassifierOutput::PlattScaling { ref a, ref b } => {
+                let mut probabilities = Array::from_iter(
+                    scores
+                        .iter()
+                        .zip(a.iter().zip(b.iter()))
+                        .map(|(score, (a, b))| 1. / (1. + (a * score + b).exp())),
+                );
+                let total: f32 = probabilities.sum();
+                if total > 0. {
+                    probabilities /= total;
+                }
+                probabilities
+            }
+        };
+        Ok(probabilities)
+    }
+}
+
+fn softmax(mut scores: Array1<f32>) -> Array1<f32> {
+    let max_score = scores.fold(std::f32::NEG_INFINITY, |max, score| max.max(*score));
+    scores.mapv_inplace(|score| (score - max_score).exp());
+    let total: f32 = scores.sum();
+    scores / total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::assert_epsilon_eq_array1;
+    use ndarray::array;
+
+    #[test]
+    fn test_softmax_linear_model() {
+        // Given
+        let intercept = array![0.98, 0.32, -0.76];
+        let weights = array![
+            [2.5, -0.6, 0.5],
+            [1.2, 1.2, -2.7],
+            [1.5, 0.1, -3.2],
+            [-0.9, 1.4, 1.8]
+        ];
+        let features = SparseFeatures::from_dense(&array![0.4, -2.3, 1.9, 1.3].view());
+        let model = MulticlassLinearModel::new(intercept, weights, LinearClassifierOutput::Softmax)
+            .unwrap();
+
+        // When
+        let probabilities = model.run(&features).unwrap();
+
+        // Then
+        // scores are [0.9, -0.67, 1.91]
+        let expected_probabilities = array![0.2529311, 0.0526211, 0.6944478];
+        assert_epsilon_eq_array1(&probabilities, &expected_probabilities, 1e-06);
+    }
+
+    #[test]
+    fn test_platt_scaled_linear_model() {
+        // Given
+        let intercept = array![0.98, 0.32, -0.76];
+        let weights = array![
+            [2.5, -0.6, 0.5],
+            [1.2, 1.2, -2.7],
+            [1.5, 0.1, -3.2],
+            [-0.9, 1.4, 1.8]
+        ];
+        let features = SparseFeatures::from_dense(&array![0.4, -2.3, 1.9, 1.3].view());
+        let output = LinearClassifierOutput::PlattScaling {
+            a: vec![-1.0, -2.0, -1.0],
+            b: vec![0.0, 0.5, 0.0],
+        };
+        let model = MulticlassLinearModel::new(intercept, weights, output).unwrap();
+
+        // When
+        let probabilities = model.run(&features).unwrap();
+
+        // Then
+        // sigmoids are [0.7109495, 0.1370513, 0.8710191]
+        let expected_probabilities = array![0.4135784, 0.0797264, 0.5066952];
+        assert_epsilon_eq_array1(&probabilities, &expected_probabilities, 1e-06);
+    }
+
+    #[test]
+    fn test_linear_model_fails_with_invalid_platt_parameters() {
+        // Given
+        let intercept = array![0.98, 0.32];
+        let weights = array![[2.5, -0.6], [1.2, 1.2]];
+        let output = LinearClassifierOutput::PlattScaling {
+            a: vec![-1.0],
+            b: vec![0.0],
+        };
+
+        // When
+        let model = MulticlassLinearModel::new(intercept, weights, output);
+
+        // Then
+        assert!(model.is_err());
+    }
+
+    #[test]
+    fn test_linear_model_fails_with_non_finite_platt_parameters() {
+        // Given
+        let intercept = array![0.98, 0.32];
+        let weights = array![[2.5, -0.6], [1.2, 1.2]];
+        let output = LinearClassifierOutput::PlattScaling {
+            a: vec![-1.0, std::f32::NAN],
+            b: vec![0.0, 0.0],
+        };
+
+        // When
+        let model = MulticlassLinearModel::new(intercept, weights, output);
+
+        // Then
+        assert!(model.is_err());
+    }
+}
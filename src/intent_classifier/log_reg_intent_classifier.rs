@@ -1,27 +1,79 @@
 use std::collections::HashSet;
+use std::fmt;
 use std::fs::File;
 use std::iter::FromIterator;
 use std::path::Path;
 use std::sync::Arc;
 
-use failure::ResultExt;
+use failure::{format_err, ResultExt};
 use itertools::Itertools;
 use log::{debug, info};
 use ndarray::prelude::*;
-use snips_nlu_ontology::IntentClassifierResult;
+use serde::Serialize;
+use snips_nlu_ontology::{IntentClassifierResult, Slot};
 
 use crate::errors::*;
-use crate::intent_classifier::{Featurizer, IntentClassifier};
-use crate::models::IntentClassifierModel;
+use crate::intent_classifier::{FeatureKind, Featurizer, IntentClassifier};
+use crate::models::{IntentClassifierModel, OutOfScopeDetectionConfig};
 use crate::resources::SharedResources;
-use crate::utils::IntentName;
+use crate::spelling_correction::Vocabulary;
+use crate::utils::{cmp_scores, IntentName};
 
-use super::logreg::MulticlassLogisticRegression;
+use super::logreg::{transpose_coeffs, MulticlassLogisticRegression};
 
 pub struct LogRegIntentClassifier {
     intent_list: Vec<Option<IntentName>>,
     featurizer: Option<Featurizer>,
     logreg: Option<MulticlassLogisticRegression>,
+    out_of_scope_detection: Option<OutOfScopeDetectionConfig>,
+}
+
+/// Contribution of an active feature to the score of an intent
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureContribution {
+    pub kind: FeatureKind,
+    pub name: String,
+    pub value: f32,
+    pub weight: f32,
+    /// Product of the feature value and of its weight
+    pub contribution: f32,
+}
+
+/// Reason why an input has been considered out of scope
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum OutOfScopeReason {
+    #[serde(rename_all = "camelCase")]
+    UnknownWordsRatio { ratio: f32, max_ratio: f32 },
+    /// Margin between the scores of the two best intents of the whitelist, the None intent
+    /// excluded
+    #[serde(rename_all = "camelCase")]
+    ScoreMargin { margin: f32, min_margin: f32 },
+}
+
+/// Parsing result along with the reason why the input has been considered out of scope, if it has
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopedIntentParserResult {
+    pub input: String,
+    pub intent: IntentClassifierResult,
+    pub slots: Vec<Slot>,
+    pub out_of_scope_reason: Option<OutOfScopeReason>,
+}
+
+impl fmt::Display for OutOfScopeReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OutOfScopeReason::UnknownWordsRatio { ratio, max_ratio } => {
+                write!(f, "ratio of unknown words {} is above {}", ratio, max_ratio)
+            }
+            OutOfScopeReason::ScoreMargin { margin, min_margin } => write!(
+                f,
+                "margin between the two best intents {} is below {}",
+                margin, min_margin
+            ),
+        }
+    }
 }
 
 impl LogRegIntentClassifier {
@@ -52,11 +104,7 @@ impl LogRegIntentClassifier {
 
         let logreg = if let (Some(intercept), Some(coeffs)) = (model.intercept, model.coeffs) {
             let arr_intercept = Array::from_vec(intercept);
-            let nb_classes = arr_intercept.dim();
-            let nb_features = coeffs[0].len();
-            // Note: the deserialized coeffs matrix is transposed
-            let arr_weights =
-                Array::from_shape_fn((nb_features, nb_classes), |(i, j)| coeffs[j][i]);
+            let arr_weights = transpose_coeffs(&coeffs, arr_intercept.dim())?;
             MulticlassLogisticRegression::new(arr_intercept, arr_weights).map(Some)
         } else {
             Ok(None)
@@ -68,8 +116,19 @@ impl LogRegIntentClassifier {
             intent_list: model.intent_list,
             featurizer,
             logreg,
+            out_of_scope_detection: model.out_of_scope_detection,
         })
     }
+
+    /// Overrides the out-of-scope detection limits found in the model, `None` disables the
+    /// detection
+    pub fn with_out_of_scope_detection(
+        mut self,
+        config: Option<OutOfScopeDetectionConfig>,
+    ) -> Self {
+        self.out_of_scope_detection = config;
+        self
+    }
 }
 
 impl IntentClassifier for LogRegIntentClassifier {
@@ -78,8 +137,23 @@ impl IntentClassifier for LogRegIntentClassifier {
         input: &str,
         intents_whitelist: Option<&[&str]>,
     ) -> Result<IntentClassifierResult> {
+        self.get_intent_with_out_of_scope_reason(input, intents_whitelist)
+            .map(|(intent_result, _)| intent_result)
+    }
+
+    fn get_intents(&self, input: &str) -> Result<Vec<IntentClassifierResult>> {
+        self.get_intents_with_whitelist(input, None)
+            .map(|(intents_results, _)| intents_results)
+    }
+
+    fn get_intent_with_out_of_scope_reason(
+        &self,
+        input: &str,
+        intents_whitelist: Option<&[&str]>,
+    ) -> Result<(IntentClassifierResult, Option<OutOfScopeReason>)> {
         debug!("Classifying intent...");
-        let intents_results = self.get_intents_with_whitelist(input, intents_whitelist)?;
+        let (intents_results, out_of_scope_reason) =
+            self.get_intents_with_whitelist(input, intents_whitelist)?;
         let intent_result = if intents_results.is_empty() {
             IntentClassifierResult {
                 intent_name: None,
@@ -89,11 +163,14 @@ impl IntentClassifier for LogRegIntentClassifier {
             intents_results.into_iter().next().unwrap()
         };
         debug!("Intent found: '{:?}'", intent_result.intent_name);
-        Ok(intent_result)
+        Ok((intent_result, out_of_scope_reason))
     }
 
-    fn get_intents(&self, input: &str) -> Result<Vec<IntentClassifierResult>> {
-        self.get_intents_with_whitelist(input, None)
+    fn vocabulary(&self) -> Vocabulary {
+        self.featurizer
+            .as_ref()
+            .map(|featurizer| featurizer.vocabulary())
+            .unwrap_or_default()
     }
 }
 
@@ -102,36 +179,53 @@ impl LogRegIntentClassifier {
         &self,
         input: &str,
         intents_whitelist: Option<&[&str]>,
-    ) -> Result<Vec<IntentClassifierResult>> {
+    ) -> Result<(Vec<IntentClassifierResult>, Option<OutOfScopeReason>)> {
         if self.intent_list.len() <= 1 {
-            return Ok(vec![IntentClassifierResult {
+            let intent_result = IntentClassifierResult {
                 intent_name: self.intent_list.first().cloned().unwrap_or(None),
                 confidence_score: 1.0,
-            }]);
-        }
-
-        if input.is_empty() || self.featurizer.is_none() || self.logreg.is_none() {
-            return Ok(self
-                .intent_list
-                .iter()
-                .map(|intent_name| IntentClassifierResult {
-                    intent_name: intent_name.clone(),
-                    confidence_score: if intent_name.is_none() { 1.0 } else { 0.0 },
-                })
-                .sorted_by(|a, b| b.confidence_score.partial_cmp(&a.confidence_score).unwrap())
-                .collect());
+            };
+            return Ok((vec![intent_result], None));
         }
 
         let opt_intents_set: Option<HashSet<&str>> =
             intents_whitelist.map(|intent_list| HashSet::from_iter(intent_list.iter().cloned()));
 
+        let is_whitelisted = |intent_name: &Option<IntentName>| {
+            intent_name
+                .as_ref()
+                .map(|intent| {
+                    opt_intents_set
+                        .as_ref()
+                        .map(|intents| intents.contains(&**intent))
+                        .unwrap_or(true)
+                })
+                .unwrap_or(true)
+        };
+
+        if input.is_empty() || self.featurizer.is_none() || self.logreg.is_none() {
+            return Ok((self.get_none_intent_results(&is_whitelisted), None));
+        }
+
         let featurizer = self.featurizer.as_ref().unwrap(); // Checked above
         let logreg = self.logreg.as_ref().unwrap(); // Checked above
 
         let features = featurizer.transform(input)?;
-        let scores = logreg.run(&features.view())?;
+        let scores = logreg.run(&features)?;
 
-        Ok(self
+        let whitelisted_scores = self
+            .intent_list
+            .iter()
+            .zip(scores.iter())
+            .filter(|(intent_name, _)| intent_name.is_some() && is_whitelisted(*intent_name))
+            .map(|(_, score)| *score)
+            .collect::<Vec<_>>();
+        if let Some(reason) = self.detect_out_of_scope(featurizer, input, whitelisted_scores) {
+            debug!("Input is out of scope: {}", reason);
+            return Ok((self.get_none_intent_results(&is_whitelisted), Some(reason)));
+        }
+
+        let intents_results = self
             .intent_list
             .iter()
             .zip(scores.into_iter())
@@ -139,26 +233,100 @@ impl LogRegIntentClassifier {
                 intent_name: intent_name.clone(),
                 confidence_score: *score,
             })
-            .filter(|res| {
-                if let Some(intent) = res.intent_name.as_ref() {
-                    opt_intents_set
-                        .as_ref()
-                        .map(|intents| intents.contains(&**intent))
-                        .unwrap_or(true)
-                } else {
-                    true
+            .filter(|res| is_whitelisted(&res.intent_name))
+            .sorted_by(|a, b| cmp_scores(b.confidence_score, a.confidence_score))
+            .collect();
+        Ok((intents_results, None))
+    }
+}
+
+impl LogRegIntentClassifier {
+    /// Results of an input which is out of scope, restricted to the whitelisted intents
+    fn get_none_intent_results<F>(&self, is_whitelisted: F) -> Vec<IntentClassifierResult>
+    where
+        F: Fn(&Option<IntentName>) -> bool,
+    {
+        self.intent_list
+            .iter()
+            .filter(|intent_name| is_whitelisted(intent_name))
+            .map(|intent_name| IntentClassifierResult {
+                intent_name: intent_name.clone(),
+                confidence_score: if intent_name.is_none() { 1.0 } else { 0.0 },
+            })
+            .sorted_by(|a, b| cmp_scores(b.confidence_score, a.confidence_score))
+            .collect()
+    }
+
+    fn detect_out_of_scope(
+        &self,
+        featurizer: &Featurizer,
+        input: &str,
+        whitelisted_scores: Vec<f32>,
+    ) -> Option<OutOfScopeReason> {
+        let config = self.out_of_scope_detection?;
+        if let Some(max_ratio) = config.max_unknown_words_ratio {
+            let ratio = featurizer.unknown_words_ratio(input);
+            if ratio > max_ratio {
+                return Some(OutOfScopeReason::UnknownWordsRatio { ratio, max_ratio });
+            }
+        }
+        if let Some(min_margin) = config.min_score_margin {
+            let sorted_scores: Vec<f32> = whitelisted_scores
+                .into_iter()
+                .sorted_by(|a, b| cmp_scores(*b, *a))
+                .collect();
+            if sorted_scores.len() >= 2 {
+                let margin = sorted_scores[0] - sorted_scores[1];
+                if margin < min_margin {
+                    return Some(OutOfScopeReason::ScoreMargin { margin, min_margin });
                 }
+            }
+        }
+        None
+    }
+
+    /// Explains the score of an intent, `None` standing for the None intent, by listing the
+    /// contributions of the features active in the input, by decreasing absolute contribution
+    pub fn explain(&self, input: &str, intent: Option<&str>) -> Result<Vec<FeatureContribution>> {
+        let class_index = self
+            .intent_list
+            .iter()
+            .position(|intent_name| intent_name.as_ref().map(|name| &**name) == intent)
+            .ok_or_else(|| SnipsNluError::UnknownIntent(intent.unwrap_or("None").to_string()))?;
+        let (featurizer, logreg) = match (self.featurizer.as_ref(), self.logreg.as_ref()) {
+            (Some(featurizer), Some(logreg)) => (featurizer, logreg),
+            _ => return Ok(vec![]),
+        };
+        let features = featurizer.transform(input)?;
+        Ok(features
+            .iter()
+            .map(|(index, value)| {
+                let (kind, name) = featurizer
+                    .feature_name(index)
+                    .ok_or_else(|| format_err!("Unknown feature at index {}", index))?;
+                let weight = logreg.feature_weight(index, class_index);
+                Ok(FeatureContribution {
+                    kind,
+                    name: name.to_string(),
+                    value,
+                    weight,
+                    contribution: value * weight,
+                })
             })
-            .sorted_by(|a, b| b.confidence_score.partial_cmp(&a.confidence_score).unwrap())
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .sorted_by(|a, b| cmp_scores(b.contribution.abs(), a.contribution.abs()))
             .collect())
     }
-}
 
-impl LogRegIntentClassifier {
     pub fn compute_features(&self, input: &str) -> Result<Array1<f32>> {
         self.featurizer
             .as_ref()
-            .map(|featurizer| featurizer.transform(input))
+            .map(|featurizer| {
+                featurizer
+                    .transform(input)
+                    .map(|features| features.to_dense())
+            })
             .unwrap_or_else(|| Ok(Array::from_iter(vec![])))
     }
 }
@@ -319,6 +487,7 @@ mod tests {
             featurizer: Some(featurizer),
             intent_list,
             logreg: Some(logreg),
+            out_of_scope_detection: None,
         }
     }
 
@@ -415,4 +584,138 @@ mod tests {
         assert_eq!(Some("MakeCoffee".to_string()), result2.intent_name);
         assert_eq!(None, result3.intent_name);
     }
+
+    #[test]
+    fn test_out_of_scope_detection_with_unknown_words() {
+        // Given
+        let config = OutOfScopeDetectionConfig {
+            max_unknown_words_ratio: Some(0.5),
+            min_score_margin: None,
+        };
+        let classifier = get_sample_log_reg_classifier().with_out_of_scope_detection(Some(config));
+
+        // When
+        let in_scope_result = classifier
+            .get_intent("Make me two cups of tea", None)
+            .unwrap();
+        let (out_of_scope_result, reason) = classifier
+            .get_intent_with_out_of_scope_reason("make me a sandwich with cheese", None)
+            .unwrap();
+        let (whitelisted_results, _) = classifier
+            .get_intents_with_whitelist("make me a sandwich with cheese", Some(&["MakeTea"]))
+            .unwrap();
+
+        // Then
+        assert_eq!(Some("MakeTea".to_string()), in_scope_result.intent_name);
+        let expected_result = IntentClassifierResult {
+            intent_name: None,
+            confidence_score: 1.0,
+        };
+        assert_eq!(expected_result, out_of_scope_result);
+        let expected_whitelisted_results = vec![
+            IntentClassifierResult {
+                intent_name: None,
+                confidence_score: 1.0,
+            },
+            IntentClassifierResult {
+                intent_name: Some("MakeTea".to_string()),
+                confidence_score: 0.0,
+            },
+        ];
+        assert_eq!(expected_whitelisted_results, whitelisted_results);
+        let expected_reason = OutOfScopeReason::UnknownWordsRatio {
+            ratio: 4. / 6.,
+            max_ratio: 0.5,
+        };
+        assert_eq!(Some(expected_reason), reason);
+    }
+
+    #[test]
+    fn test_out_of_scope_detection_with_score_margin() {
+        // Given
+        let config = OutOfScopeDetectionConfig {
+            max_unknown_words_ratio: None,
+            min_score_margin: Some(0.99),
+        };
+        let classifier = get_sample_log_reg_classifier().with_out_of_scope_detection(Some(config));
+
+        // When
+        let input = "Make me two cups of tea";
+        let (result, reason) = classifier
+            .get_intent_with_out_of_scope_reason(input, None)
+            .unwrap();
+        let (whitelisted_result, whitelisted_reason) = classifier
+            .get_intent_with_out_of_scope_reason(input, Some(&["MakeTea"]))
+            .unwrap();
+
+        // Then
+        assert_eq!(None, result.intent_name);
+        match reason {
+            Some(OutOfScopeReason::ScoreMargin { min_margin, .. }) => assert_eq!(0.99, min_margin),
+            _ => panic!(
+                "Expected a score margin out-of-scope reason, found {:?}",
+                reason
+            ),
+        }
+        assert_eq!(Some("MakeTea".to_string()), whitelisted_result.intent_name);
+        assert_eq!(None, whitelisted_reason);
+    }
+
+    #[test]
+    fn test_explain() {
+        // Given
+        let classifier = get_sample_log_reg_classifier();
+        let input = "Make me two cups of tea";
+
+        // When
+        let intent_result = classifier.get_intent(input, None).unwrap();
+        let contributions = classifier.explain(input, Some("MakeTea")).unwrap();
+
+        // Then
+        let feature_names: HashSet<&str> = contributions
+            .iter()
+            .map(|contribution| &*contribution.name)
+            .collect();
+        let possible_feature_names: HashSet<&str> = vec![
+            "make",
+            "me",
+            "cups",
+            "tea",
+            "builtinentityfeaturesnipsnumber",
+        ]
+        .into_iter()
+        .collect();
+        assert!(feature_names.is_subset(&possible_feature_names));
+        for name in &["make", "me", "cups", "tea"] {
+            assert!(feature_names.contains(name));
+        }
+        assert!(contributions
+            .iter()
+            .all(|contribution| contribution.kind == FeatureKind::Tfidf));
+        assert!(contributions
+            .windows(2)
+            .all(|pair| { pair[0].contribution.abs() >= pair[1].contribution.abs() }));
+
+        // The contributions and the intercept add up to the logit of the intent score
+        let intercept = -0.08753256;
+        let logit: f32 = intercept
+            + contributions
+                .iter()
+                .map(|contribution| contribution.contribution)
+                .sum::<f32>();
+        let score = intent_result.confidence_score;
+        assert!((logit - (score / (1. - score)).ln()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_explain_unknown_intent_fails() {
+        // Given
+        let classifier = get_sample_log_reg_classifier();
+
+        // When
+        let contributions = classifier.explain("Make me two cups of tea", Some("MakePizza"));
+
+        // Then
+        assert!(contributions.is_err());
+    }
 }
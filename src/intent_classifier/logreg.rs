@@ -1,7 +1,10 @@
+use failure::bail;
 use ndarray::prelude::*;
-use ndarray::{array, stack};
+use ndarray::stack;
 
+use super::featurizer::SparseFeatures;
 use crate::errors::*;
+use crate::utils::check_finite_values;
 
 /// The multiclass probability estimates are derived from binary (one-vs.-rest)
 /// estimates by simple normalization
@@ -15,11 +18,6 @@ pub struct MulticlassLogisticRegression {
 }
 
 impl MulticlassLogisticRegression {
-    fn nb_features(&self) -> usize {
-        // without intercept
-        self.weights.dim().0 - 1
-    }
-
     fn nb_classes(&self) -> usize {
         self.weights.dim().1
     }
@@ -27,24 +25,28 @@ impl MulticlassLogisticRegression {
     fn is_binary(&self) -> bool {
         self.nb_classes() == 1
     }
+
+    /// Weight of a feature in the score of the provided class, as indexed in the probabilities
+    /// returned by `run`
+    pub fn feature_weight(&self, feature_index: usize, class_index: usize) -> f32 {
+        if self.is_binary() {
+            // The single column holds the weights of the second class
+            let weight = self.weights[[feature_index + 1, 0]];
+            return if class_index == 0 { -weight } else { weight };
+        }
+        self.weights[[feature_index + 1, class_index]]
+    }
 }
 
 impl MulticlassLogisticRegression {
     pub fn new(intercept: Array1<f32>, weights: Array2<f32>) -> Result<Self> {
-        let nb_classes = intercept.dim();
-        let reshaped_intercept = intercept.into_shape((1, nb_classes))?;
-        let weights_with_intercept = stack![Axis(0), reshaped_intercept, weights];
         Ok(Self {
-            weights: weights_with_intercept,
+            weights: stack_intercept(intercept, weights)?,
         })
     }
 
-    pub fn run(&self, features: &ArrayView1<f32>) -> Result<Array1<f32>> {
-        let reshaped_features = features.into_shape((1, self.nb_features()))?;
-        let reshaped_features = stack![Axis(1), array![[1.]], reshaped_features];
-        let mut result = reshaped_features
-            .dot(&self.weights)
-            .into_shape(self.nb_classes())?;
+    pub fn run(&self, features: &SparseFeatures) -> Result<Array1<f32>> {
+        let mut result = decision_function(&self.weights, features)?;
         result.mapv_inplace(logit);
         if self.is_binary() {
             return Ok(arr1(&[1.0 - result[0], result[0]]));
@@ -53,6 +55,59 @@ impl MulticlassLogisticRegression {
     }
 }
 
+/// Builds the weights matrix with shape (f, c) from the deserialized coefficients, which are
+/// transposed and thus hold one row of f values per class
+pub fn transpose_coeffs(coeffs: &[Vec<f32>], nb_classes: usize) -> Result<Array2<f32>> {
+    if coeffs.is_empty() || coeffs.len() != nb_classes {
+        return Err(SnipsNluError::InvalidModel(format!(
+            "found {} rows of coeffs for {} classes",
+            coeffs.len(),
+            nb_classes
+        ))
+        .into());
+    }
+    let nb_features = coeffs[0].len();
+    if coeffs.iter().any(|row| row.len() != nb_features) {
+        return Err(SnipsNluError::InvalidModel(
+            "the rows of coeffs have different lengths".to_string(),
+        )
+        .into());
+    }
+    Ok(Array::from_shape_fn((nb_features, nb_classes), |(i, j)| {
+        coeffs[j][i]
+    }))
+}
+
+/// Builds the weights matrix with shape (f + 1, c) whose first row is the intercept
+pub fn stack_intercept(intercept: Array1<f32>, weights: Array2<f32>) -> Result<Array2<f32>> {
+    check_finite_values(intercept.iter(), "intercept")?;
+    check_finite_values(weights.iter(), "coeffs")?;
+    let nb_classes = intercept.dim();
+    let reshaped_intercept = intercept.into_shape((1, nb_classes))?;
+    Ok(stack![Axis(0), reshaped_intercept, weights])
+}
+
+/// Computes the scores of a linear model whose weights were built with `stack_intercept`
+pub fn decision_function(
+    weights_with_intercept: &Array2<f32>,
+    features: &SparseFeatures,
+) -> Result<Array1<f32>> {
+    let nb_features = weights_with_intercept.dim().0 - 1;
+    if features.dim() != nb_features {
+        bail!(
+            "Invalid number of features: expected {} but found {}",
+            nb_features,
+            features.dim()
+        );
+    }
+    // Only the weights of the non-zero features contribute to the scores
+    let mut result = weights_with_intercept.row(0).to_owned();
+    for (index, value) in features.iter() {
+        result.scaled_add(value, &weights_with_intercept.row(index + 1));
+    }
+    Ok(result)
+}
+
 fn logit(x: f32) -> f32 {
     1. / (1. + (-x).exp())
 }
@@ -60,6 +115,7 @@ fn logit(x: f32) -> f32 {
 #[cfg(test)]
 mod tests {
     use super::MulticlassLogisticRegression;
+    use crate::intent_classifier::SparseFeatures;
     use crate::testutils::assert_epsilon_eq_array1;
     use ndarray::array;
 
@@ -74,11 +130,11 @@ mod tests {
             [-0.9, 1.4, 1.8]
         ];
 
-        let features = array![0.4, -2.3, 1.9, 1.3];
+        let features = SparseFeatures::from_dense(&array![0.4, -2.3, 1.9, 1.3].view());
         let regression = MulticlassLogisticRegression::new(intercept, weights).unwrap();
 
         // When
-        let predictions = regression.run(&features.view()).unwrap();
+        let predictions = regression.run(&features).unwrap();
 
         // Then
         let expected_predictions = array![0.7109495, 0.3384968, 0.8710191];
@@ -91,14 +147,90 @@ mod tests {
         let intercept = array![0.98];
         let weights = array![[2.5], [1.2], [1.5], [-0.9]];
 
-        let features = array![0.4, -2.3, 1.9, 1.3];
+        let features = SparseFeatures::from_dense(&array![0.4, -2.3, 1.9, 1.3].view());
         let regression = MulticlassLogisticRegression::new(intercept, weights).unwrap();
 
         // When
-        let predictions = regression.run(&features.view()).unwrap();
+        let predictions = regression.run(&features).unwrap();
 
         // Then
         let expected_predictions = array![0.2890504, 0.7109495];
         assert_epsilon_eq_array1(&predictions, &expected_predictions, 1e-06);
     }
+
+    #[test]
+    fn test_multiclass_logistic_regression_with_sparse_features() {
+        // Given
+        let intercept = array![0.98, 0.32, -0.76];
+        let weights = array![
+            [2.5, -0.6, 0.5],
+            [1.2, 1.2, -2.7],
+            [1.5, 0.1, -3.2],
+            [-0.9, 1.4, 1.8]
+        ];
+
+        let dense_features = array![0.0, -2.3, 0.0, 1.3];
+        let features = SparseFeatures::from_dense(&dense_features.view());
+        let regression = MulticlassLogisticRegression::new(intercept, weights.clone()).unwrap();
+
+        // When
+        let predictions = regression.run(&features).unwrap();
+
+        // Then
+        let mut expected_predictions = dense_features.dot(&weights) + array![0.98, 0.32, -0.76];
+        expected_predictions.mapv_inplace(|x| 1. / (1. + (-x).exp()));
+        assert_epsilon_eq_array1(&predictions, &expected_predictions, 1e-06);
+    }
+
+    #[test]
+    fn test_multiclass_logistic_regression_fails_with_wrong_number_of_features() {
+        // Given
+        let intercept = array![0.98];
+        let weights = array![[2.5], [1.2], [1.5], [-0.9]];
+
+        let features = SparseFeatures::from_dense(&array![0.4, -2.3].view());
+        let regression = MulticlassLogisticRegression::new(intercept, weights).unwrap();
+
+        // When
+        let predictions = regression.run(&features);
+
+        // Then
+        assert!(predictions.is_err());
+    }
+
+    #[test]
+    fn test_multiclass_logistic_regression_fails_with_non_finite_weights() {
+        // Given
+        let intercept = array![0.98, std::f32::NAN];
+        let weights = array![[2.5, -0.6], [1.2, 1.2]];
+        let infinite_weights = array![[2.5, -0.6], [std::f32::INFINITY, 1.2]];
+
+        // When
+        let nan_regression = MulticlassLogisticRegression::new(intercept, weights.clone());
+        let infinite_regression =
+            MulticlassLogisticRegression::new(array![0.98, 0.32], infinite_weights);
+
+        // Then
+        assert!(nan_regression.is_err());
+        assert!(infinite_regression.is_err());
+    }
+
+    #[test]
+    fn test_transpose_coeffs() {
+        // Given
+        let coeffs = vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]];
+        let ragged_coeffs = vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0]];
+
+        // When
+        let weights = transpose_coeffs(&coeffs, 2).unwrap();
+        let ragged_weights = transpose_coeffs(&ragged_coeffs, 2);
+        let empty_weights = transpose_coeffs(&[], 0);
+        let mismatched_weights = transpose_coeffs(&coeffs, 3);
+
+        // Then
+        assert_eq!(array![[1.0, 4.0], [2.0, 5.0], [3.0, 6.0]], weights);
+        assert!(ragged_weights.is_err());
+        assert!(empty_weights.is_err());
+        assert!(mismatched_weights.is_err());
+    }
 }
@@ -1,4 +1,6 @@
 mod featurizer;
+mod linear_intent_classifier;
+mod linear_model;
 mod log_reg_intent_classifier;
 mod logreg;
 
@@ -10,10 +12,16 @@ use crate::errors::*;
 use failure::{format_err, ResultExt};
 use snips_nlu_ontology::IntentClassifierResult;
 
-pub use self::featurizer::{CooccurrenceVectorizer, Featurizer, TfidfVectorizer};
-pub use self::log_reg_intent_classifier::LogRegIntentClassifier;
+pub use self::featurizer::{
+    CooccurrenceVectorizer, FeatureKind, Featurizer, SparseFeatures, TfidfVectorizer,
+};
+pub use self::linear_intent_classifier::LinearIntentClassifier;
+pub use self::log_reg_intent_classifier::{
+    FeatureContribution, LogRegIntentClassifier, OutOfScopeReason, ScopedIntentParserResult,
+};
 use crate::models::ProcessingUnitMetadata;
 use crate::resources::SharedResources;
+use crate::spelling_correction::Vocabulary;
 
 pub trait IntentClassifier: Send + Sync {
     fn get_intent(
@@ -23,6 +31,21 @@ pub trait IntentClassifier: Send + Sync {
     ) -> Result<IntentClassifierResult>;
 
     fn get_intents(&self, input: &str) -> Result<Vec<IntentClassifierResult>>;
+
+    /// Same as `get_intent`, but also returns the reason why the input has been considered out of
+    /// scope, if it has
+    fn get_intent_with_out_of_scope_reason(
+        &self,
+        input: &str,
+        intents_whitelist: Option<&[&str]>,
+    ) -> Result<(IntentClassifierResult, Option<OutOfScopeReason>)> {
+        Ok((self.get_intent(input, intents_whitelist)?, None))
+    }
+
+    /// Words known by the classifier, which are used to correct the spelling of the inputs
+    fn vocabulary(&self) -> Vocabulary {
+        Vocabulary::default()
+    }
 }
 
 pub fn build_intent_classifier<P: AsRef<Path>>(
@@ -42,6 +65,9 @@ pub fn build_intent_classifier<P: AsRef<Path>>(
         ProcessingUnitMetadata::LogRegIntentClassifier => {
             Ok(Box::new(LogRegIntentClassifier::from_path(path, shared_resources)?) as _)
         }
+        ProcessingUnitMetadata::LinearIntentClassifier => {
+            Ok(Box::new(LinearIntentClassifier::from_path(path, shared_resources)?) as _)
+        }
         _ => Err(format_err!("{:?} is not an intent classifier", metadata)),
     }
 }
@@ -0,0 +1,262 @@
+use std::fs::File;
+use std::path::Path;
+
+use failure::{bail, ResultExt};
+use snips_nlu_ontology::IntentClassifierResult;
+
+use crate::errors::*;
+use crate::intent_parser::{IntentParser, InternalParsingResult, InternalSlot};
+use crate::models::CalibrationModel;
+use crate::slot_filler::FeatureCache;
+use crate::spelling_correction::Vocabulary;
+use crate::utils::{check_finite_values, cmp_scores};
+
+const CALIBRATION_FILE_NAME: &str = "calibration.json";
+
+/// Maps the raw intent scores of a parser to calibrated probabilities
+pub trait Calibrator: Send + Sync {
+    fn calibrate(&self, score: f32) -> f32;
+}
+
+pub struct TemperatureScaling {
+    temperature: f32,
+}
+
+impl TemperatureScaling {
+    pub fn new(temperature: f32) -> Result<Self> {
+        check_finite_values(&[temperature], "temperature")?;
+        if temperature <= 0. {
+            bail!(
+                "Calibration temperature must be positive, found {}",
+                temperature
+            );
+        }
+        Ok(Self { temperature })
+    }
+}
+
+impl Calibrator for TemperatureScaling {
+    fn calibrate(&self, score: f32) -> f32 {
+        if score <= 0. || score >= 1. {
+            return score;
+        }
+        let logit = (score / (1. - score)).ln();
+        1. / (1. + (-logit / self.temperature).exp())
+    }
+}
+
+pub struct IsotonicCalibration {
+    thresholds: Vec<f32>,
+    values: Vec<f32>,
+}
+
+impl IsotonicCalibration {
+    pub fn new(thresholds: Vec<f32>, values: Vec<f32>) -> Result<Self> {
+        if thresholds.is_empty() || thresholds.len() != values.len() {
+            bail!(
+                "Isotonic calibration needs as many thresholds as values, found {} and {}",
+                thresholds.len(),
+                values.len()
+            );
+        }
+        check_finite_values(thresholds.iter(), "thresholds")?;
+        check_finite_values(values.iter(), "values")?;
+        if thresholds.windows(2).any(|pair| pair[0] >= pair[1]) {
+            bail!("Isotonic calibration thresholds must be strictly increasing");
+        }
+        Ok(Self { thresholds, values })
+    }
+}
+
+impl Calibrator for IsotonicCalibration {
+    fn calibrate(&self, score: f32) -> f32 {
+        let upper_index = self
+            .thresholds
+            .iter()
+            .position(|threshold| *threshold >= score);
+        match upper_index {
+            Some(0) => self.values[0],
+            Some(index) => {
+                let (low_threshold, high_threshold) =
+                    (self.thresholds[index - 1], self.thresholds[index]);
+                let (low_value, high_value) = (self.values[index - 1], self.values[index]);
+                let ratio = (score - low_threshold) / (high_threshold - low_threshold);
+                low_value + ratio * (high_value - low_value)
+            }
+            None => *self.values.last().unwrap(), // thresholds are not empty
+        }
+    }
+}
+
+pub fn build_calibrator(model: CalibrationModel) -> Result<Box<dyn Calibrator>> {
+    match model {
+        CalibrationModel::TemperatureScaling { temperature } => {
+            Ok(Box::new(TemperatureScaling::new(temperature)?) as _)
+        }
+        CalibrationModel::Isotonic { thresholds, values } => {
+            Ok(Box::new(IsotonicCalibration::new(thresholds, values)?) as _)
+        }
+    }
+}
+
+/// Loads the calibrator stored in the parser directory, if any
+pub fn load_calibrator<P: AsRef<Path>>(parser_dir: P) -> Result<Option<Box<dyn Calibrator>>> {
+    let calibration_path = parser_dir.as_ref().join(CALIBRATION_FILE_NAME);
+    if !calibration_path.exists() {
+        return Ok(None);
+    }
+    let calibration_file = File::open(&calibration_path)
+        .with_context(|_| format!("Cannot open calibration file '{:?}'", &calibration_path))?;
+    let model: CalibrationModel = serde_json::from_reader(calibration_file)
+        .with_context(|_| format!("Invalid calibration file '{:?}'", &calibration_path))?;
+    Ok(Some(build_calibrator(model)?))
+}
+
+/// Intent parser wrapper which calibrates the scores of the intents found by the wrapped parser
+///
+/// The score of the `None` intent is left untouched.
+pub struct CalibratedIntentParser {
+    parser: Box<dyn IntentParser>,
+    calibrator: Box<dyn Calibrator>,
+}
+
+impl CalibratedIntentParser {
+    pub fn new(parser: Box<dyn IntentParser>, calibrator: Box<dyn Calibrator>) -> Self {
+        Self { parser, calibrator }
+    }
+
+    fn calibrate_result(&self, result: &mut IntentClassifierResult) {
+        if result.intent_name.is_some() {
+            result.confidence_score = self.calibrator.calibrate(result.confidence_score);
+        }
+    }
+}
+
+impl IntentParser for CalibratedIntentParser {
+    fn parse(
+        &self,
+        input: &str,
+        intents_whitelist: Option<&[&str]>,
+    ) -> Result<InternalParsingResult> {
+        let mut result = self.parser.parse(input, intents_whitelist)?;
+        self.calibrate_result(&mut result.intent);
+        Ok(result)
+    }
+
+    fn get_intents(&self, input: &str) -> Result<Vec<IntentClassifierResult>> {
+        let mut results = self.parser.get_intents(input)?;
+        for result in results.iter_mut() {
+            self.calibrate_result(result);
+        }
+        results.sort_by(|a, b| cmp_scores(b.confidence_score, a.confidence_score));
+        Ok(results)
+    }
+
+    fn get_slots(&self, input: &str, intent: &str) -> Result<Vec<InternalSlot>> {
+        self.parser.get_slots(input, intent)
+    }
+
+    fn parse_with_feature_cache(
+        &self,
+        input: &str,
+        intents_whitelist: Option<&[&str]>,
+        feature_cache: &FeatureCache,
+    ) -> Result<InternalParsingResult> {
+        let mut result =
+            self.parser
+                .parse_with_feature_cache(input, intents_whitelist, feature_cache)?;
+        self.calibrate_result(&mut result.intent);
+        Ok(result)
+    }
+
+    fn get_slots_with_feature_cache(
+        &self,
+        input: &str,
+        intent: &str,
+        feature_cache: &FeatureCache,
+    ) -> Result<Vec<InternalSlot>> {
+        self.parser
+            .get_slots_with_feature_cache(input, intent, feature_cache)
+    }
+
+    fn vocabulary(&self) -> Vocabulary {
+        self.parser.vocabulary()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::epsilon_eq;
+
+    #[test]
+    fn test_temperature_scaling() {
+        // Given
+        let calibrator = TemperatureScaling::new(2.0).unwrap();
+
+        // When
+        let calibrated_scores: Vec<f32> = vec![0.0, 0.5, 0.9, 1.0]
+            .into_iter()
+            .map(|score| calibrator.calibrate(score))
+            .collect();
+
+        // Then
+        assert_eq!(0.0, calibrated_scores[0]);
+        assert!(epsilon_eq(0.5, calibrated_scores[1], 1e-6));
+        assert!(epsilon_eq(0.75, calibrated_scores[2], 1e-6));
+        assert_eq!(1.0, calibrated_scores[3]);
+    }
+
+    #[test]
+    fn test_isotonic_calibration() {
+        // Given
+        let calibrator =
+            IsotonicCalibration::new(vec![0.2, 0.6, 1.0], vec![0.1, 0.3, 0.9]).unwrap();
+
+        // When
+        let calibrated_scores: Vec<f32> = vec![0.0, 0.4, 0.6, 0.9, 1.0]
+            .into_iter()
+            .map(|score| calibrator.calibrate(score))
+            .collect();
+
+        // Then
+        let expected_scores = vec![0.1, 0.2, 0.3, 0.75, 0.9];
+        for (expected, actual) in expected_scores.into_iter().zip(calibrated_scores) {
+            assert!(epsilon_eq(expected, actual, 1e-6));
+        }
+    }
+
+    #[test]
+    fn test_build_calibrator_fails_with_invalid_model() {
+        // Given
+        let invalid_models = vec![
+            CalibrationModel::TemperatureScaling { temperature: 0.0 },
+            CalibrationModel::TemperatureScaling {
+                temperature: std::f32::INFINITY,
+            },
+            CalibrationModel::Isotonic {
+                thresholds: vec![0.2, std::f32::NAN],
+                values: vec![0.1, 0.3],
+            },
+            CalibrationModel::Isotonic {
+                thresholds: vec![0.2, 0.5],
+                values: vec![0.1, std::f32::INFINITY],
+            },
+            CalibrationModel::Isotonic {
+                thresholds: vec![0.5, 0.2],
+                values: vec![0.1, 0.3],
+            },
+            CalibrationModel::Isotonic {
+                thresholds: vec![0.2],
+                values: vec![0.1, 0.3],
+            },
+        ];
+
+        // When
+        let calibrators: Vec<Result<Box<dyn Calibrator>>> =
+            invalid_models.into_iter().map(build_calibrator).collect();
+
+        // Then
+        assert!(calibrators.iter().all(|calibrator| calibrator.is_err()));
+    }
+}
@@ -1,3 +1,4 @@
+use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::ops::Range;
@@ -6,8 +7,8 @@ use std::str::FromStr;
 use std::sync::Arc;
 
 use failure::{format_err, ResultExt};
-use log::{debug, info};
-use regex::{Regex, RegexBuilder};
+use log::{debug, info, warn};
+use regex::{Regex, RegexBuilder, RegexSet, RegexSetBuilder, SetMatches};
 use snips_nlu_ontology::{BuiltinEntityKind, IntentClassifierResult, Language};
 use snips_nlu_utils::language::Language as NluUtilsLanguage;
 use snips_nlu_utils::range::ranges_overlap;
@@ -19,9 +20,10 @@ use crate::language::FromLanguage;
 use crate::models::DeterministicParserModel;
 use crate::resources::SharedResources;
 use crate::slot_utils::*;
+use crate::spelling_correction::Vocabulary;
 use crate::utils::{
-    deduplicate_overlapping_items, replace_entities, EntityName, IntentName, MatchedEntity,
-    SlotName,
+    cmp_scores, deduplicate_overlapping_items, replace_entities, EntityName, IntentName,
+    MatchedEntity, SlotName,
 };
 
 use super::{internal_parsing_result, IntentParser, InternalParsingResult};
@@ -29,8 +31,12 @@ use itertools::Itertools;
 
 pub struct DeterministicIntentParser {
     language: Language,
-    regexes_per_intent: HashMap<IntentName, Vec<Regex>>,
+    /// Regexes of each intent, along with their index in `regex_set`
+    regexes_per_intent: HashMap<IntentName, Vec<(usize, Regex)>>,
+    /// Combination of all the intents regexes, used to only try the regexes which match
+    regex_set: Option<RegexSet>,
     group_names_to_slot_names: HashMap<String, SlotName>,
+    group_names_to_parent_group_names: HashMap<String, String>,
     slot_names_to_entities: HashMap<IntentName, HashMap<SlotName, EntityName>>,
     stop_words: HashSet<String>,
     specific_stop_words: HashMap<IntentName, HashSet<String>>,
@@ -109,10 +115,13 @@ impl DeterministicIntentParser {
                 )
             })
             .collect();
+        let (regexes_per_intent, regex_set) = compile_regexes_per_intent(model.patterns)?;
         Ok(DeterministicIntentParser {
             language,
-            regexes_per_intent: compile_regexes_per_intent(model.patterns)?,
+            regexes_per_intent,
+            regex_set,
             group_names_to_slot_names: model.group_names_to_slot_names,
+            group_names_to_parent_group_names: model.group_names_to_parent_group_names,
             slot_names_to_entities: model.slot_names_to_entities,
             stop_words,
             specific_stop_words,
@@ -147,6 +156,7 @@ impl IntentParser for DeterministicIntentParser {
                     confidence_score: 1.0,
                 },
                 slots: vec![],
+                out_of_scope_reason: None,
             });
         debug!("Intent found: '{:?}'", result.intent.intent_name);
         debug!("{} slots extracted", result.slots.len());
@@ -188,6 +198,30 @@ impl IntentParser for DeterministicIntentParser {
         let filter = vec![intent];
         self.parse(input, Some(&filter)).map(|result| result.slots)
     }
+
+    /// Literal words of the patterns, the slot values being only known by the entity parsers
+    fn vocabulary(&self) -> Vocabulary {
+        let non_literal_regex = Regex::new(r"\(\?P<\w+>|%[^%\s]*%|\\.").unwrap();
+        let words = self
+            .regexes_per_intent
+            .values()
+            .flatten()
+            .flat_map(|(_, regex)| {
+                non_literal_regex
+                    .replace_all(regex.as_str(), " ")
+                    .split(|c: char| !c.is_alphabetic())
+                    .filter(|word| !word.is_empty())
+                    .map(|word| word.to_lowercase())
+                    .collect::<Vec<_>>()
+            })
+            .unique()
+            .sorted()
+            .collect();
+        Vocabulary {
+            words,
+            stems: vec![],
+        }
+    }
 }
 
 impl DeterministicIntentParser {
@@ -213,6 +247,9 @@ impl DeterministicIntentParser {
             .iter()
             .filter(|(intent, _)| intents_set.contains(&***intent));
 
+        // Several intents usually share the same preprocessed inputs
+        let mut set_matches_cache: HashMap<String, Option<SetMatches>> = HashMap::new();
+
         for (intent, (builtin_scope, custom_scope)) in filtered_entity_scopes {
             let builtin_entities = self
                 .shared_resources
@@ -236,14 +273,23 @@ impl DeterministicIntentParser {
                 replace_entities(input, matched_entities, get_entity_placeholder);
             let cleaned_input = self.preprocess_text(input, &**intent);
             let cleaned_formatted_input = self.preprocess_text(&*formatted_input, &**intent);
+            let cleaned_input_matches =
+                self.get_set_matches(&cleaned_input, &mut set_matches_cache);
+            let cleaned_formatted_input_matches =
+                self.get_set_matches(&cleaned_formatted_input, &mut set_matches_cache);
             if let Some(matching_result_formatted) = self
                 .regexes_per_intent
                 .get(intent)
                 .ok_or_else(|| format_err!("No associated regexes for intent '{}'", intent))?
                 .iter()
-                .find_map(|regex| {
-                    self.get_matching_result(input, &*cleaned_input, regex, intent, None)
-                        .or_else(|| {
+                .find_map(|(set_index, regex)| {
+                    let cleaned_input_result = if may_match(&cleaned_input_matches, *set_index) {
+                        self.get_matching_result(input, &*cleaned_input, regex, intent, None)
+                    } else {
+                        None
+                    };
+                    cleaned_input_result.or_else(|| {
+                        if may_match(&cleaned_formatted_input_matches, *set_index) {
                             self.get_matching_result(
                                 input,
                                 &*cleaned_formatted_input,
@@ -251,7 +297,10 @@ impl DeterministicIntentParser {
                                 intent,
                                 Some(&ranges_mapping),
                             )
-                        })
+                        } else {
+                            None
+                        }
+                    })
                 })
             {
                 results.push(matching_result_formatted);
@@ -274,15 +323,24 @@ impl DeterministicIntentParser {
                 res
             })
             .sorted_by(|res1, res2| {
-                res2.intent
-                    .confidence_score
-                    .partial_cmp(&res1.intent.confidence_score)
-                    .unwrap()
+                cmp_scores(res2.intent.confidence_score, res1.intent.confidence_score)
             })
             .take(top_n)
             .collect())
     }
 
+    fn get_set_matches(
+        &self,
+        text: &str,
+        cache: &mut HashMap<String, Option<SetMatches>>,
+    ) -> Option<SetMatches> {
+        let regex_set = self.regex_set.as_ref()?;
+        cache
+            .entry(text.to_string())
+            .or_insert_with(|| Some(regex_set.matches(text)))
+            .clone()
+    }
+
     fn preprocess_text(&self, string: &str, intent: &str) -> String {
         let stop_words = self
             .specific_stop_words
@@ -341,24 +399,26 @@ impl DeterministicIntentParser {
                     let byte_range = a_match.start()..a_match.end();
                     let mut char_range = convert_to_char_range(&formatted_input, &byte_range);
                     if let Some(ranges_mapping) = builtin_entities_ranges_mapping {
-                        char_range =
-                            ranges_mapping.get(&char_range).cloned().unwrap_or_else(|| {
-                                let shift = get_range_shift(&char_range, ranges_mapping);
-                                let range_start = (char_range.start as i32 + shift) as usize;
-                                let range_end = (char_range.end as i32 + shift) as usize;
-                                range_start..range_end
-                            });
+                        char_range = ranges_mapping
+                            .get(&char_range)
+                            .cloned()
+                            .unwrap_or_else(|| get_shifted_range(&char_range, ranges_mapping));
                     }
                     let value = substring_with_char_range(input.to_string(), &char_range);
-                    InternalSlot {
+                    let slot = InternalSlot {
                         value,
                         char_range,
                         entity,
                         slot_name,
-                    }
+                    };
+                    (group_name, slot)
                 })
                 .collect();
-            let deduplicated_slots = deduplicate_overlapping_slots(slots, self.language);
+            let deduplicated_slots = deduplicate_overlapping_slots_with_sub_slots(
+                slots,
+                &self.group_names_to_parent_group_names,
+                self.language,
+            );
             let result = internal_parsing_result(Some(intent.to_string()), 1.0, deduplicated_slots);
             return Some(result);
         }
@@ -367,19 +427,46 @@ impl DeterministicIntentParser {
     }
 }
 
+/// Checks whether the regex at `set_index` is part of the set matches, which is always the case
+/// when there is no regex set
+fn may_match(set_matches: &Option<SetMatches>, set_index: usize) -> bool {
+    set_matches
+        .as_ref()
+        .map(|matches| matches.matched(set_index))
+        .unwrap_or(true)
+}
+
+#[allow(clippy::type_complexity)]
 fn compile_regexes_per_intent(
     patterns: HashMap<IntentName, Vec<String>>,
-) -> Result<HashMap<IntentName, Vec<Regex>>> {
-    patterns
+) -> Result<(HashMap<IntentName, Vec<(usize, Regex)>>, Option<RegexSet>)> {
+    let mut all_patterns: Vec<String> = vec![];
+    let regexes_per_intent = patterns
         .into_iter()
         .map(|(intent, patterns)| {
             let regexes: Result<_> = patterns
                 .into_iter()
-                .map(|p| Ok(RegexBuilder::new(&p).case_insensitive(true).build()?))
+                .map(|p| {
+                    let regex = RegexBuilder::new(&p).case_insensitive(true).build()?;
+                    all_patterns.push(p);
+                    Ok((all_patterns.len() - 1, regex))
+                })
                 .collect();
             Ok((intent, regexes?))
         })
-        .collect()
+        .collect::<Result<_>>()?;
+    let regex_set = RegexSetBuilder::new(&all_patterns)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| {
+            // Regexes are then tried one after the other, which is slower but gives the same results
+            warn!(
+                "Cannot combine the deterministic intent parser patterns: {}",
+                e
+            )
+        })
+        .ok();
+    Ok((regexes_per_intent, regex_set))
 }
 
 fn deduplicate_overlapping_slots(
@@ -400,6 +487,57 @@ fn deduplicate_overlapping_slots(
     deduped
 }
 
+/// Deduplicates the overlapping slots, except the slots whose group is nested in a parent group
+/// whose slot contains them and is kept
+fn deduplicate_overlapping_slots_with_sub_slots(
+    grouped_slots: Vec<(&str, InternalSlot)>,
+    group_names_to_parent_group_names: &HashMap<String, String>,
+    language: Language,
+) -> Vec<InternalSlot> {
+    let is_sub_slot =
+        |group_name: &str, slot: &InternalSlot, candidates: &[(&str, InternalSlot)]| {
+            group_names_to_parent_group_names
+                .get(group_name)
+                .map(|parent_group_name| {
+                    candidates.iter().any(|(candidate_group_name, candidate)| {
+                        candidate_group_name == parent_group_name
+                            && range_contains(&candidate.char_range, &slot.char_range)
+                    })
+                })
+                .unwrap_or(false)
+        };
+    let (mut sub_slots, top_level_slots): (Vec<_>, Vec<_>) = grouped_slots
+        .iter()
+        .cloned()
+        .partition(|(group_name, slot)| is_sub_slot(group_name, slot, &grouped_slots));
+    let mut kept_slots = deduplicate_overlapping_slots(
+        top_level_slots.into_iter().map(|(_, slot)| slot).collect(),
+        language,
+    )
+    .into_iter()
+    .filter_map(|slot| {
+        grouped_slots
+            .iter()
+            .find(|(_, grouped_slot)| *grouped_slot == slot)
+            .cloned()
+    })
+    .collect::<Vec<_>>();
+    // Sub slots are processed from the largest to the smallest, so that a sub slot is kept only if
+    // its own parent slot has been kept
+    sub_slots.sort_by_key(|(_, slot)| Reverse(slot.char_range.len()));
+    for (group_name, slot) in sub_slots {
+        if is_sub_slot(group_name, &slot, &kept_slots) {
+            kept_slots.push((group_name, slot));
+        }
+    }
+    let mut slots = kept_slots
+        .into_iter()
+        .map(|(_, slot)| slot)
+        .collect::<Vec<_>>();
+    slots.sort_by_key(|slot| (slot.char_range.start, Reverse(slot.char_range.end)));
+    slots
+}
+
 fn get_entity_placeholder(entity_label: &str) -> String {
     // Here we don't need language specific tokenization,
     // we just want to generate a feature name, that's why we use EN
@@ -409,20 +547,23 @@ fn get_entity_placeholder(entity_label: &str) -> String {
     format!("%{}%", normalized_entity_label)
 }
 
-fn get_range_shift(
+/// Maps a range of the text in which entities have been replaced back to the original text,
+/// shifting each bound using the replaced range which ends the closest before it, as the range
+/// may contain replaced entities when it corresponds to a parent slot
+fn get_shifted_range(
     matched_range: &Range<usize>,
     ranges_mapping: &HashMap<Range<usize>, Range<usize>>,
-) -> i32 {
-    let mut shift: i32 = 0;
-    let mut previous_replaced_range_end: usize = 0;
-    let match_start = matched_range.start;
-    for (replaced_range, orig_range) in ranges_mapping.iter() {
-        if replaced_range.end <= match_start && replaced_range.end > previous_replaced_range_end {
-            previous_replaced_range_end = replaced_range.end;
-            shift = orig_range.end as i32 - replaced_range.end as i32;
-        }
-    }
-    shift
+) -> Range<usize> {
+    let shift_position = |position: usize| {
+        ranges_mapping
+            .iter()
+            .filter(|(replaced_range, _)| replaced_range.end <= position)
+            .max_by_key(|(replaced_range, _)| replaced_range.end)
+            // The position is after the end of the replaced range, hence no underflow
+            .map(|(replaced_range, orig_range)| position - replaced_range.end + orig_range.end)
+            .unwrap_or(position)
+    };
+    shift_position(matched_range.start)..shift_position(matched_range.end)
 }
 
 #[cfg(test)]
@@ -497,6 +638,7 @@ mod tests {
             language_code: "en".to_string(),
             patterns,
             group_names_to_slot_names,
+            group_names_to_parent_group_names: HashMap::new(),
             slot_names_to_entities,
             config: DeterministicParserConfig { ignore_stop_words },
             stop_words_whitelist,
@@ -532,6 +674,70 @@ mod tests {
         assert_eq!(expected_slots, parsing_result.slots);
     }
 
+    #[test]
+    fn test_vocabulary() {
+        // Given
+        let model = build_sample_model(
+            hashmap![
+                "MakeCoffee" => vec![r"^\s*make\s*(?P<group1>%SNIPSNUMBER%)\s*cups\s*of\s*coffee\s*$"],
+                "MakeTea" => vec![r"^\s*make\s*a\s*(?P<group2>%TEMPERATURE%)\s*tea\s*\?\s*$"],
+            ],
+            hashmap![
+                "group1" => "number_of_cups",
+                "group2" => "beverage_temperature",
+            ],
+            hashmap![
+                "MakeCoffee" => hashmap!["number_of_cups" => "snips/number"],
+                "MakeTea" => hashmap!["beverage_temperature" => "Temperature"],
+            ],
+            false,
+            hashmap![],
+        );
+        let shared_resources = Arc::new(SharedResourcesBuilder::default().build());
+        let parser = DeterministicIntentParser::new(model, shared_resources).unwrap();
+
+        // When
+        let vocabulary = parser.vocabulary();
+
+        // Then
+        let expected_words = vec!["a", "coffee", "cups", "make", "of", "tea"];
+        assert_eq!(expected_words, vocabulary.words);
+        assert!(vocabulary.stems.is_empty());
+    }
+
+    #[test]
+    fn test_regex_set_prefilter_gives_same_results() {
+        // Given
+        let trained_engine_path = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage");
+        let parser_path = trained_engine_path.join("deterministic_intent_parser");
+        let shared_resources = load_engine_shared_resources(trained_engine_path).unwrap();
+        let intent_parser =
+            DeterministicIntentParser::from_path(&parser_path, shared_resources.clone()).unwrap();
+        let mut sequential_intent_parser =
+            DeterministicIntentParser::from_path(&parser_path, shared_resources).unwrap();
+        sequential_intent_parser.regex_set = None;
+        let inputs = vec![
+            "make two cup of coffee",
+            "make me a hot cup of tea",
+            "i want three cups of tea please",
+            "brew a coffee",
+            "bla bla bla",
+        ];
+
+        for input in inputs {
+            // When
+            let parsing_result = intent_parser.parse(input, None).unwrap();
+            let sequential_parsing_result = sequential_intent_parser.parse(input, None).unwrap();
+
+            // Then
+            assert!(intent_parser.regex_set.is_some());
+            assert_eq!(sequential_parsing_result, parsing_result);
+        }
+    }
+
     #[test]
     fn test_parse_intent() {
         // Given
@@ -561,6 +767,7 @@ mod tests {
                 confidence_score: 1.0,
             },
             slots: vec![],
+            out_of_scope_reason: None,
         };
 
         assert_eq!(expected_parsing, parsing);
@@ -595,6 +802,7 @@ mod tests {
                 confidence_score: 1.0,
             },
             slots: vec![],
+            out_of_scope_reason: None,
         };
 
         assert_eq!(expected_parsing, parsing);
@@ -636,6 +844,7 @@ mod tests {
                 confidence_score: 1.0,
             },
             slots: vec![],
+            out_of_scope_reason: None,
         };
 
         assert_eq!(expected_parsing, parsing);
@@ -708,6 +917,7 @@ mod tests {
                     slot_name: "number".to_string(),
                 },
             ],
+            out_of_scope_reason: None,
         };
 
         assert_eq!(expected_parsing, parsing);
@@ -789,6 +999,7 @@ mod tests {
                             range: 0..4,
                             resolved_value: "call".to_string(),
                             entity_identifier: "event".to_string(),
+                            match_score: None,
                         }]
                     } else {
                         vec![]
@@ -814,6 +1025,7 @@ mod tests {
                 confidence_score: 1.0,
             },
             slots: vec![],
+            out_of_scope_reason: None,
         };
 
         assert_eq!(expected_parsing, parsing);
@@ -888,6 +1100,7 @@ mod tests {
                 confidence_score: 2. / 3.,
             },
             slots: vec![],
+            out_of_scope_reason: None,
         };
 
         assert_eq!(expected_parsing, parsing);
@@ -954,6 +1167,7 @@ mod tests {
                     alternative_resolved_values: vec![],
                     range: 13..17,
                     entity_identifier: "name".to_string(),
+                    match_score: None,
                 },
                 CustomEntity {
                     value: "snips".to_string(),
@@ -961,6 +1175,7 @@ mod tests {
                     alternative_resolved_values: vec![],
                     range: 21..26,
                     entity_identifier: "location".to_string(),
+                    match_score: None,
                 },
             ],
         )]);
@@ -1008,6 +1223,7 @@ mod tests {
                     slot_name: "time".to_string(),
                 },
             ],
+            out_of_scope_reason: None,
         };
 
         assert_eq!(expected_parsing, parsing);
@@ -1037,6 +1253,7 @@ mod tests {
                     alternative_resolved_values: vec![],
                     range: 7..11,
                     entity_identifier: "object".to_string(),
+                    match_score: None,
                 }],
             ),
             (
@@ -1047,6 +1264,7 @@ mod tests {
                     alternative_resolved_values: vec![],
                     range: 7..11,
                     entity_identifier: "object".to_string(),
+                    match_score: None,
                 }],
             ),
         ]);
@@ -1083,6 +1301,7 @@ mod tests {
                 entity: "object".to_string(),
                 slot_name: "object".to_string(),
             }],
+            out_of_scope_reason: None,
         };
 
         assert_eq!(expected_parsing, parsing);
@@ -1130,6 +1349,7 @@ mod tests {
                         resolved_value: "Hello".to_string(),
                         alternative_resolved_values: vec![],
                         entity_identifier: "greeting".to_string(),
+                        match_score: None,
                     });
                 };
                 if filter_entity_kinds
@@ -1142,6 +1362,7 @@ mod tests {
                         resolved_value: "John".to_string(),
                         alternative_resolved_values: vec![],
                         entity_identifier: "name".to_string(),
+                        match_score: None,
                     });
                 };
                 Ok(results)
@@ -1202,6 +1423,7 @@ mod tests {
                 alternative_resolved_values: vec![],
                 range: 11..15,
                 entity_identifier: "name".to_string(),
+                match_score: None,
             }],
         )]);
         let shared_resources = Arc::new(
@@ -1224,6 +1446,61 @@ mod tests {
         assert_eq!(expected_slots, slots);
     }
 
+    #[test]
+    fn test_parse_nested_slots() {
+        // Given
+        let text = "give me the address of the office of mom";
+        let mut model = build_sample_model(
+            hashmap!["get_address" => vec![
+                r"^\s*give\s*me\s*the\s*address\s*of\s*(?P<group0>the\s*office\s*of\s*(?P<group1>%CONTACT%))\s*$"
+            ]],
+            hashmap!["group0" => "location", "group1" => "contact"],
+            hashmap!["get_address" => hashmap!["location" => "location", "contact" => "contact"]],
+            false,
+            hashmap![],
+        );
+        model.group_names_to_parent_group_names =
+            hashmap!["group1".to_string() => "group0".to_string()];
+
+        let mocked_custom_entity_parser = MockedCustomEntityParser::from_iter(vec![(
+            text.to_string(),
+            vec![CustomEntity {
+                value: "mom".to_string(),
+                resolved_value: "mom".to_string(),
+                alternative_resolved_values: vec![],
+                range: 37..40,
+                entity_identifier: "contact".to_string(),
+                match_score: None,
+            }],
+        )]);
+        let shared_resources = Arc::new(
+            SharedResourcesBuilder::default()
+                .custom_entity_parser(mocked_custom_entity_parser)
+                .build(),
+        );
+        let parser = DeterministicIntentParser::new(model, shared_resources).unwrap();
+
+        // When
+        let slots = parser.parse(text, None).unwrap().slots;
+
+        // Then
+        let expected_slots = vec![
+            InternalSlot {
+                value: "the office of mom".to_string(),
+                char_range: 23..40,
+                entity: "location".to_string(),
+                slot_name: "location".to_string(),
+            },
+            InternalSlot {
+                value: "mom".to_string(),
+                char_range: 37..40,
+                entity: "contact".to_string(),
+                slot_name: "contact".to_string(),
+            },
+        ];
+        assert_eq!(expected_slots, slots);
+    }
+
     #[test]
     fn test_parse_slots_with_special_tokenized_out_characters() {
         // Given
@@ -1244,6 +1521,7 @@ mod tests {
                 alternative_resolved_values: vec![],
                 range: 13..26,
                 entity_identifier: "name".to_string(),
+                match_score: None,
             }],
         )]);
 
@@ -1269,6 +1547,7 @@ mod tests {
                 entity: "name".to_string(),
                 slot_name: "name".to_string(),
             }],
+            out_of_scope_reason: None,
         };
 
         assert_eq!(expected_parsing, parsing);
@@ -1300,6 +1579,7 @@ mod tests {
                 alternative_resolved_values: vec![],
                 range: 6..10,
                 entity_identifier: "name".to_string(),
+                match_score: None,
             }],
         )]);
         let shared_resources = Arc::new(
@@ -1422,7 +1702,7 @@ mod tests {
     }
 
     #[test]
-    fn test_get_range_shift() {
+    fn test_get_shifted_range() {
         // Given
         let ranges_mapping = hashmap! {
             2..5 => 2..4,
@@ -1430,7 +1710,10 @@ mod tests {
         };
 
         // When / Then
-        assert_eq!(-1, get_range_shift(&(6..7), &ranges_mapping));
-        assert_eq!(2, get_range_shift(&(12..13), &ranges_mapping));
+        assert_eq!(0..1, get_shifted_range(&(0..1), &ranges_mapping));
+        assert_eq!(5..6, get_shifted_range(&(6..7), &ranges_mapping));
+        assert_eq!(14..15, get_shifted_range(&(12..13), &ranges_mapping));
+        assert_eq!(1..6, get_shifted_range(&(1..7), &ranges_mapping));
+        assert_eq!(5..15, get_shifted_range(&(6..13), &ranges_mapping));
     }
 }
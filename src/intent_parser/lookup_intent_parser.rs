@@ -4,7 +4,9 @@ use crate::language::FromLanguage;
 use crate::models::LookupParserModel;
 use crate::resources::SharedResources;
 use crate::slot_utils::*;
-use crate::utils::{deduplicate_overlapping_entities, IntentName, MatchedEntity, SlotName};
+use crate::utils::{
+    cmp_scores, deduplicate_overlapping_entities, IntentName, MatchedEntity, SlotName,
+};
 use crate::IntentParser;
 use crate::{EntityScope, GroupedEntityScope, InputHash, IntentId, SlotId};
 use failure::ResultExt;
@@ -12,6 +14,7 @@ use itertools::Itertools;
 use log::debug;
 use snips_nlu_ontology::{BuiltinEntityKind, IntentClassifierResult, Language};
 use snips_nlu_utils::language::Language as NluUtilsLanguage;
+use snips_nlu_utils::range::ranges_overlap;
 use snips_nlu_utils::string::{
     hash_str_to_i32, normalize, substring_with_char_range, suffix_from_char_index,
 };
@@ -35,6 +38,8 @@ pub struct LookupIntentParser {
     stop_words: HashSet<String>,
     specific_stop_words: HashMap<IntentName, HashSet<String>>,
     entity_scopes: Vec<GroupedEntityScope>,
+    max_entity_combinations: usize,
+    stop_at_first_match: bool,
     shared_resources: Arc<SharedResources>,
 }
 
@@ -87,6 +92,8 @@ impl LookupIntentParser {
             stop_words,
             specific_stop_words,
             entity_scopes: model.entity_scopes,
+            max_entity_combinations: model.config.max_entity_combinations,
+            stop_at_first_match: model.config.stop_at_first_match,
             shared_resources,
         })
     }
@@ -117,6 +124,7 @@ impl IntentParser for LookupIntentParser {
                     confidence_score: 1.0,
                 },
                 slots: vec![],
+                out_of_scope_reason: None,
             });
         debug!("Intent found: '{:?}'", result.intent.intent_name);
         debug!("{} slots extracted", result.slots.len());
@@ -168,19 +176,57 @@ impl LookupIntentParser {
         intents: Option<&[&str]>,
     ) -> Result<Vec<InternalParsingResult>> {
         let mut results_per_intent = HashMap::<String, Vec<InternalParsingResult>>::new();
-        for (text_candidate, entities) in self.get_candidates(input, intents)? {
-            let candidate_key = hash_str_to_i32(&text_candidate);
-            if let Some(result) = self
-                .map
-                .get(&candidate_key)
-                .and_then(|val| self.parse_map_output(input, val, entities, intents))
-            {
-                if let Some(intent_name) = result.intent.intent_name.as_ref() {
-                    results_per_intent
-                        .entry(intent_name.to_string())
-                        .and_modify(|results| results.push(result.clone()))
-                        .or_insert_with(|| vec![result]);
+        let scoped_entities = self.get_scoped_entities(input, intents)?;
+        let max_nb_entities = scoped_entities
+            .iter()
+            .map(|(_, entities)| entities.len())
+            .max()
+            .unwrap_or(0);
+        let mut seen_candidates: HashSet<(String, Vec<MatchedEntity>)> = HashSet::new();
+        let mut nb_remaining_combinations = self.max_entity_combinations;
+        // We try subsets of entities to match utterances containing ambivalent words which can be
+        // both entity values or random words. The input without entities is tried first, and then
+        // subsets of decreasing sizes.
+        let subset_sizes = Some(0).into_iter().chain((1..=max_nb_entities).rev());
+        'subsets: for subset_size in subset_sizes {
+            for (intent_group, entities) in scoped_entities.iter() {
+                if subset_size > entities.len() {
+                    continue;
                 }
+                let combinations =
+                    get_entities_combinations(entities, subset_size, nb_remaining_combinations);
+                nb_remaining_combinations -= combinations.len();
+                for combination in combinations {
+                    for candidate in self.get_candidates(input, intent_group, combination) {
+                        if !seen_candidates.insert(candidate.clone()) {
+                            continue;
+                        }
+                        let (text_candidate, entities) = candidate;
+                        let candidate_key = hash_str_to_i32(&text_candidate);
+                        if let Some(result) = self
+                            .map
+                            .get(&candidate_key)
+                            .and_then(|val| self.parse_map_output(input, val, entities, intents))
+                        {
+                            if let Some(intent_name) = result.intent.intent_name.as_ref() {
+                                results_per_intent
+                                    .entry(intent_name.to_string())
+                                    .and_modify(|results| results.push(result.clone()))
+                                    .or_insert_with(|| vec![result]);
+                                if self.stop_at_first_match {
+                                    break 'subsets;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            if nb_remaining_combinations == 0 {
+                debug!(
+                    "Maximum number of entity combinations ({}) reached",
+                    self.max_entity_combinations
+                );
+                break;
             }
         }
         let results: Vec<(InternalParsingResult, f32)> = results_per_intent
@@ -194,7 +240,7 @@ impl LookupIntentParser {
                         let score = 1. / (1. + res.slots.len() as f32);
                         (res, score)
                     })
-                    .max_by(|(_, score_a), (_, score_b)| score_a.partial_cmp(score_b).unwrap())
+                    .max_by(|(_, score_a), (_, score_b)| cmp_scores(*score_a, *score_b))
             })
             .collect();
 
@@ -207,21 +253,20 @@ impl LookupIntentParser {
                 res
             })
             .sorted_by(|res1, res2| {
-                res2.intent
-                    .confidence_score
-                    .partial_cmp(&res1.intent.confidence_score)
-                    .unwrap()
+                cmp_scores(res2.intent.confidence_score, res1.intent.confidence_score)
             })
             .take(top_n)
             .collect())
     }
 
-    fn get_candidates(
-        &self,
+    /// Returns the entities found in the input for each group of intents sharing the same entity
+    /// scope
+    fn get_scoped_entities<'a>(
+        &'a self,
         input: &str,
         intents_whitelist: Option<&[&str]>,
-    ) -> Result<Vec<(String, Vec<MatchedEntity>)>> {
-        let mut candidates: Vec<(String, Vec<MatchedEntity>)> = Vec::new();
+    ) -> Result<Vec<(Vec<&'a String>, Vec<MatchedEntity>)>> {
+        let mut scoped_entities = vec![];
         for entity_scope in self.entity_scopes.iter() {
             let intent_group: Vec<&String> = entity_scope
                 .intent_group
@@ -236,20 +281,27 @@ impl LookupIntentParser {
                 continue;
             }
             let all_entities = self.get_all_entities(input, &entity_scope.entity_scope)?;
-            // We generate all subsets of entities to match utterances containing ambivalent
-            // words which can be both entity values or random words
-            for entities in get_items_combinations(all_entities) {
-                let processed_text = replace_entities_with_placeholders(input, entities.as_ref());
-                for intent in intent_group.iter() {
-                    let cleaned_text = self.preprocess_text(input, intent);
-                    let cleaned_processed_text = self.preprocess_text(&processed_text, intent);
-                    candidates.push((cleaned_text, vec![]));
-                    candidates.push((cleaned_processed_text, entities.clone()));
-                }
-            }
+            scoped_entities.push((intent_group, all_entities));
         }
+        Ok(scoped_entities)
+    }
 
-        Ok(candidates.into_iter().unique().collect())
+    fn get_candidates(
+        &self,
+        input: &str,
+        intent_group: &[&String],
+        entities: Vec<MatchedEntity>,
+    ) -> Vec<(String, Vec<MatchedEntity>)> {
+        let processed_text = replace_entities_with_placeholders(input, entities.as_ref());
+        intent_group
+            .iter()
+            .map(|intent| {
+                (
+                    self.preprocess_text(&processed_text, intent),
+                    entities.clone(),
+                )
+            })
+            .collect()
     }
 
     fn get_all_entities(
@@ -319,7 +371,11 @@ impl LookupIntentParser {
                     slot_name: slot_name.to_string(),
                 });
             }
-            Some(InternalParsingResult { intent, slots })
+            Some(InternalParsingResult {
+                intent,
+                slots,
+                out_of_scope_reason: None,
+            })
         } else {
             // if intent name not in intents, return None
             None
@@ -371,15 +427,65 @@ fn replace_entities_with_placeholders(text: &str, entities: &[MatchedEntity]) ->
     }
 }
 
-fn get_items_combinations<T>(items: Vec<T>) -> Vec<Vec<T>>
-where
-    T: Clone,
-{
-    let mut combinations: Vec<Vec<T>> = (1..items.len() + 1)
-        .rev()
-        .flat_map(|nb_entities| items.clone().into_iter().combinations(nb_entities))
-        .collect();
-    combinations.insert(0, vec![]);
+/// Returns, in lexicographic order, at most `max_nb_combinations` subsets of `subset_size`
+/// entities which do not overlap, entities being sorted by range
+fn get_entities_combinations(
+    entities: &[MatchedEntity],
+    subset_size: usize,
+    max_nb_combinations: usize,
+) -> Vec<Vec<MatchedEntity>> {
+    fn extend_combination(
+        entities: &[MatchedEntity],
+        start_index: usize,
+        subset_size: usize,
+        max_nb_combinations: usize,
+        combination: &mut Vec<MatchedEntity>,
+        combinations: &mut Vec<Vec<MatchedEntity>>,
+    ) {
+        if combination.len() == subset_size {
+            combinations.push(combination.clone());
+            return;
+        }
+        let nb_missing_entities = subset_size - combination.len();
+        for index in start_index..entities.len() {
+            if entities.len() - index < nb_missing_entities
+                || combinations.len() >= max_nb_combinations
+            {
+                return;
+            }
+            let entity = &entities[index];
+            // Since entities are sorted, an entity can only overlap the last one of the subset
+            if combination
+                .last()
+                .map(|last_entity| ranges_overlap(&last_entity.range, &entity.range))
+                .unwrap_or(false)
+            {
+                continue;
+            }
+            combination.push(entity.clone());
+            extend_combination(
+                entities,
+                index + 1,
+                subset_size,
+                max_nb_combinations,
+                combination,
+                combinations,
+            );
+            combination.pop();
+        }
+    }
+
+    let mut combinations = vec![];
+    if max_nb_combinations > 0 {
+        extend_combination(
+            entities,
+            0,
+            subset_size,
+            max_nb_combinations,
+            &mut vec![],
+            &mut combinations,
+        );
+    }
     combinations
 }
 
@@ -422,7 +528,11 @@ mod tests {
             map,
             entity_scopes,
             stop_words_whitelist,
-            config: LookupParserConfig { ignore_stop_words },
+            config: LookupParserConfig {
+                ignore_stop_words,
+                max_entity_combinations: 1024,
+                stop_at_first_match: false,
+            },
         }
     }
 
@@ -489,6 +599,7 @@ mod tests {
                 confidence_score: 1.0,
             },
             slots: vec![],
+            out_of_scope_reason: None,
         };
 
         assert_eq!(expected_parsing, parsing);
@@ -529,6 +640,7 @@ mod tests {
                 confidence_score: 1.0,
             },
             slots: vec![],
+            out_of_scope_reason: None,
         };
 
         assert_eq!(expected_parsing, parsing);
@@ -576,6 +688,7 @@ mod tests {
                 confidence_score: 1.0,
             },
             slots: vec![],
+            out_of_scope_reason: None,
         };
 
         assert_eq!(expected_parsing, parsing);
@@ -652,6 +765,7 @@ mod tests {
                     slot_name: "number".to_string(),
                 },
             ],
+            out_of_scope_reason: None,
         };
 
         assert_eq!(expected_parsing, parsing);
@@ -691,6 +805,7 @@ mod tests {
                     alternative_resolved_values: vec![],
                     range: 7..12,
                     entity_identifier: "name".to_string(),
+                    match_score: None,
                 },
                 CustomEntity {
                     value: "emily".to_string(),
@@ -698,6 +813,7 @@ mod tests {
                     alternative_resolved_values: vec![],
                     range: 16..21,
                     entity_identifier: "name".to_string(),
+                    match_score: None,
                 },
             ],
         )]);
@@ -724,42 +840,125 @@ mod tests {
                 entity: "name".to_string(),
                 slot_name: "name".to_string(),
             }],
+            out_of_scope_reason: None,
         };
 
         assert_eq!(expected_parsing, parsing);
     }
 
     #[test]
-    fn test_very_ambiguous_utterances_should_not_be_parsed() {
+    fn test_parse_intent_with_bounded_entity_combinations() {
         // Given
-        let map = hashmap![
-            hash_str_to_i32("% event % tomorrow") => (0, vec![0]),
-            hash_str_to_i32("call % snipsdatetime %") => (1, vec![1]),
-        ];
-        let entity_scopes = vec![
-            GroupedEntityScope {
-                intent_group: vec!["intent1".to_string()],
+        let text = "give a daisy to emily";
+        let build_parser = |max_entity_combinations: usize, stop_at_first_match: bool| {
+            let map = hashmap![
+                hash_str_to_i32("give a rose to % name %") => (0, vec![0]),
+                hash_str_to_i32("give a daisy to % name %") => (0, vec![0]),
+            ];
+            let entity_scopes = vec![GroupedEntityScope {
+                intent_group: vec!["give_flower".to_string()],
                 entity_scope: EntityScope {
                     builtin: vec![],
-                    custom: vec!["event".to_string()],
-                },
-            },
-            GroupedEntityScope {
-                intent_group: vec!["intent2".to_string()],
-                entity_scope: EntityScope {
-                    builtin: vec!["snips/datetime".to_string()],
-                    custom: vec![],
+                    custom: vec!["name".to_string()],
                 },
-            },
-        ];
-        let model = build_sample_model(
-            vec!["event", "time"],
-            vec!["intent1", "intent2"],
-            map,
-            entity_scopes,
-            hashmap![],
-            true,
+            }];
+            let mut model = build_sample_model(
+                vec!["name"],
+                vec!["give_flower"],
+                map,
+                entity_scopes,
+                hashmap![],
+                false,
+            );
+            model.config.max_entity_combinations = max_entity_combinations;
+            model.config.stop_at_first_match = stop_at_first_match;
+
+            let mocked_custom_entity_parser = MockedCustomEntityParser::from_iter(vec![(
+                text.to_string(),
+                vec![
+                    CustomEntity {
+                        value: "daisy".to_string(),
+                        resolved_value: "daisy".to_string(),
+                        alternative_resolved_values: vec![],
+                        range: 7..12,
+                        entity_identifier: "name".to_string(),
+                        match_score: None,
+                    },
+                    CustomEntity {
+                        value: "emily".to_string(),
+                        resolved_value: "emily".to_string(),
+                        alternative_resolved_values: vec![],
+                        range: 16..21,
+                        entity_identifier: "name".to_string(),
+                        match_score: None,
+                    },
+                ],
+            )]);
+            let shared_resources = Arc::new(
+                SharedResourcesBuilder::default()
+                    .custom_entity_parser(mocked_custom_entity_parser)
+                    .build(),
+            );
+            LookupIntentParser::new(model, shared_resources).unwrap()
+        };
+        // Only the raw input and the subset containing both entities can be tried
+        let bounded_parser = build_parser(2, false);
+        let early_stopping_parser = build_parser(1024, true);
+
+        // When
+        let bounded_parsing = bounded_parser.parse(text, None).unwrap();
+        let early_stopping_parsing = early_stopping_parser.parse(text, None).unwrap();
+
+        // Then
+        assert_eq!(None, bounded_parsing.intent.intent_name);
+        let expected_slots = vec![InternalSlot {
+            value: "emily".to_string(),
+            char_range: 16..21,
+            entity: "name".to_string(),
+            slot_name: "name".to_string(),
+        }];
+        assert_eq!(
+            Some("give_flower".to_string()),
+            early_stopping_parsing.intent.intent_name
         );
+        assert_eq!(expected_slots, early_stopping_parsing.slots);
+    }
+
+    #[test]
+    fn test_very_ambiguous_utterances_should_not_be_parsed() {
+        // Given
+        let build_model = |stop_at_first_match: bool| {
+            let map = hashmap![
+                hash_str_to_i32("% event % tomorrow") => (0, vec![0]),
+                hash_str_to_i32("call % snipsdatetime %") => (1, vec![1]),
+            ];
+            let entity_scopes = vec![
+                GroupedEntityScope {
+                    intent_group: vec!["intent1".to_string()],
+                    entity_scope: EntityScope {
+                        builtin: vec![],
+                        custom: vec!["event".to_string()],
+                    },
+                },
+                GroupedEntityScope {
+                    intent_group: vec!["intent2".to_string()],
+                    entity_scope: EntityScope {
+                        builtin: vec!["snips/datetime".to_string()],
+                        custom: vec![],
+                    },
+                },
+            ];
+            let mut model = build_sample_model(
+                vec!["event", "time"],
+                vec!["intent1", "intent2"],
+                map,
+                entity_scopes,
+                hashmap![],
+                true,
+            );
+            model.config.stop_at_first_match = stop_at_first_match;
+            model
+        };
 
         struct TestBuiltinEntityParser {}
 
@@ -820,6 +1019,7 @@ mod tests {
                             resolved_value: "call".to_string(),
                             alternative_resolved_values: vec![],
                             entity_identifier: "event".to_string(),
+                            match_score: None,
                         }]
                     } else {
                         vec![]
@@ -833,10 +1033,13 @@ mod tests {
                 .custom_entity_parser(TestCustomEntityParser {})
                 .build(),
         );
-        let parser = LookupIntentParser::new(model, shared_resources).unwrap();
+        let parser = LookupIntentParser::new(build_model(false), shared_resources.clone()).unwrap();
+        let early_stopping_parser =
+            LookupIntentParser::new(build_model(true), shared_resources).unwrap();
 
         // When
         let parsing = parser.parse("call tomorrow", None).unwrap();
+        let early_stopping_parsing = early_stopping_parser.parse("call tomorrow", None).unwrap();
 
         // Then
         let expected_parsing = InternalParsingResult {
@@ -845,9 +1048,16 @@ mod tests {
                 confidence_score: 1.0,
             },
             slots: vec![],
+            out_of_scope_reason: None,
         };
 
         assert_eq!(expected_parsing, parsing);
+        // The ambiguity is not detected when stopping at the first match
+        assert_eq!(
+            Some("intent1".to_string()),
+            early_stopping_parsing.intent.intent_name
+        );
+        assert_eq!(1.0, early_stopping_parsing.intent.confidence_score);
     }
 
     #[test]
@@ -935,6 +1145,7 @@ mod tests {
                 confidence_score: 2. / 3.,
             },
             slots: vec![],
+            out_of_scope_reason: None,
         };
 
         assert_eq!(expected_parsing, parsing);
@@ -1001,6 +1212,7 @@ mod tests {
                     alternative_resolved_values: vec![],
                     range: 13..17,
                     entity_identifier: "name".to_string(),
+                    match_score: None,
                 },
                 CustomEntity {
                     value: "snips".to_string(),
@@ -1008,6 +1220,7 @@ mod tests {
                     alternative_resolved_values: vec![],
                     range: 21..26,
                     entity_identifier: "location".to_string(),
+                    match_score: None,
                 },
             ],
         )]);
@@ -1055,6 +1268,7 @@ mod tests {
                     slot_name: "time".to_string(),
                 },
             ],
+            out_of_scope_reason: None,
         };
 
         assert_eq!(expected_parsing, parsing);
@@ -1091,6 +1305,7 @@ mod tests {
                     alternative_resolved_values: vec![],
                     range: 7..11,
                     entity_identifier: "object".to_string(),
+                    match_score: None,
                 }],
             ),
             (
@@ -1101,6 +1316,7 @@ mod tests {
                     alternative_resolved_values: vec![],
                     range: 7..11,
                     entity_identifier: "object".to_string(),
+                    match_score: None,
                 }],
             ),
         ]);
@@ -1137,6 +1353,7 @@ mod tests {
                 entity: "object".to_string(),
                 slot_name: "object".to_string(),
             }],
+            out_of_scope_reason: None,
         };
 
         assert_eq!(expected_parsing, parsing);
@@ -1205,6 +1422,7 @@ mod tests {
                         resolved_value: "Hello".to_string(),
                         alternative_resolved_values: vec![],
                         entity_identifier: "greeting".to_string(),
+                        match_score: None,
                     });
                 };
                 if filter_entity_kinds
@@ -1217,6 +1435,7 @@ mod tests {
                         resolved_value: "John".to_string(),
                         alternative_resolved_values: vec![],
                         entity_identifier: "name".to_string(),
+                        match_score: None,
                     });
                 };
                 Ok(results)
@@ -1287,6 +1506,7 @@ mod tests {
                 alternative_resolved_values: vec![],
                 range: 13..26,
                 entity_identifier: "name".to_string(),
+                match_score: None,
             }],
         )]);
 
@@ -1312,6 +1532,7 @@ mod tests {
                 entity: "name".to_string(),
                 slot_name: "name".to_string(),
             }],
+            out_of_scope_reason: None,
         };
 
         assert_eq!(expected_parsing, parsing);
@@ -1357,6 +1578,7 @@ mod tests {
                 alternative_resolved_values: vec![],
                 range: 6..10,
                 entity_identifier: "name".to_string(),
+                match_score: None,
             }],
         )]);
         let shared_resources = Arc::new(
@@ -1419,17 +1641,29 @@ mod tests {
     }
 
     #[test]
-    fn test_get_combinations() {
+    fn test_get_entities_combinations() {
+        // Given
+        let entity = |start: usize, end: usize| MatchedEntity {
+            range: start..end,
+            entity_name: "entity".to_string(),
+        };
+        let entities = vec![entity(0, 3), entity(2, 5), entity(6, 8), entity(9, 12)];
+
+        // When
+        let combinations = get_entities_combinations(&entities, 2, 10);
+        let limited_combinations = get_entities_combinations(&entities, 2, 2);
+        let empty_combination = get_entities_combinations(&entities, 0, 10);
+
+        // Then
         let expected_combinations = vec![
-            vec![],
-            vec![3, 0, 5],
-            vec![3, 0],
-            vec![3, 5],
-            vec![0, 5],
-            vec![3],
-            vec![0],
-            vec![5],
+            vec![entity(0, 3), entity(6, 8)],
+            vec![entity(0, 3), entity(9, 12)],
+            vec![entity(2, 5), entity(6, 8)],
+            vec![entity(2, 5), entity(9, 12)],
+            vec![entity(6, 8), entity(9, 12)],
         ];
-        assert_eq!(expected_combinations, get_items_combinations(vec![3, 0, 5]))
+        assert_eq!(expected_combinations, combinations);
+        assert_eq!(expected_combinations[..2].to_vec(), limited_combinations);
+        assert_eq!(vec![Vec::<MatchedEntity>::new()], empty_combination);
     }
 }
@@ -1,3 +1,4 @@
+pub mod calibration;
 pub mod deterministic_intent_parser;
 pub mod lookup_intent_parser;
 pub mod probabilistic_intent_parser;
@@ -8,19 +9,25 @@ use std::sync::Arc;
 use failure::format_err;
 use snips_nlu_ontology::IntentClassifierResult;
 
+pub use self::calibration::{CalibratedIntentParser, Calibrator};
 pub use self::deterministic_intent_parser::DeterministicIntentParser;
 pub use self::lookup_intent_parser::LookupIntentParser;
 pub use self::probabilistic_intent_parser::ProbabilisticIntentParser;
 use crate::errors::*;
+use crate::intent_classifier::OutOfScopeReason;
 use crate::models::ProcessingUnitMetadata;
 use crate::resources::SharedResources;
+use crate::slot_filler::FeatureCache;
 pub use crate::slot_utils::InternalSlot;
+use crate::spelling_correction::Vocabulary;
 use crate::utils::IntentName;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct InternalParsingResult {
     pub intent: IntentClassifierResult,
     pub slots: Vec<InternalSlot>,
+    /// Reason why the input has been considered out of scope by the intent classifier, if it has
+    pub out_of_scope_reason: Option<OutOfScopeReason>,
 }
 
 impl InternalParsingResult {
@@ -31,6 +38,7 @@ impl InternalParsingResult {
                 confidence_score: 1.0,
             },
             slots: vec![],
+            out_of_scope_reason: None,
         }
     }
 }
@@ -46,6 +54,7 @@ pub fn internal_parsing_result(
             confidence_score: intent_proba,
         },
         slots,
+        out_of_scope_reason: None,
     }
 }
 
@@ -59,6 +68,33 @@ pub trait IntentParser: Send + Sync {
     fn get_intents(&self, input: &str) -> Result<Vec<IntentClassifierResult>>;
 
     fn get_slots(&self, input: &str, intent: &str) -> Result<Vec<InternalSlot>>;
+
+    /// Same as `parse`, but reuses the slot filler feature values computed while parsing the
+    /// same input
+    fn parse_with_feature_cache(
+        &self,
+        input: &str,
+        intents_whitelist: Option<&[&str]>,
+        _feature_cache: &FeatureCache,
+    ) -> Result<InternalParsingResult> {
+        self.parse(input, intents_whitelist)
+    }
+
+    /// Same as `get_slots`, but reuses the slot filler feature values computed while parsing the
+    /// same input
+    fn get_slots_with_feature_cache(
+        &self,
+        input: &str,
+        intent: &str,
+        _feature_cache: &FeatureCache,
+    ) -> Result<Vec<InternalSlot>> {
+        self.get_slots(input, intent)
+    }
+
+    /// Words known by the parser, which are used to correct the spelling of the inputs
+    fn vocabulary(&self) -> Vocabulary {
+        Vocabulary::default()
+    }
 }
 
 pub fn build_intent_parser<P: AsRef<Path>>(
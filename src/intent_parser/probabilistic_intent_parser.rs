@@ -4,13 +4,14 @@ use std::iter::FromIterator;
 use std::path::Path;
 use std::sync::Arc;
 
-use failure::{format_err, ResultExt};
+use failure::ResultExt;
 
 use crate::errors::*;
 use crate::intent_classifier::{build_intent_classifier, IntentClassifier};
 use crate::models::ProbabilisticParserModel;
 use crate::resources::SharedResources;
-use crate::slot_filler::{build_slot_filler, SlotFiller};
+use crate::slot_filler::{build_slot_filler, FeatureCache, SlotFiller};
+use crate::spelling_correction::Vocabulary;
 use crate::utils::IntentName;
 
 use super::{IntentClassifierResult, IntentParser, InternalParsingResult};
@@ -63,38 +64,63 @@ impl IntentParser for ProbabilisticIntentParser {
         input: &str,
         intents_whitelist: Option<&[&str]>,
     ) -> Result<InternalParsingResult> {
-        let intent_result = self
+        self.parse_with_feature_cache(input, intents_whitelist, &FeatureCache::default())
+    }
+
+    fn get_intents(&self, input: &str) -> Result<Vec<IntentClassifierResult>> {
+        self.intent_classifier.get_intents(input)
+    }
+
+    fn get_slots(&self, input: &str, intent: &str) -> Result<Vec<InternalSlot>> {
+        self.get_slots_with_feature_cache(input, intent, &FeatureCache::default())
+    }
+
+    fn parse_with_feature_cache(
+        &self,
+        input: &str,
+        intents_whitelist: Option<&[&str]>,
+        feature_cache: &FeatureCache,
+    ) -> Result<InternalParsingResult> {
+        let (intent_result, out_of_scope_reason) = self
             .intent_classifier
-            .get_intent(input, intents_whitelist)?;
+            .get_intent_with_out_of_scope_reason(input, intents_whitelist)?;
         let slots = if let Some(name) = intent_result.intent_name.as_ref() {
             self.slot_fillers
                 .get(name)
                 .ok_or_else(|| SnipsNluError::UnknownIntent(name.to_string()))?
-                .get_slots(input)?
+                .get_slots_with_feature_cache(input, feature_cache)?
         } else {
             vec![]
         };
         Ok(InternalParsingResult {
             intent: intent_result,
             slots,
+            out_of_scope_reason,
         })
     }
 
-    fn get_intents(&self, input: &str) -> Result<Vec<IntentClassifierResult>> {
-        self.intent_classifier.get_intents(input)
-    }
-
-    fn get_slots(&self, input: &str, intent: &str) -> Result<Vec<InternalSlot>> {
+    fn get_slots_with_feature_cache(
+        &self,
+        input: &str,
+        intent: &str,
+        feature_cache: &FeatureCache,
+    ) -> Result<Vec<InternalSlot>> {
         self.slot_fillers
             .get(intent)
-            .ok_or_else(|| format_err!("Unknown intent: {}", intent))
-            .and_then(|slot_filler| slot_filler.get_slots(input))
+            .ok_or_else(|| SnipsNluError::UnknownIntent(intent.to_string()).into())
+            .and_then(|slot_filler| slot_filler.get_slots_with_feature_cache(input, feature_cache))
+    }
+
+    fn vocabulary(&self) -> Vocabulary {
+        self.intent_classifier.vocabulary()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::intent_classifier::{LogRegIntentClassifier, OutOfScopeReason};
+    use crate::models::OutOfScopeDetectionConfig;
     use crate::resources::loading::load_engine_shared_resources;
     use crate::slot_utils::InternalSlot;
 
@@ -159,4 +185,45 @@ mod tests {
         ];
         assert_eq!(expected_slots, slots);
     }
+
+    #[test]
+    fn test_parse_returns_out_of_scope_reason() {
+        // Given
+        let trained_engine_path = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage");
+        let classifier_path = trained_engine_path
+            .join("probabilistic_intent_parser")
+            .join("intent_classifier");
+        let resources = load_engine_shared_resources(trained_engine_path).unwrap();
+        let config = OutOfScopeDetectionConfig {
+            max_unknown_words_ratio: Some(0.5),
+            min_score_margin: None,
+        };
+        let intent_classifier = LogRegIntentClassifier::from_path(classifier_path, resources)
+            .unwrap()
+            .with_out_of_scope_detection(Some(config));
+        let intent_parser = ProbabilisticIntentParser {
+            intent_classifier: Box::new(intent_classifier),
+            slot_fillers: HashMap::new(),
+        };
+
+        // When
+        let parsing_result = intent_parser
+            .parse("make me a sandwich with some cheese", None)
+            .unwrap();
+
+        // Then
+        assert_eq!(None, parsing_result.intent.intent_name);
+        match parsing_result.out_of_scope_reason {
+            Some(OutOfScopeReason::UnknownWordsRatio { max_ratio, .. }) => {
+                assert_eq!(0.5, max_ratio)
+            }
+            reason => panic!(
+                "Expected an unknown words ratio out-of-scope reason, found {:?}",
+                reason
+            ),
+        }
+    }
 }
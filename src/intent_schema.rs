@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use snips_nlu_ontology::{Slot, SlotValue};
+
+use crate::errors::*;
+use crate::models::{IntentSchema, SlotSchema};
+use crate::slot_post_processing::SlotValidationError;
+use crate::utils::{EntityName, IntentName, SlotName};
+
+/// Slots of a parsing result checked against the schema of its intent
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CheckedSlots {
+    /// Slots whose value is allowed by the schema
+    pub slots: Vec<Slot>,
+    pub missing_slots: Vec<SlotName>,
+    pub duplicated_slots: Vec<SlotName>,
+    pub invalid_slots: Vec<SlotValidationError>,
+}
+
+/// Checks that the intents and slots of the schema are known by the engine
+pub fn validate_intent_schema(
+    schema: &IntentSchema,
+    slot_name_mappings: &HashMap<IntentName, HashMap<SlotName, EntityName>>,
+) -> Result<()> {
+    for (intent_name, intent_schema) in schema.intents.iter() {
+        let slot_name_mapping = slot_name_mappings
+            .get(intent_name)
+            .ok_or_else(|| SnipsNluError::UnknownIntent(intent_name.to_string()))?;
+        if let Some(unknown_slot) = intent_schema
+            .slots
+            .keys()
+            .find(|slot_name| !slot_name_mapping.contains_key(*slot_name))
+        {
+            return Err(SnipsNluError::UnknownSlot(unknown_slot.to_string()).into());
+        }
+    }
+    Ok(())
+}
+
+/// Checks the slots extracted for an intent against the schema of this intent, the slots having a
+/// value which is not allowed being reported as invalid
+pub fn check_slots(
+    schema: &IntentSchema,
+    intent_name: Option<&str>,
+    slots: Vec<Slot>,
+) -> CheckedSlots {
+    let intent_schema = match intent_name.and_then(|name| schema.intents.get(name)) {
+        Some(intent_schema) => intent_schema,
+        None => {
+            return CheckedSlots {
+                slots,
+                ..Default::default()
+            }
+        }
+    };
+    let mut checked_slots = CheckedSlots::default();
+    for slot in slots {
+        let is_allowed = intent_schema
+            .slots
+            .get(&slot.slot_name)
+            .map(|slot_schema| is_allowed_value(slot_schema, &slot))
+            .unwrap_or(true);
+        if is_allowed {
+            checked_slots.slots.push(slot);
+        } else {
+            let reason = format!(
+                "Value '{}' is not allowed for slot '{}'",
+                slot_value_repr(&slot),
+                slot.slot_name
+            );
+            checked_slots
+                .invalid_slots
+                .push(SlotValidationError { slot, reason });
+        }
+    }
+    for (slot_name, slot_schema) in intent_schema.slots.iter().sorted_by_key(|(name, _)| *name) {
+        let count = checked_slots
+            .slots
+            .iter()
+            .filter(|slot| slot.slot_name == *slot_name)
+            .count();
+        if slot_schema.required && count == 0 {
+            checked_slots.missing_slots.push(slot_name.to_string());
+        }
+        if slot_schema
+            .max_count
+            .map(|max_count| count > max_count)
+            .unwrap_or(false)
+        {
+            checked_slots.duplicated_slots.push(slot_name.to_string());
+        }
+    }
+    checked_slots
+}
+
+fn is_allowed_value(slot_schema: &SlotSchema, slot: &Slot) -> bool {
+    slot_schema
+        .allowed_values
+        .as_ref()
+        .map(|allowed_values| {
+            allowed_values
+                .iter()
+                .any(|value| value == slot_value_repr(slot))
+        })
+        .unwrap_or(true)
+}
+
+fn slot_value_repr(slot: &Slot) -> &str {
+    match &slot.value {
+        SlotValue::Custom(string_value) => &string_value.value,
+        _ => &slot.raw_value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use maplit::hashmap;
+    use snips_nlu_ontology::NumberValue;
+
+    use crate::models::IntentSlotsSchema;
+
+    fn custom_slot(value: &str, slot_name: &str) -> Slot {
+        Slot {
+            raw_value: value.to_lowercase(),
+            value: SlotValue::Custom(value.to_string().into()),
+            alternatives: vec![],
+            range: 0..value.chars().count(),
+            entity: slot_name.to_string(),
+            slot_name: slot_name.to_string(),
+            confidence_score: None,
+        }
+    }
+
+    fn build_schema() -> IntentSchema {
+        IntentSchema {
+            intents: hashmap! {
+                "MakeTea".to_string() => IntentSlotsSchema {
+                    slots: hashmap! {
+                        "number_of_cups".to_string() => SlotSchema {
+                            required: true,
+                            max_count: Some(1),
+                            allowed_values: None,
+                        },
+                        "beverage_temperature".to_string() => SlotSchema {
+                            required: false,
+                            max_count: Some(1),
+                            allowed_values: Some(vec!["hot".to_string(), "cold".to_string()]),
+                        },
+                    }
+                }
+            },
+        }
+    }
+
+    #[test]
+    fn test_check_slots() {
+        // Given
+        let schema = build_schema();
+        let slots = vec![
+            custom_slot("hot", "beverage_temperature"),
+            custom_slot("Boiling", "beverage_temperature"),
+            custom_slot("cold", "beverage_temperature"),
+        ];
+
+        // When
+        let checked_slots = check_slots(&schema, Some("MakeTea"), slots);
+
+        // Then
+        let expected_checked_slots = CheckedSlots {
+            slots: vec![
+                custom_slot("hot", "beverage_temperature"),
+                custom_slot("cold", "beverage_temperature"),
+            ],
+            missing_slots: vec!["number_of_cups".to_string()],
+            duplicated_slots: vec!["beverage_temperature".to_string()],
+            invalid_slots: vec![SlotValidationError {
+                slot: custom_slot("Boiling", "beverage_temperature"),
+                reason: "Value 'Boiling' is not allowed for slot 'beverage_temperature'"
+                    .to_string(),
+            }],
+        };
+        assert_eq!(expected_checked_slots, checked_slots);
+    }
+
+    #[test]
+    fn test_check_slots_without_intent_schema() {
+        // Given
+        let schema = build_schema();
+        let slots = vec![Slot {
+            raw_value: "two".to_string(),
+            value: SlotValue::Number(NumberValue { value: 2.0 }),
+            alternatives: vec![],
+            range: 8..11,
+            entity: "snips/number".to_string(),
+            slot_name: "number_of_cups".to_string(),
+            confidence_score: None,
+        }];
+
+        // When
+        let checked_slots = check_slots(&schema, Some("MakeCoffee"), slots.clone());
+
+        // Then
+        let expected_checked_slots = CheckedSlots {
+            slots,
+            ..Default::default()
+        };
+        assert_eq!(expected_checked_slots, checked_slots);
+    }
+
+    #[test]
+    fn test_validate_intent_schema() {
+        // Given
+        let schema = build_schema();
+        let known_slots = hashmap! {
+            "MakeTea".to_string() => hashmap! {
+                "number_of_cups".to_string() => "snips/number".to_string(),
+                "beverage_temperature".to_string() => "Temperature".to_string(),
+            }
+        };
+        let missing_slot = hashmap! {
+            "MakeTea".to_string() => hashmap! {
+                "number_of_cups".to_string() => "snips/number".to_string(),
+            }
+        };
+
+        // When
+        let valid_result = validate_intent_schema(&schema, &known_slots);
+        let invalid_result = validate_intent_schema(&schema, &missing_slot);
+        let unknown_intent_result = validate_intent_schema(&schema, &HashMap::new());
+
+        // Then
+        assert!(valid_result.is_ok());
+        assert_eq!(
+            SnipsNluErrorKind::UnknownSlot,
+            error_kind(&invalid_result.unwrap_err())
+        );
+        assert_eq!(
+            SnipsNluErrorKind::UnknownIntent,
+            error_kind(&unknown_intent_result.unwrap_err())
+        );
+    }
+}
@@ -4,17 +4,22 @@
     clippy::module_inception
 )]
 
+mod engine_lock;
 mod entity_parser;
 pub mod errors;
 pub mod injection;
 mod intent_classifier;
 mod intent_parser;
+mod intent_schema;
 mod language;
 pub mod models;
 mod nlu_engine;
+mod normalization;
 mod resources;
 mod slot_filler;
+mod slot_post_processing;
 mod slot_utils;
+mod spelling_correction;
 #[cfg(test)]
 mod testutils;
 mod utils;
@@ -22,14 +27,31 @@ mod utils;
 pub const MODEL_VERSION: &str = "0.20.0";
 
 pub extern crate snips_nlu_ontology as ontology;
+pub use crate::entity_parser::{CacheStatistics, EntityMatch};
 pub use crate::errors::*;
-pub use crate::intent_classifier::{IntentClassifier, LogRegIntentClassifier};
+pub use crate::intent_classifier::{
+    FeatureContribution, FeatureKind, IntentClassifier, LinearIntentClassifier,
+    LogRegIntentClassifier, OutOfScopeReason, ScopedIntentParserResult,
+};
 pub use crate::intent_parser::{
     DeterministicIntentParser, IntentParser, LookupIntentParser, ProbabilisticIntentParser,
 };
 pub use crate::models::*;
-pub use crate::nlu_engine::SnipsNluEngine;
-pub use crate::resources::loading::load_shared_resources;
+pub use crate::nlu_engine::{EntityParsersCacheStatistics, NluEngineConfig, SnipsNluEngine};
+pub use crate::normalization::{NormalizationStep, NormalizedText, TextNormalizer};
+pub use crate::resources::loading::{
+    load_shared_resources, load_shared_resources_with_cache_capacity,
+};
 pub use crate::resources::SharedResources;
-pub use crate::slot_filler::{CRFSlotFiller, SlotFiller};
+pub use crate::slot_filler::{
+    CRFModel, CRFSlotFiller, Feature, FeatureCache, FeatureRegistry, SlotFiller,
+};
+pub use crate::slot_post_processing::{
+    SlotPostProcessing, SlotPostProcessor, SlotPostProcessors, SlotValidationError,
+    ValidatedIntentParserResult,
+};
+pub use crate::slot_utils::{NestedIntentParserResult, NestedSlot};
+pub use crate::spelling_correction::{
+    CorrectedIntentParserResult, SpellingCorrection, SpellingCorrectionConfig, Vocabulary,
+};
 pub use snips_nlu_ontology::Language;
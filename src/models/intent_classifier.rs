@@ -10,6 +10,41 @@ pub struct IntentClassifierModel {
     pub intercept: Option<Vec<f32>>,
     pub coeffs: Option<Vec<Vec<f32>>>,
     pub intent_list: Vec<Option<IntentName>>,
+    #[serde(default)]
+    pub out_of_scope_detection: Option<OutOfScopeDetectionConfig>,
+}
+
+/// Limits beyond which an input is considered out of scope, regardless of the intent scores
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub struct OutOfScopeDetectionConfig {
+    /// Maximum ratio of input words which are missing from the tf-idf vocabulary
+    #[serde(default)]
+    pub max_unknown_words_ratio: Option<f32>,
+    /// Minimum difference between the two best intent scores, computed over the intents of the
+    /// whitelist and excluding the None intent
+    #[serde(default)]
+    pub min_score_margin: Option<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LinearIntentClassifierModel {
+    pub featurizer: Option<String>,
+    pub intercept: Option<Vec<f32>>,
+    pub coeffs: Option<Vec<Vec<f32>>>,
+    pub intent_list: Vec<Option<IntentName>>,
+    pub output: LinearClassifierOutput,
+}
+
+/// Describes how the decision scores of a linear model are mapped to probabilities
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+#[serde(rename_all = "snake_case")]
+pub enum LinearClassifierOutput {
+    /// Multinomial model, the probabilities are the softmax of the scores
+    Softmax,
+    /// One-vs-rest model, typically a linear SVM, whose score `s` for class `i` is mapped to
+    /// `1 / (1 + exp(a[i] * s + b[i]))` before normalizing the probabilities across classes
+    PlattScaling { a: Vec<f32>, b: Vec<f32> },
 }
 
 #[derive(Debug, Deserialize)]
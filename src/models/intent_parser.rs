@@ -13,6 +13,10 @@ pub struct DeterministicParserModel {
     pub language_code: String,
     pub patterns: HashMap<IntentName, Vec<String>>,
     pub group_names_to_slot_names: HashMap<String, SlotName>,
+    /// Group names of the slots which can contain the slots of other groups, used to extract
+    /// nested slots
+    #[serde(default)]
+    pub group_names_to_parent_group_names: HashMap<String, String>,
     pub slot_names_to_entities: HashMap<IntentName, HashMap<SlotName, EntityName>>,
     #[serde(default)]
     pub stop_words_whitelist: HashMap<IntentName, Vec<String>>,
@@ -52,6 +56,21 @@ pub struct DeterministicParserConfig {
 pub struct LookupParserConfig {
     #[serde(default)]
     pub ignore_stop_words: bool,
+    /// Maximum number of entity subsets tried when looking up an input
+    #[serde(default = "default_max_entity_combinations")]
+    pub max_entity_combinations: usize,
+    /// Stop the lookup at the first candidate found in the map, instead of trying all subsets
+    ///
+    /// This is disabled by default because the ambiguity between intents can only be detected by
+    /// trying all the subsets: the parser then returns no intent when several intents match the
+    /// input with the same number of slots, while the first matching intent is returned with a
+    /// full confidence when stopping early.
+    #[serde(default)]
+    pub stop_at_first_match: bool,
+}
+
+fn default_max_entity_combinations() -> usize {
+    1024
 }
 
 #[derive(Debug, Deserialize)]
@@ -64,3 +83,19 @@ pub struct SlotFillerMetadata {
     pub intent: IntentName,
     pub slot_filler_name: String,
 }
+
+/// Calibration of the intent scores of a parser, stored in an optional `calibration.json` file
+/// of the parser directory
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+#[serde(rename_all = "snake_case")]
+pub enum CalibrationModel {
+    /// The logit of the score is divided by the temperature
+    TemperatureScaling { temperature: f32 },
+    /// The score is linearly interpolated between the points of an isotonic regression,
+    /// thresholds being sorted in increasing order
+    Isotonic {
+        thresholds: Vec<f32>,
+        values: Vec<f32>,
+    },
+}
@@ -0,0 +1,32 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::utils::{IntentName, SlotName};
+
+/// Optional description of the slots expected by each intent, loaded from the
+/// `intent_schema.json` file of the engine directory
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct IntentSchema {
+    pub intents: HashMap<IntentName, IntentSlotsSchema>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct IntentSlotsSchema {
+    #[serde(default)]
+    pub slots: HashMap<SlotName, SlotSchema>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct SlotSchema {
+    /// Whether the slot must be present in the parsing results of the intent
+    #[serde(default)]
+    pub required: bool,
+    /// Maximum number of occurrences of the slot in a parsing result
+    #[serde(default)]
+    pub max_count: Option<usize>,
+    /// Values accepted for the slot, compared to the resolved value of custom entity slots and
+    /// to the raw value of builtin entity slots
+    #[serde(default)]
+    pub allowed_values: Option<Vec<String>>,
+}
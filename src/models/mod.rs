@@ -1,11 +1,13 @@
 pub mod intent_classifier;
 pub mod intent_parser;
+pub mod intent_schema;
 pub mod nlu_engine;
 pub mod processing_unit_metadata;
 pub mod slot_filler;
 
 pub use self::intent_classifier::*;
 pub use self::intent_parser::*;
+pub use self::intent_schema::*;
 pub use self::nlu_engine::*;
 pub use self::processing_unit_metadata::*;
 pub use self::slot_filler::*;
@@ -9,6 +9,7 @@ pub enum ProcessingUnitMetadata {
     ProbabilisticIntentParser,
     CrfSlotFiller,
     LogRegIntentClassifier,
+    LinearIntentClassifier,
 }
 
 #[cfg(test)]
@@ -24,4 +25,13 @@ mod tests {
         let metadata: ProcessingUnitMetadata = serde_json::from_str(data).unwrap();
         assert_eq!(ProcessingUnitMetadata::CrfSlotFiller, metadata);
     }
+
+    #[test]
+    fn test_deserialize_linear_intent_classifier() {
+        let data = r#"{
+                        "unit_name": "linear_intent_classifier"
+                      }"#;
+        let metadata: ProcessingUnitMetadata = serde_json::from_str(data).unwrap();
+        assert_eq!(ProcessingUnitMetadata::LinearIntentClassifier, metadata);
+    }
 }
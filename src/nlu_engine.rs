@@ -1,3 +1,4 @@
+use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io;
@@ -5,33 +6,151 @@ use std::path::Path;
 use std::str::FromStr;
 use std::sync::Arc;
 
-use failure::{bail, format_err, ResultExt};
+use failure::{bail, ResultExt};
 use itertools::Itertools;
+use log::warn;
 use snips_nlu_ontology::{
     BuiltinEntityKind, IntentClassifierResult, IntentParserResult, Language, Slot, SlotValue,
 };
 use snips_nlu_utils::string::substring_with_char_range;
 
-use crate::entity_parser::{BuiltinEntityParser, CustomEntityParser};
+use crate::engine_lock::EngineDirLock;
+use crate::entity_parser::{BuiltinEntityParser, CacheStatistics, CustomEntityParser, EntityMatch};
 use crate::errors::*;
+use crate::intent_classifier::ScopedIntentParserResult;
+use crate::intent_parser::calibration::load_calibrator;
 use crate::intent_parser::*;
+use crate::intent_schema::{check_slots, validate_intent_schema};
 use crate::models::{
-    DatasetMetadata, Entity, ModelVersion, NluEngineModel, ProcessingUnitMetadata,
+    DatasetMetadata, Entity, IntentSchema, ModelVersion, NluEngineModel, ProcessingUnitMetadata,
 };
+use crate::normalization::{NormalizationStep, NormalizedText, TextNormalizer};
 use crate::ontology::IntentParserAlternative;
-use crate::resources::loading::load_shared_resources;
+use crate::resources::loading::{
+    load_gazetteers_vocabulary, load_inflections, load_shared_resources_with_feature_registry,
+    DEFAULT_ENTITY_PARSER_CACHE_CAPACITY,
+};
 use crate::resources::SharedResources;
+use crate::slot_filler::{FeatureCache, FeatureRegistry};
+use crate::slot_post_processing::{
+    SlotPostProcessors, SlotValidationError, ValidatedIntentParserResult,
+};
 use crate::slot_utils::*;
-use crate::utils::{extract_nlu_engine_zip_archive, EntityName, IterOps, SlotName};
+use crate::spelling_correction::{
+    CorrectedIntentParserResult, SpellingCorrection, SpellingCorrectionConfig, SpellingCorrector,
+    Vocabulary,
+};
+use crate::utils::{cmp_scores, extract_nlu_engine_zip_archive, EntityName, IterOps, SlotName};
 
 pub struct SnipsNluEngine {
     dataset_metadata: DatasetMetadata,
     intent_parsers: Vec<Box<dyn IntentParser>>,
     shared_resources: Arc<SharedResources>,
+    max_input_length: Option<usize>,
+    slot_post_processors: SlotPostProcessors,
+    intent_schema: Option<IntentSchema>,
+    normalizer: TextNormalizer,
+    spelling_corrector: Option<SpellingCorrector>,
+}
+
+/// Options used when loading a `SnipsNluEngine`
+#[derive(Debug, Clone)]
+pub struct NluEngineConfig {
+    entity_parser_cache_capacity: usize,
+    max_input_length: Option<usize>,
+    feature_registry: FeatureRegistry,
+    slot_post_processors: SlotPostProcessors,
+    intent_schema: Option<IntentSchema>,
+    normalization_steps: Vec<NormalizationStep>,
+    spelling_correction: Option<SpellingCorrectionConfig>,
+}
+
+impl Default for NluEngineConfig {
+    fn default() -> Self {
+        Self {
+            entity_parser_cache_capacity: DEFAULT_ENTITY_PARSER_CACHE_CAPACITY,
+            max_input_length: None,
+            feature_registry: FeatureRegistry::default(),
+            slot_post_processors: SlotPostProcessors::default(),
+            intent_schema: None,
+            normalization_steps: vec![],
+            spelling_correction: None,
+        }
+    }
+}
+
+impl NluEngineConfig {
+    /// Number of results cached by each of the builtin and custom entity parsers, 0 disables
+    /// the caches
+    pub fn entity_parser_cache_capacity(mut self, capacity: usize) -> Self {
+        self.entity_parser_cache_capacity = capacity;
+        self
+    }
+
+    /// Maximum number of characters of the inputs, longer inputs being rejected with a
+    /// `SnipsNluError::InputTooLong` error
+    pub fn max_input_length(mut self, max_length: usize) -> Self {
+        self.max_input_length = Some(max_length);
+        self
+    }
+
+    /// Features provided by the application, which the CRF slot fillers of the engine can use
+    pub fn feature_registry(mut self, feature_registry: FeatureRegistry) -> Self {
+        self.feature_registry = feature_registry;
+        self
+    }
+
+    /// Hooks applied to the resolved slots, which can transform, validate or reject them
+    pub fn slot_post_processors(mut self, slot_post_processors: SlotPostProcessors) -> Self {
+        self.slot_post_processors = slot_post_processors;
+        self
+    }
+
+    /// Schema of the slots expected by each intent, which takes precedence over the
+    /// `intent_schema.json` file of the engine directory
+    pub fn intent_schema(mut self, intent_schema: IntentSchema) -> Self {
+        self.intent_schema = Some(intent_schema);
+        self
+    }
+
+    /// Normalization steps applied, in order, to the inputs before they are parsed, the ranges of
+    /// the slots and entities still referring to the original inputs
+    pub fn normalization_steps(mut self, normalization_steps: Vec<NormalizationStep>) -> Self {
+        self.normalization_steps = normalization_steps;
+        self
+    }
+
+    /// Enables the spelling correction of the inputs, whose corrections are parsed along with the
+    /// inputs, the best parsing result being kept
+    pub fn spelling_correction(mut self, spelling_correction: SpellingCorrectionConfig) -> Self {
+        self.spelling_correction = Some(spelling_correction);
+        self
+    }
+}
+
+/// Usage statistics of the caches of the builtin and custom entity parsers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntityParsersCacheStatistics {
+    pub builtin_entity_parser: Option<CacheStatistics>,
+    pub custom_entity_parser: Option<CacheStatistics>,
+}
+
+/// Normalized input which has been parsed, possibly after the correction of its spelling
+struct ParsedInput {
+    text: NormalizedText,
+    corrections: Vec<SpellingCorrection>,
+    parsing_result: InternalParsingResult,
 }
 
 impl SnipsNluEngine {
     pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_path_with_config(path, NluEngineConfig::default())
+    }
+
+    pub fn from_path_with_config<P: AsRef<Path>>(path: P, config: NluEngineConfig) -> Result<Self> {
+        // Prevents loading the engine while an injection is modifying it
+        let _lock = EngineDirLock::shared(&path)
+            .with_context(|_| format!("Could not lock nlu engine directory {:?}", path.as_ref()))?;
         let model = SnipsNluEngine::load_model(&path)?;
 
         let language = Language::from_str(&model.dataset_metadata.language_code)?;
@@ -40,15 +159,53 @@ impl SnipsNluEngine {
         let builtin_parser_path = path.as_ref().join(&model.builtin_entity_parser);
         let custom_parser_path = path.as_ref().join(&model.custom_entity_parser);
 
-        let shared_resources =
-            load_shared_resources(&resources_path, builtin_parser_path, custom_parser_path)?;
+        let shared_resources = load_shared_resources_with_feature_registry(
+            &resources_path,
+            builtin_parser_path,
+            custom_parser_path,
+            config.entity_parser_cache_capacity,
+            config.feature_registry,
+        )
+        .with_context(|_| SnipsNluError::ModelLoad(resources_path.to_string_lossy().to_string()))?;
 
-        let parsers = Self::load_intent_parsers(path, &model, shared_resources.clone())?;
+        let parsers = Self::load_intent_parsers(&path, &model, shared_resources.clone())
+            .with_context(|_| {
+                SnipsNluError::ModelLoad(path.as_ref().to_string_lossy().to_string())
+            })?;
+
+        let intent_schema = match config.intent_schema {
+            Some(intent_schema) => Some(intent_schema),
+            None => Self::load_intent_schema(&path)?,
+        };
+        if let Some(intent_schema) = intent_schema.as_ref() {
+            validate_intent_schema(intent_schema, &model.dataset_metadata.slot_name_mappings)
+                .with_context(|_| "Invalid intent schema".to_string())?;
+        }
+
+        let spelling_corrector = match config.spelling_correction {
+            Some(spelling_correction) => Some(
+                Self::load_spelling_corrector(
+                    &resources_path,
+                    &parsers,
+                    spelling_correction,
+                    shared_resources.clone(),
+                )
+                .with_context(|_| {
+                    SnipsNluError::ModelLoad(resources_path.to_string_lossy().to_string())
+                })?,
+            ),
+            None => None,
+        };
 
         Ok(SnipsNluEngine {
             dataset_metadata: model.dataset_metadata,
             intent_parsers: parsers,
             shared_resources,
+            max_input_length: config.max_input_length,
+            slot_post_processors: config.slot_post_processors,
+            intent_schema,
+            normalizer: TextNormalizer::new(config.normalization_steps, language),
+            spelling_corrector,
         })
     }
 
@@ -77,6 +234,56 @@ impl SnipsNluEngine {
         Ok(model)
     }
 
+    /// Builds the spelling corrector from the vocabulary of the intent parsers and of the
+    /// gazetteers, and from the custom entity values which can be listed
+    fn load_spelling_corrector<P: AsRef<Path>>(
+        resources_dir: P,
+        intent_parsers: &[Box<dyn IntentParser>],
+        config: SpellingCorrectionConfig,
+        shared_resources: Arc<SharedResources>,
+    ) -> Result<SpellingCorrector> {
+        let mut vocabulary = Vocabulary::default();
+        for intent_parser in intent_parsers {
+            vocabulary.extend(intent_parser.vocabulary());
+        }
+        if vocabulary.is_empty() {
+            warn!(
+                "The intent parsers have no vocabulary, the inputs are only corrected with the \
+                 gazetteers, the custom entity values and the additional words"
+            );
+        }
+        vocabulary.extend(load_gazetteers_vocabulary(&resources_dir)?);
+        vocabulary
+            .words
+            .extend(shared_resources.custom_entity_parser.entity_values());
+        let inflections = if vocabulary.stems.is_empty() {
+            HashMap::new()
+        } else {
+            load_inflections(&resources_dir)?
+        };
+        if !vocabulary.stems.is_empty() && inflections.is_empty() {
+            warn!("The resources have no stems file, the stems of the vocabulary are used as is");
+        }
+        Ok(SpellingCorrector::new(
+            vocabulary.into_words(&inflections),
+            config,
+            shared_resources,
+        ))
+    }
+
+    fn load_intent_schema<P: AsRef<Path>>(engine_dir: P) -> Result<Option<IntentSchema>> {
+        let schema_path = engine_dir.as_ref().join("intent_schema.json");
+        if !schema_path.exists() {
+            return Ok(None);
+        }
+        let schema_file = fs::File::open(&schema_path)
+            .with_context(|_| format!("Could not open intent schema file {:?}", &schema_path))?;
+        let intent_schema = serde_json::from_reader(schema_file).with_context(|_| {
+            SnipsNluError::ModelLoad(schema_path.to_string_lossy().to_string())
+        })?;
+        Ok(Some(intent_schema))
+    }
+
     fn load_intent_parsers<P: AsRef<Path>>(
         engine_dir: P,
         model: &NluEngineModel,
@@ -98,7 +305,13 @@ impl SnipsNluEngine {
                             parser_name
                         )
                     })?;
-                Ok(build_intent_parser(metadata, parser_path, shared_resources.clone())? as _)
+                let parser = build_intent_parser(metadata, &parser_path, shared_resources.clone())?;
+                // Calibration makes the scores of the different parsers comparable
+                Ok(match load_calibrator(&parser_path)? {
+                    Some(calibrator) => Box::new(CalibratedIntentParser::new(parser, calibrator))
+                        as Box<dyn IntentParser>,
+                    None => parser,
+                })
             })
             .collect::<Result<Vec<_>>>()
     }
@@ -110,23 +323,55 @@ impl SnipsNluEngine {
         path: P,
         shared_resources: Arc<SharedResources>,
     ) -> Result<Self> {
+        let _lock = EngineDirLock::shared(&path)?;
         let model = SnipsNluEngine::load_model(&path)?;
+        let language = Language::from_str(&model.dataset_metadata.language_code)?;
         let parsers = Self::load_intent_parsers(path, &model, shared_resources.clone())?;
 
         Ok(SnipsNluEngine {
             dataset_metadata: model.dataset_metadata,
             intent_parsers: parsers,
             shared_resources,
+            max_input_length: None,
+            slot_post_processors: SlotPostProcessors::default(),
+            intent_schema: None,
+            normalizer: TextNormalizer::new(vec![], language),
+            spelling_corrector: None,
         })
     }
 }
 
 impl SnipsNluEngine {
     pub fn from_zip<R: io::Read + io::Seek>(reader: R) -> Result<Self> {
+        Self::from_zip_with_config(reader, NluEngineConfig::default())
+    }
+
+    pub fn from_zip_with_config<R: io::Read + io::Seek>(
+        reader: R,
+        config: NluEngineConfig,
+    ) -> Result<Self> {
         let temp_dir = tempfile::Builder::new().prefix("temp_dir_nlu_").tempdir()?;
         let temp_dir_path = temp_dir.path();
         let engine_dir_path = extract_nlu_engine_zip_archive(reader, temp_dir_path)?;
-        Ok(SnipsNluEngine::from_path(engine_dir_path)?)
+        Ok(SnipsNluEngine::from_path_with_config(
+            engine_dir_path,
+            config,
+        )?)
+    }
+}
+
+impl SnipsNluEngine {
+    pub fn entity_parsers_cache_statistics(&self) -> EntityParsersCacheStatistics {
+        EntityParsersCacheStatistics {
+            builtin_entity_parser: self
+                .shared_resources
+                .builtin_entity_parser
+                .cache_statistics(),
+            custom_entity_parser: self
+                .shared_resources
+                .custom_entity_parser
+                .cache_statistics(),
+        }
     }
 }
 
@@ -156,51 +401,40 @@ impl SnipsNluEngine {
         W: Into<Option<Vec<&'a str>>>,
         B: Into<Option<Vec<&'b str>>>,
     {
+        self.check_input_length(input)?;
         let intents_whitelist_owned =
             self.get_intents_whitelist(intents_whitelist, intents_blacklist)?;
         let intents_whitelist = intents_whitelist_owned
             .as_ref()
             .map(|whitelist| whitelist.as_ref());
-        let mut parsing_result: Option<IntentParserResult> = None;
-        let mut none_score: f32 = 0.0;
-        for parser in &self.intent_parsers {
-            let internal_parsing_result = parser.parse(input, intents_whitelist)?;
-            if internal_parsing_result.intent.intent_name.is_some() {
-                let resolved_slots = self
-                    .resolve_slots(input, internal_parsing_result.slots, slots_alternatives)
-                    .with_context(|_| "Cannot resolve slots".to_string())?;
-
-                parsing_result = Some(IntentParserResult {
-                    input: input.to_string(),
-                    intent: internal_parsing_result.intent,
-                    slots: resolved_slots,
-                    alternatives: vec![],
-                });
-                break;
-            } else {
-                none_score = internal_parsing_result.intent.confidence_score;
-            }
-        }
-        let mut parsing_result = parsing_result.unwrap_or_else(|| {
-            // If all parsers failed to extract an intent, we use the confidence score
-            // returned by the last parser
-            IntentParserResult {
-                input: input.to_string(),
-                intent: IntentClassifierResult {
-                    intent_name: None,
-                    confidence_score: none_score,
-                },
-                slots: vec![],
-                alternatives: vec![],
-            }
-        });
+        // The feature values are shared by the slot fillers run for the intent alternatives
+        let feature_cache = FeatureCache::default();
+        let ParsedInput {
+            text: normalized_input,
+            parsing_result: internal_parsing_result,
+            ..
+        } = self.parse_input(input, intents_whitelist, &feature_cache)?;
+        let resolved_slots = self
+            .resolve_slots(
+                input,
+                &normalized_input,
+                internal_parsing_result.slots,
+                slots_alternatives,
+            )
+            .with_context(|_| "Cannot resolve slots".to_string())?;
+        let mut parsing_result = IntentParserResult {
+            input: input.to_string(),
+            intent: internal_parsing_result.intent,
+            slots: resolved_slots,
+            alternatives: vec![],
+        };
 
         if intents_alternatives == 0 {
             return Ok(parsing_result);
         }
 
         let alternative_results: Vec<IntentParserAlternative> = self
-            .get_intents(input)?
+            .get_normalized_intents(&normalized_input)?
             .into_iter()
             .filter(|res| {
                 res.intent_name
@@ -218,10 +452,12 @@ impl SnipsNluEngine {
                 res.intent_name
                     .as_ref()
                     .map(|intent_name| {
-                        Ok(self.get_slots_with_alternatives(
+                        Ok(self.get_normalized_slots(
                             input,
+                            &normalized_input,
                             intent_name,
                             slots_alternatives,
+                            &feature_cache,
                         )?)
                     })
                     .unwrap_or_else(|| Ok(vec![]))
@@ -233,6 +469,273 @@ impl SnipsNluEngine {
         Ok(parsing_result)
     }
 
+    /// Parses the input like `parse`, but also returns the slots which are nested in other slots
+    ///
+    /// Nested slots are extracted by the deterministic intent parsers whose model maps the
+    /// groups of the nested slots to the groups of their parent slots. A nested slot is dropped
+    /// along with its parent slot when the latter cannot be resolved.
+    pub fn parse_with_nested_slots<'a, 'b, W, B>(
+        &self,
+        input: &str,
+        intents_whitelist: W,
+        intents_blacklist: B,
+    ) -> Result<NestedIntentParserResult>
+    where
+        W: Into<Option<Vec<&'a str>>>,
+        B: Into<Option<Vec<&'b str>>>,
+    {
+        self.check_input_length(input)?;
+        let intents_whitelist_owned =
+            self.get_intents_whitelist(intents_whitelist, intents_blacklist)?;
+        let intents_whitelist = intents_whitelist_owned
+            .as_ref()
+            .map(|whitelist| whitelist.as_ref());
+        let ParsedInput {
+            text: normalized_input,
+            parsing_result: internal_parsing_result,
+            ..
+        } = self.parse_input(input, intents_whitelist, &FeatureCache::default())?;
+        let internal_slots = internal_parsing_result.slots;
+        let resolved_slots = self
+            .resolve_each_slot(input, &normalized_input, internal_slots.clone(), 0)
+            .with_context(|_| "Cannot resolve slots".to_string())?;
+        let resolved_slots = internal_slots
+            .into_iter()
+            .zip(resolved_slots.into_iter().map(|opt_slot| {
+                opt_slot.map(|slot| self.slot_post_processors.transform_slot(slot))
+            }))
+            .collect::<Vec<_>>();
+        let nested_slots = nest_slots(
+            resolved_slots
+                .iter()
+                .map(|(internal_slot, _)| internal_slot.clone())
+                .collect(),
+        );
+        Ok(NestedIntentParserResult {
+            input: input.to_string(),
+            intent: internal_parsing_result.intent,
+            slots: convert_to_nested_slots(nested_slots, &resolved_slots),
+        })
+    }
+
+    /// Parses the input like `parse`, but also returns the slots which have been rejected by the
+    /// slot post processors of the engine, and checks the slots against the intent schema
+    ///
+    /// The slots whose value is not allowed by the schema are reported as validation errors,
+    /// while the missing and duplicated slots are only reported.
+    pub fn parse_with_slot_validation<'a, 'b, W, B>(
+        &self,
+        input: &str,
+        intents_whitelist: W,
+        intents_blacklist: B,
+    ) -> Result<ValidatedIntentParserResult>
+    where
+        W: Into<Option<Vec<&'a str>>>,
+        B: Into<Option<Vec<&'b str>>>,
+    {
+        self.check_input_length(input)?;
+        let intents_whitelist_owned =
+            self.get_intents_whitelist(intents_whitelist, intents_blacklist)?;
+        let intents_whitelist = intents_whitelist_owned
+            .as_ref()
+            .map(|whitelist| whitelist.as_ref());
+        let ParsedInput {
+            text: normalized_input,
+            parsing_result: internal_parsing_result,
+            ..
+        } = self.parse_input(input, intents_whitelist, &FeatureCache::default())?;
+        let (slots, mut slot_validation_errors) = self
+            .resolve_and_validate_slots(input, &normalized_input, internal_parsing_result.slots, 0)
+            .with_context(|_| "Cannot resolve slots".to_string())?;
+        let intent = internal_parsing_result.intent;
+        let (slots, missing_slots, duplicated_slots) = match self.intent_schema.as_ref() {
+            Some(intent_schema) => {
+                let intent_name = intent.intent_name.as_ref().map(|name| &**name);
+                let checked_slots = check_slots(intent_schema, intent_name, slots);
+                slot_validation_errors.extend(checked_slots.invalid_slots);
+                (
+                    checked_slots.slots,
+                    checked_slots.missing_slots,
+                    checked_slots.duplicated_slots,
+                )
+            }
+            None => (slots, vec![], vec![]),
+        };
+        Ok(ValidatedIntentParserResult {
+            input: input.to_string(),
+            intent,
+            slots,
+            slot_validation_errors,
+            missing_slots,
+            duplicated_slots,
+        })
+    }
+
+    /// Parses the input like `parse`, but also returns the spelling corrections which have been
+    /// applied to the input before parsing it
+    ///
+    /// Corrections are only proposed when the spelling correction is enabled in the
+    /// `NluEngineConfig`. A correction is kept when it yields an intent while the input does not,
+    /// or when it yields an intent with a higher confidence score than the intent of the input.
+    pub fn parse_with_spelling_correction<'a, 'b, W, B>(
+        &self,
+        input: &str,
+        intents_whitelist: W,
+        intents_blacklist: B,
+    ) -> Result<CorrectedIntentParserResult>
+    where
+        W: Into<Option<Vec<&'a str>>>,
+        B: Into<Option<Vec<&'b str>>>,
+    {
+        self.check_input_length(input)?;
+        let intents_whitelist_owned =
+            self.get_intents_whitelist(intents_whitelist, intents_blacklist)?;
+        let intents_whitelist = intents_whitelist_owned
+            .as_ref()
+            .map(|whitelist| whitelist.as_ref());
+        let ParsedInput {
+            text: normalized_input,
+            corrections,
+            parsing_result,
+        } = self.parse_input(input, intents_whitelist, &FeatureCache::default())?;
+        let slots = self
+            .resolve_slots(input, &normalized_input, parsing_result.slots, 0)
+            .with_context(|_| "Cannot resolve slots".to_string())?;
+        let corrected_input = if corrections.is_empty() {
+            None
+        } else {
+            Some(normalized_input.text)
+        };
+        Ok(CorrectedIntentParserResult {
+            input: input.to_string(),
+            corrected_input,
+            corrections,
+            intent: parsing_result.intent,
+            slots,
+        })
+    }
+
+    /// Parses the input like `parse`, but also returns the reason why the input has been
+    /// considered out of scope by the intent classifier, if it has
+    pub fn parse_with_out_of_scope_reason<'a, 'b, W, B>(
+        &self,
+        input: &str,
+        intents_whitelist: W,
+        intents_blacklist: B,
+    ) -> Result<ScopedIntentParserResult>
+    where
+        W: Into<Option<Vec<&'a str>>>,
+        B: Into<Option<Vec<&'b str>>>,
+    {
+        self.check_input_length(input)?;
+        let intents_whitelist_owned =
+            self.get_intents_whitelist(intents_whitelist, intents_blacklist)?;
+        let intents_whitelist = intents_whitelist_owned
+            .as_ref()
+            .map(|whitelist| whitelist.as_ref());
+        let ParsedInput {
+            text: normalized_input,
+            parsing_result,
+            ..
+        } = self.parse_input(input, intents_whitelist, &FeatureCache::default())?;
+        let slots = self
+            .resolve_slots(input, &normalized_input, parsing_result.slots, 0)
+            .with_context(|_| "Cannot resolve slots".to_string())?;
+        Ok(ScopedIntentParserResult {
+            input: input.to_string(),
+            intent: parsing_result.intent,
+            slots,
+            out_of_scope_reason: parsing_result.out_of_scope_reason,
+        })
+    }
+
+    /// Normalizes and parses the input, along with its spelling corrections when the spelling
+    /// correction is enabled
+    ///
+    /// Only intent confidence scores are compared: the score of a `None` intent is the
+    /// probability of the input being out of scope, so a correction yielding an intent is always
+    /// preferred to a `None` result.
+    fn parse_input(
+        &self,
+        input: &str,
+        intents_whitelist: Option<&[&str]>,
+        feature_cache: &FeatureCache,
+    ) -> Result<ParsedInput> {
+        let normalized_input = self.normalizer.normalize(input);
+        let parsing_result =
+            self.parse_internal(&normalized_input.text, intents_whitelist, feature_cache)?;
+        let corrected_inputs = match self.spelling_corrector.as_ref() {
+            Some(spelling_corrector) => {
+                spelling_corrector.propose_corrections(input, &normalized_input)?
+            }
+            None => vec![],
+        };
+        let mut parsed_input = ParsedInput {
+            text: normalized_input,
+            corrections: vec![],
+            parsing_result,
+        };
+        for corrected_input in corrected_inputs {
+            let corrected_parsing_result =
+                self.parse_internal(&corrected_input.text.text, intents_whitelist, feature_cache)?;
+            let best_intent = &parsed_input.parsing_result.intent;
+            let is_better = corrected_parsing_result.intent.intent_name.is_some()
+                && (best_intent.intent_name.is_none()
+                    || corrected_parsing_result.intent.confidence_score
+                        > best_intent.confidence_score);
+            if is_better {
+                parsed_input = ParsedInput {
+                    text: corrected_input.text,
+                    corrections: corrected_input.corrections,
+                    parsing_result: corrected_parsing_result,
+                };
+            }
+        }
+        Ok(parsed_input)
+    }
+
+    /// Returns the result of the first intent parser which extracts an intent
+    fn parse_internal(
+        &self,
+        input: &str,
+        intents_whitelist: Option<&[&str]>,
+        feature_cache: &FeatureCache,
+    ) -> Result<InternalParsingResult> {
+        let mut none_score: f32 = 0.0;
+        let mut out_of_scope_reason = None;
+        for parser in &self.intent_parsers {
+            let internal_parsing_result =
+                parser.parse_with_feature_cache(input, intents_whitelist, feature_cache)?;
+            if internal_parsing_result.intent.intent_name.is_some() {
+                return Ok(internal_parsing_result);
+            }
+            none_score = internal_parsing_result.intent.confidence_score;
+            out_of_scope_reason = internal_parsing_result
+                .out_of_scope_reason
+                .or(out_of_scope_reason);
+        }
+        // If all parsers failed to extract an intent, we use the confidence score
+        // returned by the last parser
+        Ok(InternalParsingResult {
+            intent: IntentClassifierResult {
+                intent_name: None,
+                confidence_score: none_score,
+            },
+            slots: vec![],
+            out_of_scope_reason,
+        })
+    }
+
+    fn check_input_length(&self, input: &str) -> Result<()> {
+        if let Some(max_length) = self.max_input_length {
+            let length = input.chars().count();
+            if length > max_length {
+                bail!(SnipsNluError::InputTooLong { length, max_length });
+            }
+        }
+        Ok(())
+    }
+
     fn get_intents_whitelist<'a: 'c, 'b: 'c, 'c, W, B>(
         &'c self,
         intents_whitelist: W,
@@ -256,10 +759,7 @@ impl SnipsNluEngine {
             .flatten()
             .find(|intent| !all_intents.contains(*intent))
         {
-            return Err(format_err!(
-                "Cannot use unknown intent '{}' in intents filter",
-                unknown_intent
-            ));
+            return Err(SnipsNluError::UnknownIntent(unknown_intent.to_string()).into());
         };
         let reverted_whitelist: Option<Vec<&str>> = intents_blacklist.map(|blacklist| {
             all_intents
@@ -279,10 +779,18 @@ impl SnipsNluEngine {
     }
 
     pub fn get_intents(&self, input: &str) -> Result<Vec<IntentClassifierResult>> {
+        self.check_input_length(input)?;
+        self.get_normalized_intents(&self.normalizer.normalize(input))
+    }
+
+    fn get_normalized_intents(
+        &self,
+        normalized_input: &NormalizedText,
+    ) -> Result<Vec<IntentClassifierResult>> {
         let nb_intents = self.dataset_metadata.slot_name_mappings.len();
         let mut results = HashMap::with_capacity(nb_intents + 1);
         for parser in self.intent_parsers.iter() {
-            let parser_results = parser.get_intents(input)?;
+            let parser_results = parser.get_intents(&normalized_input.text)?;
             if results.is_empty() {
                 for res in parser_results.into_iter() {
                     results.insert(res.intent_name.clone(), res);
@@ -305,7 +813,7 @@ impl SnipsNluEngine {
         Ok(results
             .into_iter()
             .map(|(_, res)| res)
-            .sorted_by(|a, b| b.confidence_score.partial_cmp(&a.confidence_score).unwrap())
+            .sorted_by(|a, b| cmp_scores(b.confidence_score, a.confidence_score))
             .collect())
     }
 
@@ -318,25 +826,101 @@ impl SnipsNluEngine {
         input: &str,
         intent: &str,
         slots_alternatives: usize,
+    ) -> Result<Vec<Slot>> {
+        self.check_input_length(input)?;
+        let normalized_input = self.normalizer.normalize(input);
+        self.get_normalized_slots(
+            input,
+            &normalized_input,
+            intent,
+            slots_alternatives,
+            &FeatureCache::default(),
+        )
+    }
+
+    fn get_normalized_slots(
+        &self,
+        input: &str,
+        normalized_input: &NormalizedText,
+        intent: &str,
+        slots_alternatives: usize,
+        feature_cache: &FeatureCache,
     ) -> Result<Vec<Slot>> {
         for parser in &self.intent_parsers {
-            let slots = parser.get_slots(input, intent)?;
+            let slots = parser.get_slots_with_feature_cache(
+                &normalized_input.text,
+                intent,
+                feature_cache,
+            )?;
             if !slots.is_empty() {
-                return self.resolve_slots(input, slots, slots_alternatives);
+                return self.resolve_slots(input, normalized_input, slots, slots_alternatives);
             }
         }
         Ok(vec![])
     }
 
+    /// Resolves the slots which are not nested in other slots and runs the post processors on
+    /// them, the rejected slots being kept as the rejections cannot be reported
     fn resolve_slots(
         &self,
-        text: &str,
+        input: &str,
+        normalized_input: &NormalizedText,
+        slots: Vec<InternalSlot>,
+        slots_alternatives: usize,
+    ) -> Result<Vec<Slot>> {
+        let resolved_slots =
+            self.resolve_top_level_slots(input, normalized_input, slots, slots_alternatives)?;
+        Ok(self.slot_post_processors.transform_slots(resolved_slots))
+    }
+
+    /// Resolves the slots which are not nested in other slots and runs the post processors on
+    /// them, splitting the accepted slots from the rejected ones
+    fn resolve_and_validate_slots(
+        &self,
+        input: &str,
+        normalized_input: &NormalizedText,
+        slots: Vec<InternalSlot>,
+        slots_alternatives: usize,
+    ) -> Result<(Vec<Slot>, Vec<SlotValidationError>)> {
+        let resolved_slots =
+            self.resolve_top_level_slots(input, normalized_input, slots, slots_alternatives)?;
+        Ok(self.slot_post_processors.process_slots(resolved_slots))
+    }
+
+    fn resolve_top_level_slots(
+        &self,
+        input: &str,
+        normalized_input: &NormalizedText,
         slots: Vec<InternalSlot>,
         slots_alternatives: usize,
     ) -> Result<Vec<Slot>> {
+        Ok(self
+            .resolve_each_slot(
+                input,
+                normalized_input,
+                top_level_slots(slots),
+                slots_alternatives,
+            )?
+            .into_iter()
+            .flatten()
+            .collect())
+    }
+
+    /// Resolves each of the slots, the slots which cannot be resolved being mapped to `None`
+    ///
+    /// The slots are extracted from the normalized input, and the ranges of the resolved slots
+    /// are mapped back to the original input.
+    fn resolve_each_slot(
+        &self,
+        input: &str,
+        normalized_input: &NormalizedText,
+        slots: Vec<InternalSlot>,
+        slots_alternatives: usize,
+    ) -> Result<Vec<Option<Slot>>> {
         if slots.is_empty() {
             return Ok(vec![]);
         }
+        let text = &*normalized_input.text;
         let builtin_entity_scope: Vec<BuiltinEntityKind> = slots
             .iter()
             .filter_map(|slot| BuiltinEntityKind::from_str(&slot.entity).ok())
@@ -367,7 +951,7 @@ impl SnipsNluEngine {
 
         let mut resolved_slots = Vec::with_capacity(slots.len());
         for slot in slots.into_iter() {
-            let opt_resolved_slot =
+            let resolved_slot =
                 if let Some(entity) = self.dataset_metadata.entities.get(&slot.entity) {
                     resolve_custom_slot(
                         slot,
@@ -384,14 +968,42 @@ impl SnipsNluEngine {
                         slots_alternatives,
                     )?
                 };
-            if let Some(resolved_slot) = opt_resolved_slot {
-                resolved_slots.push(resolved_slot);
-            }
+            resolved_slots
+                .push(resolved_slot.map(|slot| restore_slot_range(input, normalized_input, slot)));
         }
         Ok(resolved_slots)
     }
 }
 
+/// Maps the range of a slot extracted from the normalized input to the original input
+fn restore_slot_range(input: &str, normalized_input: &NormalizedText, slot: Slot) -> Slot {
+    let range = normalized_input.original_range(&slot.range);
+    Slot {
+        raw_value: substring_with_char_range(input.to_string(), &range),
+        range,
+        ..slot
+    }
+}
+
+fn convert_to_nested_slots(
+    nested_slots: Vec<InternalNestedSlot>,
+    resolved_slots: &[(InternalSlot, Option<Slot>)],
+) -> Vec<NestedSlot> {
+    nested_slots
+        .into_iter()
+        .filter_map(|nested_slot| {
+            let slot = resolved_slots
+                .iter()
+                .find(|(internal_slot, _)| *internal_slot == nested_slot.slot)
+                .and_then(|(_, resolved_slot)| resolved_slot.clone())?;
+            Some(NestedSlot {
+                slot,
+                sub_slots: convert_to_nested_slots(nested_slot.sub_slots, resolved_slots),
+            })
+        })
+        .collect()
+}
+
 impl SnipsNluEngine {
     pub fn extract_slot(
         &self,
@@ -409,13 +1021,14 @@ impl SnipsNluEngine {
         slot_name: &str,
         slot_alternatives: usize,
     ) -> Result<Option<Slot>> {
+        self.check_input_length(&input)?;
         let entity_name = self
             .dataset_metadata
             .slot_name_mappings
             .get(intent_name)
-            .ok_or_else(|| format_err!("Unknown intent: {}", intent_name))?
+            .ok_or_else(|| SnipsNluError::UnknownIntent(intent_name.to_string()))?
             .get(slot_name)
-            .ok_or_else(|| format_err!("Unknown slot: {}", &slot_name))?;
+            .ok_or_else(|| SnipsNluError::UnknownSlot(slot_name.to_string()))?;
 
         let slot = if let Some(custom_entity) = self.dataset_metadata.entities.get(entity_name) {
             extract_custom_slot(
@@ -437,6 +1050,95 @@ impl SnipsNluEngine {
         };
         Ok(slot)
     }
+
+    /// Extracts the builtin and custom entities of the input, sorted by range, without the
+    /// slot resolution step
+    ///
+    /// The scope restricts the extraction to the provided builtin entity identifiers and custom
+    /// entity names, all the entities being extracted when it is `None`.
+    pub fn extract_entities<'a, S>(&self, input: &str, scope: S) -> Result<Vec<EntityMatch>>
+    where
+        S: Into<Option<Vec<&'a str>>>,
+    {
+        self.extract_entities_with_alternatives(input, scope, 0)
+    }
+
+    pub fn extract_entities_with_alternatives<'a, S>(
+        &self,
+        input: &str,
+        scope: S,
+        entities_alternatives: usize,
+    ) -> Result<Vec<EntityMatch>>
+    where
+        S: Into<Option<Vec<&'a str>>>,
+    {
+        self.check_input_length(input)?;
+        let (builtin_scope, custom_scope) = match scope.into() {
+            Some(scope) => {
+                let mut builtin_scope = vec![];
+                let mut custom_scope = vec![];
+                for entity in scope {
+                    if self.dataset_metadata.entities.contains_key(entity) {
+                        custom_scope.push(entity.to_string());
+                    } else if let Ok(entity_kind) = BuiltinEntityKind::from_identifier(entity) {
+                        builtin_scope.push(entity_kind);
+                    } else {
+                        bail!(SnipsNluError::UnknownEntity(entity.to_string()));
+                    }
+                }
+                (Some(builtin_scope), Some(custom_scope))
+            }
+            None => (None, None),
+        };
+
+        let normalized_input = self.normalizer.normalize(input);
+        let mut entities: Vec<EntityMatch> = vec![];
+        if builtin_scope
+            .as_ref()
+            .map(|kinds| !kinds.is_empty())
+            .unwrap_or(true)
+        {
+            let builtin_entities = self
+                .shared_resources
+                .builtin_entity_parser
+                .extract_entities(
+                    &normalized_input.text,
+                    builtin_scope.as_ref().map(|kinds| kinds.as_ref()),
+                    true,
+                    entities_alternatives,
+                )?;
+            entities.extend(builtin_entities.into_iter().map(EntityMatch::from));
+        }
+        if custom_scope
+            .as_ref()
+            .map(|entities| !entities.is_empty())
+            .unwrap_or(true)
+        {
+            let custom_entities = self
+                .shared_resources
+                .custom_entity_parser
+                .extract_entities(
+                    &normalized_input.text,
+                    custom_scope.as_ref().map(|entities| entities.as_ref()),
+                    entities_alternatives,
+                )?;
+            entities.extend(custom_entities.into_iter().map(EntityMatch::from));
+        }
+        for entity in entities.iter_mut() {
+            entity.range = normalized_input.original_range(&entity.range);
+            entity.value = substring_with_char_range(input.to_string(), &entity.range);
+        }
+        entities.sort_by_key(|entity| {
+            (
+                entity.range.start,
+                Reverse(entity.range.end),
+                entity.entity.clone(),
+            )
+        });
+        // The builtin entity parser may return the same entity several times
+        entities.dedup_by(|a, b| a.range == b.range && a.entity == b.entity);
+        Ok(entities)
+    }
 }
 
 fn extract_custom_slot(
@@ -464,7 +1166,7 @@ fn extract_custom_slot(
             range: matched_entity.range,
             entity: entity_name.clone(),
             slot_name: slot_name.clone(),
-            confidence_score: None,
+            confidence_score: matched_entity.match_score,
         })
     } else if custom_entity.automatically_extensible {
         let range = 0..input.chars().count();
@@ -514,9 +1216,17 @@ fn extract_builtin_slot(
 mod tests {
     use std::iter::FromIterator;
 
+    use fs_extra::dir;
+    use maplit::hashmap;
     use snips_nlu_ontology::{NumberValue, StringValue};
+    use tempfile::tempdir;
 
     use crate::entity_parser::custom_entity_parser::CustomEntity;
+    use crate::models::{
+        DeterministicParserConfig, DeterministicParserModel, IntentSlotsSchema, SlotSchema,
+    };
+    use crate::resources::loading::load_engine_shared_resources;
+    use crate::slot_post_processing::{SlotPostProcessing, SlotPostProcessor};
     use crate::testutils::*;
 
     use super::*;
@@ -590,16 +1300,459 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_with_whitelist_and_blacklist() {
+    fn test_parse_with_slot_validation() {
         // Given
+        struct MaxNumberOfCups {}
+
+        impl SlotPostProcessor for MaxNumberOfCups {
+            fn process(&self, slot: Slot) -> SlotPostProcessing {
+                match slot.value {
+                    SlotValue::Number(NumberValue { value }) if value > 1.0 => {
+                        SlotPostProcessing::Reject("Too many cups".to_string())
+                    }
+                    _ => SlotPostProcessing::Accept(slot),
+                }
+            }
+        }
+
         let path = Path::new("data")
             .join("tests")
             .join("models")
             .join("nlu_engine_beverage");
-        let nlu_engine = SnipsNluEngine::from_path(path).unwrap();
+        let config = NluEngineConfig::default().slot_post_processors(
+            SlotPostProcessors::default()
+                .register_for_slot_name("number_of_cups", MaxNumberOfCups {}),
+        );
+        let nlu_engine = SnipsNluEngine::from_path_with_config(path, config).unwrap();
 
         // When
-        let whitelist_result = nlu_engine
+        let input = "Make me two cups of coffee please";
+        let result = nlu_engine
+            .parse_with_slot_validation(input, None, None)
+            .unwrap();
+        let unvalidated_result = nlu_engine.parse(input, None, None).unwrap();
+        let nested_result = nlu_engine
+            .parse_with_nested_slots(input, None, None)
+            .unwrap();
+
+        // Then
+        let expected_validation_errors = vec![SlotValidationError {
+            slot: Slot {
+                raw_value: "two".to_string(),
+                value: SlotValue::Number(NumberValue { value: 2.0 }),
+                alternatives: vec![],
+                range: 8..11,
+                entity: "snips/number".to_string(),
+                slot_name: "number_of_cups".to_string(),
+                confidence_score: None,
+            },
+            reason: "Too many cups".to_string(),
+        }];
+        assert_eq!(Some("MakeCoffee".to_string()), result.intent.intent_name);
+        assert_eq!(Vec::<Slot>::new(), result.slots);
+        assert_eq!(expected_validation_errors, result.slot_validation_errors);
+        // The rejected slot is kept by the parsing methods which cannot report it
+        let rejected_slots = vec![expected_validation_errors[0].slot.clone()];
+        assert_eq!(rejected_slots, unvalidated_result.slots);
+        assert_eq!(1, nested_result.slots.len());
+        assert_eq!(rejected_slots[0], nested_result.slots[0].slot);
+    }
+
+    #[test]
+    fn test_parse_with_normalization() {
+        // Given
+        let path = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage");
+        let config = NluEngineConfig::default().normalization_steps(vec![
+            NormalizationStep::UnicodeNfkc,
+            NormalizationStep::PunctuationStripping,
+            NormalizationStep::NumberWordsToDigits,
+        ]);
+        let nlu_engine = SnipsNluEngine::from_path_with_config(path, config).unwrap();
+        let input = "Make me Two cups of coffee, please!!";
+
+        // When
+        let result = nlu_engine.parse(input, None, None).unwrap();
+        let entities = nlu_engine
+            .extract_entities(input, vec!["snips/number"])
+            .unwrap();
+
+        // Then
+        let expected_slots = vec![Slot {
+            raw_value: "Two".to_string(),
+            value: SlotValue::Number(NumberValue { value: 2.0 }),
+            alternatives: vec![],
+            range: 8..11,
+            entity: "snips/number".to_string(),
+            slot_name: "number_of_cups".to_string(),
+            confidence_score: None,
+        }];
+        let expected_entities = vec![EntityMatch {
+            value: "Two".to_string(),
+            range: 8..11,
+            entity: "snips/number".to_string(),
+            resolved_value: SlotValue::Number(NumberValue { value: 2.0 }),
+            alternatives: vec![],
+            match_score: None,
+        }];
+        assert_eq!(input, result.input);
+        assert_eq!(Some("MakeCoffee".to_string()), result.intent.intent_name);
+        assert_eq!(expected_slots, result.slots);
+        assert_eq!(expected_entities, entities);
+    }
+
+    #[test]
+    fn test_parse_with_spelling_correction() {
+        // Given
+        let path = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage");
+        let config = NluEngineConfig::default().spelling_correction(
+            SpellingCorrectionConfig::default().additional_words(vec!["boiling".to_string()]),
+        );
+        let nlu_engine = SnipsNluEngine::from_path_with_config(path, config).unwrap();
+        let input = "Brew two cups of cofee";
+
+        // When
+        let result = nlu_engine
+            .parse_with_spelling_correction(input, None, None)
+            .unwrap();
+        let parse_result = nlu_engine.parse(input, None, None).unwrap();
+        let correct_input_result = nlu_engine
+            .parse_with_spelling_correction("Brew two cups of coffee", None, None)
+            .unwrap();
+
+        // Then
+        let expected_slots = vec![Slot {
+            raw_value: "two".to_string(),
+            value: SlotValue::Number(NumberValue { value: 2.0 }),
+            alternatives: vec![],
+            range: 5..8,
+            entity: "snips/number".to_string(),
+            slot_name: "number_of_cups".to_string(),
+            confidence_score: None,
+        }];
+        let expected_corrections = vec![SpellingCorrection {
+            raw_value: "cofee".to_string(),
+            corrected_value: "coffee".to_string(),
+            range: 17..22,
+        }];
+        assert_eq!(input, result.input);
+        assert_eq!(
+            Some("Brew two cups of coffee".to_string()),
+            result.corrected_input
+        );
+        assert_eq!(expected_corrections, result.corrections);
+        assert_eq!(Some("MakeCoffee".to_string()), result.intent.intent_name);
+        assert_eq!(1.0, result.intent.confidence_score);
+        assert_eq!(expected_slots, result.slots);
+        assert_eq!(result.intent, parse_result.intent);
+        assert_eq!(expected_slots, parse_result.slots);
+        assert_eq!(None, correct_input_result.corrected_input);
+        assert!(correct_input_result.corrections.is_empty());
+    }
+
+    #[test]
+    fn test_spelling_corrections_include_gazetteer_words() {
+        // Given
+        let path = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage");
+        let resources_path = path.join("resources").join("en");
+        let shared_resources = load_engine_shared_resources(&path).unwrap();
+        let spelling_corrector = SnipsNluEngine::load_spelling_corrector(
+            resources_path,
+            &[],
+            SpellingCorrectionConfig::default(),
+            shared_resources,
+        )
+        .unwrap();
+        let input = "the abilty of coffee";
+        let normalized_input = TextNormalizer::new(vec![], Language::EN).normalize(input);
+
+        // When
+        let proposals = spelling_corrector
+            .propose_corrections(input, &normalized_input)
+            .unwrap();
+
+        // Then
+        let expected_corrections = vec![SpellingCorrection {
+            raw_value: "abilty".to_string(),
+            corrected_value: "ability".to_string(),
+            range: 4..10,
+        }];
+        assert_eq!(1, proposals.len());
+        assert_eq!(expected_corrections, proposals[0].corrections);
+    }
+
+    #[test]
+    fn test_parse_with_intent_schema() {
+        // Given
+        let path = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage");
+        let engine_dir = tempdir().unwrap();
+        dir::copy(&path, engine_dir.path(), &dir::CopyOptions::new()).unwrap();
+        let engine_path = engine_dir.path().join("nlu_engine_beverage");
+        let intent_schema = r#"{
+            "intents": {
+                "MakeTea": {
+                    "slots": {
+                        "number_of_cups": {"required": true, "max_count": 1},
+                        "beverage_temperature": {"required": true, "allowed_values": ["cold"]}
+                    }
+                }
+            }
+        }"#;
+        fs::write(engine_path.join("intent_schema.json"), intent_schema).unwrap();
+        let nlu_engine = SnipsNluEngine::from_path(&engine_path).unwrap();
+
+        let invalid_schema = IntentSchema {
+            intents: hashmap! {
+                "MakeCoffee".to_string() => IntentSlotsSchema {
+                    slots: hashmap! { "beverage_temperature".to_string() => SlotSchema::default() }
+                }
+            },
+        };
+        let invalid_config = NluEngineConfig::default().intent_schema(invalid_schema);
+
+        // When
+        let result = nlu_engine
+            .parse_with_slot_validation("Make me two hot cups of tea", None, None)
+            .unwrap();
+        let invalid_schema_error =
+            SnipsNluEngine::from_path_with_config(&path, invalid_config).unwrap_err();
+
+        // Then
+        let expected_slots = vec![Slot {
+            raw_value: "two".to_string(),
+            value: SlotValue::Number(NumberValue { value: 2.0 }),
+            alternatives: vec![],
+            range: 8..11,
+            entity: "snips/number".to_string(),
+            slot_name: "number_of_cups".to_string(),
+            confidence_score: None,
+        }];
+        let expected_validation_errors = vec![SlotValidationError {
+            slot: Slot {
+                raw_value: "hot".to_string(),
+                value: SlotValue::Custom(StringValue {
+                    value: "hot".to_string(),
+                }),
+                alternatives: vec![],
+                range: 12..15,
+                entity: "Temperature".to_string(),
+                slot_name: "beverage_temperature".to_string(),
+                confidence_score: None,
+            },
+            reason: "Value 'hot' is not allowed for slot 'beverage_temperature'".to_string(),
+        }];
+        assert_eq!(Some("MakeTea".to_string()), result.intent.intent_name);
+        assert_eq!(expected_slots, result.slots);
+        assert_eq!(expected_validation_errors, result.slot_validation_errors);
+        assert_eq!(
+            vec!["beverage_temperature".to_string()],
+            result.missing_slots
+        );
+        assert_eq!(Vec::<String>::new(), result.duplicated_slots);
+        assert_eq!(
+            SnipsNluErrorKind::UnknownSlot,
+            error_kind(&invalid_schema_error)
+        );
+    }
+
+    #[test]
+    fn test_entity_parsers_cache_statistics() {
+        // Given
+        let path = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage");
+        let nlu_engine = SnipsNluEngine::from_path(path).unwrap();
+
+        // When
+        for _ in 0..2 {
+            nlu_engine
+                .parse("Make me two cups of coffee please", None, None)
+                .unwrap();
+        }
+        let statistics = nlu_engine.entity_parsers_cache_statistics();
+
+        // Then
+        let builtin_statistics = statistics.builtin_entity_parser.unwrap();
+        let custom_statistics = statistics.custom_entity_parser.unwrap();
+        assert_eq!(1000, builtin_statistics.capacity);
+        assert!(builtin_statistics.hits > 0);
+        assert!(builtin_statistics.misses > 0);
+        assert!(custom_statistics.hits > 0);
+        assert!(custom_statistics.misses > 0);
+    }
+
+    #[test]
+    fn test_parse_with_disabled_entity_parsers_cache() {
+        // Given
+        let path = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage");
+        let config = NluEngineConfig::default().entity_parser_cache_capacity(0);
+        let nlu_engine = SnipsNluEngine::from_path_with_config(path, config).unwrap();
+
+        // When
+        let result = nlu_engine
+            .parse("Make me two cups of coffee please", None, None)
+            .unwrap();
+        let statistics = nlu_engine.entity_parsers_cache_statistics();
+
+        // Then
+        assert_eq!(Some("MakeCoffee".to_string()), result.intent.intent_name);
+        let builtin_statistics = statistics.builtin_entity_parser.unwrap();
+        assert_eq!(0, builtin_statistics.capacity);
+        assert_eq!(0, builtin_statistics.size);
+        assert_eq!(0, builtin_statistics.hits);
+    }
+
+    #[test]
+    fn test_error_kinds() {
+        // Given
+        let path = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage");
+        let nlu_engine = SnipsNluEngine::from_path(&path).unwrap();
+
+        let engine_dir = tempdir().unwrap();
+        dir::copy(&path, engine_dir.path(), &dir::CopyOptions::new()).unwrap();
+        let outdated_engine_path = engine_dir.path().join("nlu_engine_beverage");
+        let engine_model_path = outdated_engine_path.join("nlu_engine.json");
+        let engine_model = fs::read_to_string(&engine_model_path)
+            .unwrap()
+            .replace(crate::MODEL_VERSION, "0.0.1");
+        fs::write(&engine_model_path, engine_model).unwrap();
+
+        // When
+        let unknown_intent_error = nlu_engine
+            .parse("Make me a coffee", vec!["MakeChocolate"], None)
+            .unwrap_err();
+        let unknown_slot_error = nlu_engine
+            .extract_slot("Make me a coffee".to_string(), "MakeCoffee", "temperature")
+            .unwrap_err();
+        let unknown_entity_error = nlu_engine
+            .extract_entities("Make me a coffee", vec!["snips/unknown"])
+            .unwrap_err();
+        let wrong_version_error = SnipsNluEngine::from_path(&outdated_engine_path)
+            .err()
+            .unwrap();
+
+        // Then
+        assert_eq!(
+            SnipsNluErrorKind::UnknownIntent,
+            error_kind(&unknown_intent_error)
+        );
+        assert_eq!(
+            SnipsNluErrorKind::UnknownSlot,
+            error_kind(&unknown_slot_error)
+        );
+        assert_eq!(
+            SnipsNluErrorKind::UnknownEntity,
+            error_kind(&unknown_entity_error)
+        );
+        assert_eq!(
+            SnipsNluErrorKind::WrongModelVersion,
+            error_kind(&wrong_version_error)
+        );
+    }
+
+    #[test]
+    fn test_parse_with_max_input_length() {
+        // Given
+        let path = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage");
+        let config = NluEngineConfig::default().max_input_length(40);
+        let nlu_engine = SnipsNluEngine::from_path_with_config(path, config).unwrap();
+        let long_input = format!("Make me two cups of coffee{}", " please".repeat(10));
+
+        // When
+        let result = nlu_engine.parse("Make me two cups of coffee please", None, None);
+        let long_input_result = nlu_engine.parse(&long_input, None, None);
+        let long_input_intents = nlu_engine.get_intents(&long_input);
+        let long_input_slot =
+            nlu_engine.extract_slot(long_input.clone(), "MakeCoffee", "number_of_cups");
+
+        // Then
+        assert_eq!(
+            Some("MakeCoffee".to_string()),
+            result.unwrap().intent.intent_name
+        );
+        let error = long_input_result.unwrap_err();
+        match error.downcast_ref::<SnipsNluError>() {
+            Some(SnipsNluError::InputTooLong { length, max_length }) => {
+                assert_eq!(96, *length);
+                assert_eq!(40, *max_length);
+            }
+            _ => panic!("Unexpected error: {}", error),
+        }
+        assert!(long_input_intents.is_err());
+        assert!(long_input_slot.is_err());
+    }
+
+    #[test]
+    fn test_parse_with_calibrated_parsers() {
+        // Given
+        let path = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage");
+        let engine_dir = tempdir().unwrap();
+        dir::copy(&path, engine_dir.path(), &dir::CopyOptions::new()).unwrap();
+        let calibrated_path = engine_dir.path().join("nlu_engine_beverage");
+        let calibration = r#"{"type": "isotonic", "thresholds": [0.0, 1.0], "values": [0.0, 0.8]}"#;
+        for parser_name in &[
+            "lookup_intent_parser",
+            "deterministic_intent_parser",
+            "probabilistic_intent_parser",
+        ] {
+            fs::write(
+                calibrated_path.join(parser_name).join("calibration.json"),
+                calibration,
+            )
+            .unwrap();
+        }
+        let nlu_engine = SnipsNluEngine::from_path(path).unwrap();
+        let calibrated_nlu_engine = SnipsNluEngine::from_path(calibrated_path).unwrap();
+
+        // When
+        let input = "Make me two cups of coffee please";
+        let result = nlu_engine.parse(input, None, None).unwrap();
+        let calibrated_result = calibrated_nlu_engine.parse(input, None, None).unwrap();
+
+        // Then
+        assert_eq!(
+            result.intent.intent_name,
+            calibrated_result.intent.intent_name
+        );
+        let expected_confidence = 0.8 * result.intent.confidence_score;
+        assert!((expected_confidence - calibrated_result.intent.confidence_score).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_parse_with_whitelist_and_blacklist() {
+        // Given
+        let path = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage");
+        let nlu_engine = SnipsNluEngine::from_path(path).unwrap();
+
+        // When
+        let whitelist_result = nlu_engine
             .parse("Make me two cups of coffee please", vec!["MakeTea"], None)
             .unwrap();
 
@@ -806,6 +1959,112 @@ mod tests {
         assert_eq!(expected_result, result);
     }
 
+    #[test]
+    fn test_parse_with_nested_slots() {
+        // Given
+        let input = "give me the address of the office of mom";
+        let parser_model = DeterministicParserModel {
+            language_code: "en".to_string(),
+            patterns: hashmap! {
+                "GetAddress".to_string() => vec![
+                    r"^\s*give\s*me\s*the\s*address\s*of\s*(?P<group0>the\s*office\s*of\s*(?P<group1>%CONTACT%))\s*$".to_string()
+                ]
+            },
+            group_names_to_slot_names: hashmap! {
+                "group0".to_string() => "location".to_string(),
+                "group1".to_string() => "contact".to_string(),
+            },
+            group_names_to_parent_group_names: hashmap! {
+                "group1".to_string() => "group0".to_string()
+            },
+            slot_names_to_entities: hashmap! {
+                "GetAddress".to_string() => hashmap! {
+                    "location".to_string() => "location".to_string(),
+                    "contact".to_string() => "contact".to_string(),
+                }
+            },
+            config: DeterministicParserConfig {
+                ignore_stop_words: false,
+            },
+            stop_words_whitelist: HashMap::new(),
+        };
+        let mocked_custom_entity_parser = MockedCustomEntityParser::from_iter(vec![(
+            input.to_string(),
+            vec![CustomEntity {
+                value: "mom".to_string(),
+                resolved_value: "Mom".to_string(),
+                alternative_resolved_values: vec![],
+                range: 37..40,
+                entity_identifier: "contact".to_string(),
+                match_score: None,
+            }],
+        )]);
+        let shared_resources = Arc::new(
+            SharedResourcesBuilder::default()
+                .custom_entity_parser(mocked_custom_entity_parser)
+                .build(),
+        );
+        let intent_parser =
+            DeterministicIntentParser::new(parser_model, shared_resources.clone()).unwrap();
+        let nlu_engine = SnipsNluEngine {
+            dataset_metadata: DatasetMetadata {
+                language_code: "en".to_string(),
+                entities: hashmap! {
+                    "location".to_string() => Entity { automatically_extensible: true },
+                    "contact".to_string() => Entity { automatically_extensible: false },
+                },
+                slot_name_mappings: hashmap! {
+                    "GetAddress".to_string() => hashmap! {
+                        "location".to_string() => "location".to_string(),
+                        "contact".to_string() => "contact".to_string(),
+                    }
+                },
+            },
+            intent_parsers: vec![Box::new(intent_parser)],
+            shared_resources,
+            max_input_length: None,
+            slot_post_processors: SlotPostProcessors::default(),
+            intent_schema: None,
+            normalizer: TextNormalizer::new(vec![], Language::EN),
+            spelling_corrector: None,
+        };
+
+        // When
+        let result = nlu_engine
+            .parse_with_nested_slots(input, None, None)
+            .unwrap();
+        let flat_result = nlu_engine.parse(input, None, None).unwrap();
+
+        // Then
+        let location_slot = Slot {
+            raw_value: "the office of mom".to_string(),
+            value: SlotValue::Custom("the office of mom".to_string().into()),
+            alternatives: vec![],
+            range: 23..40,
+            entity: "location".to_string(),
+            slot_name: "location".to_string(),
+            confidence_score: None,
+        };
+        let expected_slots = vec![NestedSlot {
+            slot: location_slot.clone(),
+            sub_slots: vec![NestedSlot {
+                slot: Slot {
+                    raw_value: "mom".to_string(),
+                    value: SlotValue::Custom("Mom".to_string().into()),
+                    alternatives: vec![],
+                    range: 37..40,
+                    entity: "contact".to_string(),
+                    slot_name: "contact".to_string(),
+                    confidence_score: None,
+                },
+                sub_slots: vec![],
+            }],
+        }];
+        assert_eq!(Some("GetAddress".to_string()), result.intent.intent_name);
+        assert_eq!(expected_slots, result.slots);
+        assert_eq!(vec![location_slot], flat_result.slots);
+    }
+
     #[test]
     fn test_get_intents() {
         // Given
@@ -904,6 +2163,47 @@ mod tests {
         assert_eq!(expected_slots, slots);
     }
 
+    #[test]
+    fn test_extract_entities() {
+        // Given
+        let path = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage");
+        let nlu_engine = SnipsNluEngine::from_path(path).unwrap();
+        let input = "Make me two hot cups of tea";
+
+        // When
+        let entities = nlu_engine
+            .extract_entities(input, vec!["Temperature", "snips/number"])
+            .unwrap();
+        let custom_entities = nlu_engine
+            .extract_entities(input, vec!["Temperature"])
+            .unwrap();
+        let unknown_entity_result = nlu_engine.extract_entities(input, vec!["snips/unknown"]);
+
+        // Then
+        let number_entity = EntityMatch {
+            value: "two".to_string(),
+            range: 8..11,
+            entity: "snips/number".to_string(),
+            resolved_value: SlotValue::Number(NumberValue { value: 2.0 }),
+            alternatives: vec![],
+            match_score: None,
+        };
+        let temperature_entity = EntityMatch {
+            value: "hot".to_string(),
+            range: 12..15,
+            entity: "Temperature".to_string(),
+            resolved_value: SlotValue::Custom("hot".to_string().into()),
+            alternatives: vec![],
+            match_score: None,
+        };
+        assert_eq!(vec![number_entity, temperature_entity.clone()], entities);
+        assert_eq!(vec![temperature_entity], custom_entities);
+        assert!(unknown_entity_result.is_err());
+    }
+
     #[test]
     fn test_extract_custom_slot_when_tagged() {
         // Given
@@ -923,6 +2223,7 @@ mod tests {
                     alternative_resolved_values: vec![],
                     range: 6..9,
                     entity_identifier: entity_name.to_string(),
+                    match_score: None,
                 },
                 CustomEntity {
                     value: "b c d".to_string(),
@@ -930,6 +2231,7 @@ mod tests {
                     alternative_resolved_values: vec![],
                     range: 8..13,
                     entity_identifier: entity_name.to_string(),
+                    match_score: None,
                 },
             ],
         )]));
@@ -1040,6 +2342,7 @@ mod tests {
                 alternative_resolved_values: vec!["Invader Attack".to_string()],
                 range: 18..25,
                 entity_identifier: entity_name.to_string(),
+                match_score: None,
             }],
         )]));
 
@@ -0,0 +1,588 @@
+use std::iter::once;
+use std::ops::Range;
+
+use snips_nlu_ontology::Language;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Normalization applied to the inputs before they are processed by the parsers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalizationStep {
+    /// Unicode compatibility composition, which for instance maps full width characters and
+    /// ligatures to their usual form
+    UnicodeNfkc,
+    /// Removes the diacritics, e.g. "café" becomes "cafe"
+    AccentFolding,
+    /// Expands the contractions, e.g. "don't" becomes "do not", only supported in English
+    ContractionExpansion,
+    /// Removes the punctuation, except between two alphanumeric characters as in "2.5" or "10:30"
+    PunctuationStripping,
+    /// Converts the numbers written in words to digits, e.g. "twenty one" becomes "21", only
+    /// supported in English
+    NumberWordsToDigits,
+}
+
+/// Normalized text along with the mapping of its characters to the original text
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormalizedText {
+    pub text: String,
+    /// Range of characters of the original text from which each character of the text comes
+    char_ranges: Vec<Range<usize>>,
+    original_length: usize,
+}
+
+impl NormalizedText {
+    /// Maps a range of characters of the normalized text to the range of characters of the
+    /// original text it comes from
+    pub fn original_range(&self, range: &Range<usize>) -> Range<usize> {
+        let start = range.start.min(self.char_ranges.len());
+        let end = range.end.min(self.char_ranges.len());
+        if start >= end {
+            let position = self
+                .char_ranges
+                .get(start)
+                .map(|char_range| char_range.start)
+                .unwrap_or(self.original_length);
+            return position..position;
+        }
+        self.char_ranges[start..end]
+            .iter()
+            .fold(usize::max_value()..0, |merged, char_range| {
+                merged.start.min(char_range.start)..merged.end.max(char_range.end)
+            })
+    }
+
+    /// Replaces the provided ranges of characters, which must be sorted and disjoint, each
+    /// character of a replacement being mapped to the original range of the replaced characters
+    pub(crate) fn replace_ranges(&self, replacements: &[(Range<usize>, String)]) -> Self {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut text = String::with_capacity(self.text.len());
+        let mut char_ranges = Vec::with_capacity(self.char_ranges.len());
+        let mut position = 0;
+        for (range, replacement) in replacements.iter() {
+            text.extend(&chars[position..range.start]);
+            char_ranges.extend_from_slice(&self.char_ranges[position..range.start]);
+            let original_range = self.original_range(range);
+            text.push_str(replacement);
+            char_ranges.extend(replacement.chars().map(|_| original_range.clone()));
+            position = range.end;
+        }
+        text.extend(&chars[position..]);
+        char_ranges.extend_from_slice(&self.char_ranges[position..]);
+        Self {
+            text,
+            char_ranges,
+            original_length: self.original_length,
+        }
+    }
+}
+
+/// Runs the normalization steps, in order, on the inputs of the engine
+#[derive(Debug, Clone)]
+pub struct TextNormalizer {
+    steps: Vec<NormalizationStep>,
+    language: Language,
+}
+
+type NormalizedChars = Vec<(char, Range<usize>)>;
+
+impl TextNormalizer {
+    pub fn new(steps: Vec<NormalizationStep>, language: Language) -> Self {
+        Self { steps, language }
+    }
+
+    pub fn steps(&self) -> &[NormalizationStep] {
+        &self.steps
+    }
+
+    pub fn normalize(&self, text: &str) -> NormalizedText {
+        let mut chars: NormalizedChars = text
+            .chars()
+            .enumerate()
+            .map(|(index, c)| (c, index..index + 1))
+            .collect();
+        let original_length = chars.len();
+        for step in self.steps.iter() {
+            chars = match step {
+                NormalizationStep::UnicodeNfkc => apply_nfkc(chars),
+                NormalizationStep::AccentFolding => fold_accents(chars),
+                NormalizationStep::ContractionExpansion => match self.language {
+                    Language::EN => expand_english_contractions(chars),
+                    _ => chars,
+                },
+                NormalizationStep::PunctuationStripping => strip_punctuation(chars),
+                NormalizationStep::NumberWordsToDigits => match self.language {
+                    Language::EN => convert_english_number_words(chars),
+                    _ => chars,
+                },
+            };
+        }
+        NormalizedText {
+            text: chars.iter().map(|(c, _)| c).collect(),
+            char_ranges: chars
+                .into_iter()
+                .map(|(_, char_range)| char_range)
+                .collect(),
+            original_length,
+        }
+    }
+}
+
+fn merge_ranges(first: &Range<usize>, last: &Range<usize>) -> Range<usize> {
+    first.start.min(last.start)..first.end.max(last.end)
+}
+
+/// Replaces the characters of `chars[span]` with `replacement`, whose characters are all mapped to
+/// the original range of the span
+fn push_replacement(
+    output: &mut NormalizedChars,
+    chars: &[(char, Range<usize>)],
+    span: Range<usize>,
+    replacement: &str,
+) {
+    let original_range = merge_ranges(&chars[span.start].1, &chars[span.end - 1].1);
+    output.extend(replacement.chars().map(|c| (c, original_range.clone())));
+}
+
+fn apply_nfkc(chars: NormalizedChars) -> NormalizedChars {
+    // Combining marks are composed with the character preceding them
+    let mut output = Vec::with_capacity(chars.len());
+    let mut cluster_start = 0;
+    for index in 1..=chars.len() {
+        if index < chars.len() && is_combining_mark(chars[index].0) {
+            continue;
+        }
+        let cluster: String = chars[cluster_start..index].iter().map(|(c, _)| c).collect();
+        push_replacement(
+            &mut output,
+            &chars,
+            cluster_start..index,
+            &cluster.nfkc().collect::<String>(),
+        );
+        cluster_start = index;
+    }
+    output
+}
+
+fn fold_accents(chars: NormalizedChars) -> NormalizedChars {
+    chars
+        .into_iter()
+        .flat_map(|(c, char_range)| {
+            once(c)
+                .nfd()
+                .filter(|decomposed| !is_combining_mark(*decomposed))
+                .map(move |decomposed| (decomposed, char_range.clone()))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '’'
+}
+
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation() || "¡¿«»‘’“”„…–—·".contains(c)
+}
+
+/// Returns the spans of the consecutive characters which satisfy the predicate
+fn find_spans<F>(chars: &[(char, Range<usize>)], predicate: F) -> Vec<Range<usize>>
+where
+    F: Fn(char) -> bool,
+{
+    let mut spans = vec![];
+    let mut span_start = None;
+    for (index, (c, _)) in chars.iter().enumerate() {
+        match (predicate(*c), span_start) {
+            (true, None) => span_start = Some(index),
+            (false, Some(start)) => {
+                spans.push(start..index);
+                span_start = None;
+            }
+            _ => (),
+        }
+    }
+    if let Some(start) = span_start {
+        spans.push(start..chars.len());
+    }
+    spans
+}
+
+const ENGLISH_CONTRACTIONS: &[(&str, &str)] = &[
+    ("won't", "will not"),
+    ("can't", "can not"),
+    ("shan't", "shall not"),
+    ("let's", "let us"),
+];
+
+const ENGLISH_CONTRACTION_SUFFIXES: &[(&str, &str)] = &[
+    ("n't", " not"),
+    ("'re", " are"),
+    ("'ll", " will"),
+    ("'ve", " have"),
+    ("'m", " am"),
+    ("'d", " would"),
+];
+
+fn expand_english_contractions(chars: NormalizedChars) -> NormalizedChars {
+    let words = find_spans(&chars, |c| c.is_alphanumeric() || is_apostrophe(c));
+    let mut output = Vec::with_capacity(chars.len());
+    let mut next_index = 0;
+    for word in words {
+        let normalized_word: String = chars[word.clone()]
+            .iter()
+            .map(|(c, _)| if is_apostrophe(*c) { '\'' } else { *c })
+            .flat_map(char::to_lowercase)
+            .collect();
+        let full_expansion = ENGLISH_CONTRACTIONS
+            .iter()
+            .find(|(contraction, _)| *contraction == normalized_word);
+        let suffix_expansion = ENGLISH_CONTRACTION_SUFFIXES.iter().find(|(suffix, _)| {
+            normalized_word.len() > suffix.len() && normalized_word.ends_with(suffix)
+        });
+        output.extend_from_slice(&chars[next_index..word.start]);
+        if let Some((_, expansion)) = full_expansion {
+            push_replacement(&mut output, &chars, word.clone(), expansion);
+        } else if let Some((suffix, expansion)) = suffix_expansion {
+            let suffix_start = word.end - suffix.chars().count();
+            output.extend_from_slice(&chars[word.start..suffix_start]);
+            push_replacement(&mut output, &chars, suffix_start..word.end, expansion);
+        } else {
+            output.extend_from_slice(&chars[word.clone()]);
+        }
+        next_index = word.end;
+    }
+    output.extend_from_slice(&chars[next_index..]);
+    output
+}
+
+fn strip_punctuation(chars: NormalizedChars) -> NormalizedChars {
+    let mut output: NormalizedChars = Vec::with_capacity(chars.len());
+    for (index, (c, char_range)) in chars.iter().enumerate() {
+        if !is_punctuation(*c) {
+            output.push((*c, char_range.clone()));
+            continue;
+        }
+        let previous_char = index.checked_sub(1).map(|previous| chars[previous].0);
+        let next_char = chars.get(index + 1).map(|(next, _)| *next);
+        let is_within_word = previous_char.map(char::is_alphanumeric).unwrap_or(false)
+            && next_char.map(char::is_alphanumeric).unwrap_or(false);
+        if is_within_word {
+            output.push((*c, char_range.clone()));
+            continue;
+        }
+        let is_separated = output
+            .last()
+            .map(|(previous, _)| previous.is_whitespace())
+            .unwrap_or(true)
+            || next_char
+                .map(|next| next.is_whitespace() || is_punctuation(next))
+                .unwrap_or(true);
+        if !is_separated {
+            output.push((' ', char_range.clone()));
+        }
+    }
+    output
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NumberWordKind {
+    Zero,
+    Unit,
+    Teen,
+    Tens,
+    Hundred,
+    Scale,
+    And,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct NumberWord {
+    kind: NumberWordKind,
+    value: u64,
+}
+
+fn parse_english_number_word(word: &str) -> Option<NumberWord> {
+    const UNITS: &[&str] = &[
+        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+    ];
+    const TEENS: &[&str] = &[
+        "ten",
+        "eleven",
+        "twelve",
+        "thirteen",
+        "fourteen",
+        "fifteen",
+        "sixteen",
+        "seventeen",
+        "eighteen",
+        "nineteen",
+    ];
+    const TENS: &[&str] = &[
+        "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+    ];
+    let number_word = |kind, value| Some(NumberWord { kind, value });
+    if let Some(position) = UNITS.iter().position(|unit| *unit == word) {
+        return number_word(NumberWordKind::Unit, position as u64 + 1);
+    }
+    if let Some(position) = TEENS.iter().position(|teen| *teen == word) {
+        return number_word(NumberWordKind::Teen, position as u64 + 10);
+    }
+    if let Some(position) = TENS.iter().position(|tens| *tens == word) {
+        return number_word(NumberWordKind::Tens, 10 * (position as u64 + 2));
+    }
+    match word {
+        "zero" => number_word(NumberWordKind::Zero, 0),
+        "hundred" => number_word(NumberWordKind::Hundred, 100),
+        "thousand" => number_word(NumberWordKind::Scale, 1_000),
+        "million" => number_word(NumberWordKind::Scale, 1_000_000),
+        "billion" => number_word(NumberWordKind::Scale, 1_000_000_000),
+        "and" => number_word(NumberWordKind::And, 0),
+        _ => None,
+    }
+}
+
+/// Incrementally parses a sequence of number words
+#[derive(Debug, Default)]
+struct NumberParser {
+    kinds: Vec<NumberWordKind>,
+    total: u64,
+    current: u64,
+    last_scale: Option<u64>,
+}
+
+impl NumberParser {
+    fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+    }
+
+    /// Adds the word to the number, unless it cannot follow the previous words
+    fn push(&mut self, word: NumberWord) -> bool {
+        let previous = self.kinds.last().cloned();
+        let follows_separator = previous
+            .map(|previous| {
+                previous == NumberWordKind::Hundred
+                    || previous == NumberWordKind::Scale
+                    || previous == NumberWordKind::And
+            })
+            .unwrap_or(true);
+        let is_valid = match word.kind {
+            NumberWordKind::Zero => previous.is_none(),
+            NumberWordKind::Unit => follows_separator || previous == Some(NumberWordKind::Tens),
+            NumberWordKind::Teen | NumberWordKind::Tens => follows_separator,
+            NumberWordKind::Hundred => {
+                (previous == Some(NumberWordKind::Unit) || previous == Some(NumberWordKind::Teen))
+                    && self.current < 100
+            }
+            NumberWordKind::Scale => {
+                previous
+                    .map(|previous| {
+                        previous != NumberWordKind::And && previous != NumberWordKind::Zero
+                    })
+                    .unwrap_or(false)
+                    && self
+                        .last_scale
+                        .map(|last| word.value < last)
+                        .unwrap_or(true)
+            }
+            NumberWordKind::And => {
+                previous == Some(NumberWordKind::Hundred) || previous == Some(NumberWordKind::Scale)
+            }
+        };
+        if !is_valid {
+            return false;
+        }
+        match word.kind {
+            NumberWordKind::Zero | NumberWordKind::And => (),
+            NumberWordKind::Unit | NumberWordKind::Teen | NumberWordKind::Tens => {
+                self.current += word.value
+            }
+            NumberWordKind::Hundred => self.current *= word.value,
+            NumberWordKind::Scale => {
+                self.total += self.current * word.value;
+                self.current = 0;
+                self.last_scale = Some(word.value);
+            }
+        }
+        self.kinds.push(word.kind);
+        true
+    }
+
+    fn ends_with_and(&self) -> bool {
+        self.kinds.last() == Some(&NumberWordKind::And)
+    }
+
+    fn value(&self) -> u64 {
+        self.total + self.current
+    }
+}
+
+fn convert_english_number_words(chars: NormalizedChars) -> NormalizedChars {
+    let words: Vec<(Range<usize>, Option<NumberWord>)> = find_spans(&chars, char::is_alphabetic)
+        .into_iter()
+        .map(|span| {
+            let word: String = chars[span.clone()]
+                .iter()
+                .flat_map(|(c, _)| c.to_lowercase())
+                .collect();
+            (span, parse_english_number_word(&word))
+        })
+        .collect();
+
+    // Spans of the words of each number, along with the value of the number
+    let mut numbers: Vec<(Range<usize>, u64)> = vec![];
+    let mut parser = NumberParser::default();
+    let mut number_words: Vec<Range<usize>> = vec![];
+    let mut close_number = |parser: &mut NumberParser, number_words: &mut Vec<Range<usize>>| {
+        if !parser.is_empty() {
+            // A trailing "and" is not part of the number
+            if parser.ends_with_and() {
+                number_words.pop();
+            }
+            let first_word = number_words.first().unwrap();
+            let last_word = number_words.last().unwrap();
+            numbers.push((first_word.start..last_word.end, parser.value()));
+        }
+        *parser = NumberParser::default();
+        number_words.clear();
+    };
+    for (index, (span, number_word)) in words.iter().enumerate() {
+        // The words of a number can only be separated by whitespaces and hyphens
+        let is_adjacent = index > 0
+            && chars[words[index - 1].0.end..span.start]
+                .iter()
+                .all(|(c, _)| c.is_whitespace() || *c == '-');
+        if !is_adjacent {
+            close_number(&mut parser, &mut number_words);
+        }
+        let number_word = match number_word {
+            Some(number_word) => *number_word,
+            None => {
+                close_number(&mut parser, &mut number_words);
+                continue;
+            }
+        };
+        if !parser.push(number_word) {
+            close_number(&mut parser, &mut number_words);
+            if number_word.kind == NumberWordKind::And || !parser.push(number_word) {
+                continue;
+            }
+        }
+        number_words.push(span.clone());
+    }
+    close_number(&mut parser, &mut number_words);
+
+    let mut output = Vec::with_capacity(chars.len());
+    let mut next_index = 0;
+    for (span, value) in numbers {
+        output.extend_from_slice(&chars[next_index..span.start]);
+        push_replacement(&mut output, &chars, span.clone(), &value.to_string());
+        next_index = span.end;
+    }
+    output.extend_from_slice(&chars[next_index..]);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(steps: Vec<NormalizationStep>, text: &str) -> NormalizedText {
+        TextNormalizer::new(steps, Language::EN).normalize(text)
+    }
+
+    #[test]
+    fn test_unicode_nfkc_and_accent_folding() {
+        // Given
+        let steps = vec![
+            NormalizationStep::UnicodeNfkc,
+            NormalizationStep::AccentFolding,
+        ];
+        let text = "ｃａｆé au lait ﬁne";
+
+        // When
+        let normalized_text = normalize(steps, text);
+
+        // Then
+        assert_eq!("cafe au lait fine", normalized_text.text);
+        assert_eq!(0..4, normalized_text.original_range(&(0..4)));
+        assert_eq!(13..16, normalized_text.original_range(&(13..17)));
+    }
+
+    #[test]
+    fn test_contraction_expansion() {
+        // Given
+        let steps = vec![NormalizationStep::ContractionExpansion];
+        let text = "I won’t go, I'm tired and they don't care";
+
+        // When
+        let normalized_text = normalize(steps, text);
+
+        // Then
+        assert_eq!(
+            "I will not go, I am tired and they do not care",
+            normalized_text.text
+        );
+        assert_eq!(2..7, normalized_text.original_range(&(2..10)));
+        assert_eq!(12..15, normalized_text.original_range(&(15..19)));
+        assert_eq!(31..36, normalized_text.original_range(&(35..41)));
+    }
+
+    #[test]
+    fn test_punctuation_stripping() {
+        // Given
+        let steps = vec![NormalizationStep::PunctuationStripping];
+        let text = "Hello, set it to 2.5 at 10:30!!now \"please\"";
+
+        // When
+        let normalized_text = normalize(steps, text);
+
+        // Then
+        assert_eq!(
+            "Hello set it to 2.5 at 10:30 now please",
+            normalized_text.text
+        );
+        assert_eq!(36..42, normalized_text.original_range(&(33..39)));
+    }
+
+    #[test]
+    fn test_number_words_to_digits() {
+        // Given
+        let steps = vec![NormalizationStep::NumberWordsToDigits];
+        let text = "Make me twenty-one coffees and two hundred and five teas, one two";
+
+        // When
+        let normalized_text = normalize(steps, text);
+
+        // Then
+        assert_eq!("Make me 21 coffees and 205 teas, 1 2", normalized_text.text);
+        assert_eq!(8..18, normalized_text.original_range(&(8..10)));
+        assert_eq!(31..51, normalized_text.original_range(&(23..26)));
+        assert_eq!(58..61, normalized_text.original_range(&(33..34)));
+    }
+
+    #[test]
+    fn test_number_words_scales() {
+        // Given
+        let steps = vec![NormalizationStep::NumberWordsToDigits];
+        let text = "three thousand two hundred and twelve and zero";
+
+        // When
+        let normalized_text = normalize(steps, text);
+
+        // Then
+        assert_eq!("3212 and 0", normalized_text.text);
+    }
+
+    #[test]
+    fn test_empty_range_mapping() {
+        // Given
+        let normalized_text = normalize(vec![NormalizationStep::NumberWordsToDigits], "one");
+
+        // When
+        let end_range = normalized_text.original_range(&(1..1));
+
+        // Then
+        assert_eq!(3..3, end_range);
+    }
+}
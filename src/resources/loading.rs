@@ -14,9 +14,11 @@ use crate::entity_parser::{CachingBuiltinEntityParser, CachingCustomEntityParser
 use crate::errors::*;
 use crate::models::nlu_engine::NluEngineModel;
 use crate::resources::gazetteer::{Gazetteer, HashSetGazetteer};
-use crate::resources::stemmer::{HashMapStemmer, Stemmer};
+use crate::resources::stemmer::{read_inflections, HashMapStemmer, Stemmer};
 use crate::resources::word_clusterer::{HashMapWordClusterer, WordClusterer};
 use crate::resources::SharedResources;
+use crate::slot_filler::FeatureRegistry;
+use crate::spelling_correction::Vocabulary;
 
 #[derive(Debug, Deserialize, Clone)]
 struct ResourcesMetadata {
@@ -27,28 +29,64 @@ struct ResourcesMetadata {
     stop_words: Option<String>,
 }
 
+/// Default number of entries cached by each of the builtin and custom entity parsers
+pub const DEFAULT_ENTITY_PARSER_CACHE_CAPACITY: usize = 1000;
+
+/// Suffix of the names of the gazetteers which contain stems instead of words
+const STEMMED_GAZETTEER_SUFFIX: &str = "_stemmed";
+
 pub fn load_shared_resources<P: AsRef<Path>, Q: AsRef<Path>, R: AsRef<Path>>(
     resources_dir: P,
     builtin_entity_parser_path: Q,
     custom_entity_parser_path: R,
 ) -> Result<Arc<SharedResources>> {
-    let metadata_file_path = resources_dir.as_ref().join("metadata.json");
-    let metadata_file = File::open(&metadata_file_path)?;
-    let metadata: ResourcesMetadata =
-        serde_json::from_reader(metadata_file).with_context(|_| {
-            format!(
-                "Cannot deserialize resources metadata file '{:?}'",
-                metadata_file_path
-            )
-        })?;
+    load_shared_resources_with_cache_capacity(
+        resources_dir,
+        builtin_entity_parser_path,
+        custom_entity_parser_path,
+        DEFAULT_ENTITY_PARSER_CACHE_CAPACITY,
+    )
+}
+
+/// Loads the shared resources with entity parsers caching at most `cache_capacity` results each,
+/// a capacity of 0 disables the caches
+pub fn load_shared_resources_with_cache_capacity<P: AsRef<Path>, Q: AsRef<Path>, R: AsRef<Path>>(
+    resources_dir: P,
+    builtin_entity_parser_path: Q,
+    custom_entity_parser_path: R,
+    cache_capacity: usize,
+) -> Result<Arc<SharedResources>> {
+    load_shared_resources_with_feature_registry(
+        resources_dir,
+        builtin_entity_parser_path,
+        custom_entity_parser_path,
+        cache_capacity,
+        FeatureRegistry::default(),
+    )
+}
+
+/// Loads the shared resources along with the CRF slot filler features provided by the
+/// application
+pub(crate) fn load_shared_resources_with_feature_registry<
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+    R: AsRef<Path>,
+>(
+    resources_dir: P,
+    builtin_entity_parser_path: Q,
+    custom_entity_parser_path: R,
+    cache_capacity: usize,
+    feature_registry: FeatureRegistry,
+) -> Result<Arc<SharedResources>> {
+    let metadata = load_metadata(&resources_dir)?;
     let stemmer = load_stemmer(&resources_dir, &metadata)?;
     let gazetteers = load_gazetteers(&resources_dir, &metadata)?;
     let word_clusterers = load_word_clusterers(&resources_dir, &metadata)?;
     let stop_words = load_stop_words(&resources_dir, &metadata)?;
     let builtin_entity_parser =
-        CachingBuiltinEntityParser::from_path(builtin_entity_parser_path, 1000)?;
+        CachingBuiltinEntityParser::from_path(builtin_entity_parser_path, cache_capacity)?;
     let custom_entity_parser =
-        CachingCustomEntityParser::from_path(custom_entity_parser_path, 1000)?;
+        CachingCustomEntityParser::from_path(custom_entity_parser_path, cache_capacity)?;
 
     Ok(Arc::new(SharedResources {
         builtin_entity_parser: Arc::new(builtin_entity_parser),
@@ -57,6 +95,7 @@ pub fn load_shared_resources<P: AsRef<Path>, Q: AsRef<Path>, R: AsRef<Path>>(
         stemmer,
         word_clusterers,
         stop_words,
+        feature_registry,
     }))
 }
 
@@ -76,6 +115,68 @@ pub fn load_engine_shared_resources<P: AsRef<Path>>(engine_dir: P) -> Result<Arc
     load_shared_resources(&resources_path, builtin_parser_path, custom_parser_path)
 }
 
+/// Loads the words and stems of the gazetteers, which are not kept by the shared resources as the
+/// gazetteers only store the hashes of their values
+pub(crate) fn load_gazetteers_vocabulary<P: AsRef<Path>>(resources_dir: P) -> Result<Vocabulary> {
+    let metadata = load_metadata(&resources_dir)?;
+    let mut vocabulary = Vocabulary::default();
+    if let Some(gazetteer_names) = metadata.gazetteers.as_ref() {
+        let gazetteers_directory = resources_dir.as_ref().join("gazetteers");
+        for gazetteer_name in gazetteer_names {
+            let gazetteer_path = gazetteers_directory
+                .join(gazetteer_name.clone())
+                .with_extension("txt");
+            let file = File::open(&gazetteer_path)
+                .with_context(|_| format!("Cannot open gazetteer file {:?}", gazetteer_path))?;
+            let mut values = vec![];
+            for line in BufReader::new(file).lines() {
+                let value = line?;
+                if !value.is_empty() {
+                    values.push(value);
+                }
+            }
+            if gazetteer_name.ends_with(STEMMED_GAZETTEER_SUFFIX) {
+                vocabulary.stems.extend(values);
+            } else {
+                vocabulary.words.extend(values);
+            }
+        }
+    }
+    Ok(vocabulary)
+}
+
+/// Loads the words which are reduced to each stem by the stemmer of the resources, which are used
+/// to turn stems back into proper words
+pub(crate) fn load_inflections<P: AsRef<Path>>(
+    resources_dir: P,
+) -> Result<HashMap<String, Vec<String>>> {
+    let metadata = load_metadata(&resources_dir)?;
+    if let Some(stems) = metadata.stems.as_ref() {
+        let stems_path = resources_dir
+            .as_ref()
+            .join("stemming")
+            .join(stems)
+            .with_extension("txt");
+        let stems_reader = File::open(&stems_path)
+            .with_context(|_| format!("Cannot open stems file {:?}", stems_path))?;
+        Ok(read_inflections(stems_reader)
+            .with_context(|_| format!("Cannot read stems file {:?}", stems_path))?)
+    } else {
+        Ok(HashMap::new())
+    }
+}
+
+fn load_metadata<P: AsRef<Path>>(resources_dir: &P) -> Result<ResourcesMetadata> {
+    let metadata_file_path = resources_dir.as_ref().join("metadata.json");
+    let metadata_file = File::open(&metadata_file_path)?;
+    Ok(serde_json::from_reader(metadata_file).with_context(|_| {
+        format!(
+            "Cannot deserialize resources metadata file '{:?}'",
+            metadata_file_path
+        )
+    })?)
+}
+
 fn load_stemmer<P: AsRef<Path>>(
     resources_dir: &P,
     metadata: &ResourcesMetadata,
@@ -10,6 +10,7 @@ use self::gazetteer::Gazetteer;
 use self::stemmer::Stemmer;
 use self::word_clusterer::WordClusterer;
 use super::entity_parser::{BuiltinEntityParser, CustomEntityParser};
+use super::slot_filler::FeatureRegistry;
 
 pub struct SharedResources {
     pub builtin_entity_parser: Arc<dyn BuiltinEntityParser>,
@@ -18,4 +19,5 @@ pub struct SharedResources {
     pub stemmer: Option<Arc<dyn Stemmer>>,
     pub word_clusterers: HashMap<String, Arc<dyn WordClusterer>>,
     pub stop_words: HashSet<String>,
+    pub feature_registry: FeatureRegistry,
 }
@@ -33,6 +33,29 @@ impl HashMapStemmer {
     }
 }
 
+/// Reads the words which are reduced to each stem, from a stems file in the format expected by
+/// `HashMapStemmer::from_reader`
+pub fn read_inflections<R: Read>(reader: R) -> Result<HashMap<String, Vec<String>>> {
+    let mut inflections = HashMap::new();
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .delimiter(b',')
+        .quoting(false)
+        .flexible(true)
+        .has_headers(false)
+        .from_reader(reader);
+
+    for record in csv_reader.records() {
+        let elements = record?;
+        let words = elements
+            .iter()
+            .skip(1)
+            .map(|word| word.to_string())
+            .collect();
+        inflections.insert(elements[0].to_string(), words);
+    }
+    Ok(inflections)
+}
+
 impl FromIterator<(String, String)> for HashMapStemmer {
     fn from_iter<T: IntoIterator<Item = (String, String)>>(iter: T) -> Self {
         Self {
@@ -76,4 +99,32 @@ do,done,don't,doing,did,does"#
         assert_eq!(stemmer.stem("\"investigate"), "investigate".to_string());
         assert_eq!(stemmer.stem("unknown"), "unknown".to_string());
     }
+
+    #[test]
+    fn test_read_inflections() {
+        // Given
+        let stems: &[u8] = r#"
+investigate,investigated,investigation
+do,done,doing,did,does"#
+            .as_ref();
+
+        // When
+        let inflections = read_inflections(stems).unwrap();
+
+        // Then
+        assert_eq!(2, inflections.len());
+        assert_eq!(
+            vec!["investigated".to_string(), "investigation".to_string()],
+            inflections["investigate"]
+        );
+        assert_eq!(
+            vec![
+                "done".to_string(),
+                "doing".to_string(),
+                "did".to_string(),
+                "does".to_string(),
+            ],
+            inflections["do"]
+        );
+    }
 }
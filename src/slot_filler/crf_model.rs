@@ -0,0 +1,480 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use failure::{bail, format_err, ResultExt};
+
+use crate::errors::*;
+
+const MODEL_MAGIC: &[u8] = b"lCRF";
+const MODEL_TYPE: &[u8] = b"FOMC";
+const HEADER_SIZE: usize = 48;
+const FEATURES_CHUNK_ID: &[u8] = b"FEAT";
+const CHUNK_HEADER_SIZE: usize = 12;
+const FEATURE_SIZE: usize = 20;
+const CQDB_CHUNK_ID: &[u8] = b"CQDB";
+const CQDB_HEADER_SIZE: usize = 24;
+const STATE_FEATURE: u32 = 0;
+const TRANSITION_FEATURE: u32 = 1;
+
+/// Linear-chain CRF model read from a file in the crfsuite format
+///
+/// This is a native implementation of the crfsuite tagger, which does not require the crfsuite C
+/// library. Items are sequences of `(key, value)` features, which are converted to the
+/// `"key:value"` attributes used during training, as done by python-crfsuite.
+pub struct CRFModel {
+    labels: Vec<String>,
+    attributes: HashMap<String, usize>,
+    /// Weights of the state features as `(label, weight)` pairs, for each attribute
+    state_weights: Vec<Vec<(usize, f64)>>,
+    /// Weights of the transitions from the row label to the column label
+    transition_weights: Vec<Vec<f64>>,
+}
+
+impl CRFModel {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let data = fs::read(&path)
+            .with_context(|_| format!("Cannot read CRF model file '{:?}'", path.as_ref()))?;
+        Ok(Self::from_bytes(&data)
+            .with_context(|_| format!("Invalid CRF model file '{:?}'", path.as_ref()))?)
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        if data.len() < HEADER_SIZE {
+            bail!("Truncated model header");
+        }
+        if &data[0..4] != MODEL_MAGIC || &data[8..12] != MODEL_TYPE {
+            bail!("Not a crfsuite CRF1d model");
+        }
+        let features_offset = read_u32(data, 28)? as usize;
+        let labels_offset = read_u32(data, 32)? as usize;
+        let attributes_offset = read_u32(data, 36)? as usize;
+
+        let labels = read_cqdb_strings(data, labels_offset)?;
+        let attributes = read_cqdb_strings(data, attributes_offset)?
+            .into_iter()
+            .enumerate()
+            .map(|(attribute_id, attribute)| (attribute, attribute_id))
+            .collect::<HashMap<_, _>>();
+
+        if read_bytes(data, features_offset, 4)? != FEATURES_CHUNK_ID {
+            bail!("Missing features chunk");
+        }
+        let nb_features = read_u32(data, features_offset + 8)? as usize;
+        let mut state_weights = vec![vec![]; attributes.len()];
+        let mut transition_weights = vec![vec![0.0; labels.len()]; labels.len()];
+        for feature_id in 0..nb_features {
+            let offset = features_offset + CHUNK_HEADER_SIZE + feature_id * FEATURE_SIZE;
+            let feature_type = read_u32(data, offset)?;
+            let source = read_u32(data, offset + 4)? as usize;
+            let destination = read_u32(data, offset + 8)? as usize;
+            let weight = read_f64(data, offset + 12)?;
+            if !weight.is_finite() {
+                return Err(SnipsNluError::NonFiniteModelValues("crf weights".to_string()).into());
+            }
+            if destination >= labels.len() {
+                bail!("Unknown label id {} in feature {}", destination, feature_id);
+            }
+            match feature_type {
+                STATE_FEATURE => state_weights
+                    .get_mut(source)
+                    .ok_or_else(|| {
+                        format_err!("Unknown attribute id {} in feature {}", source, feature_id)
+                    })?
+                    .push((destination, weight)),
+                TRANSITION_FEATURE => {
+                    *transition_weights
+                        .get_mut(source)
+                        .and_then(|row| row.get_mut(destination))
+                        .ok_or_else(|| {
+                            format_err!("Unknown label id {} in feature {}", source, feature_id)
+                        })? = weight
+                }
+                _ => bail!("Unknown type {} of feature {}", feature_type, feature_id),
+            }
+        }
+
+        Ok(Self {
+            labels,
+            attributes,
+            state_weights,
+            transition_weights,
+        })
+    }
+
+    /// Maps the labels of the model, for instance to decode them
+    pub fn map_labels<F>(mut self, mapper: F) -> Result<Self>
+    where
+        F: Fn(&str) -> Result<String>,
+    {
+        self.labels = self
+            .labels
+            .iter()
+            .map(|label| mapper(label))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(self)
+    }
+
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// Returns the most likely sequence of labels of the items, found with the Viterbi algorithm
+    pub fn tag(&self, items: &[Vec<(String, String)>]) -> Vec<String> {
+        self.viterbi(&self.state_scores(items))
+            .into_iter()
+            .map(|label_id| self.labels[label_id].clone())
+            .collect()
+    }
+
+    /// Returns the probability of the sequence of labels given the items
+    pub fn sequence_probability(
+        &self,
+        items: &[Vec<(String, String)>],
+        labels: &[String],
+    ) -> Result<f64> {
+        if items.len() != labels.len() {
+            bail!(
+                "Mismatched number of labels: {} labels for {} items",
+                labels.len(),
+                items.len()
+            );
+        }
+        let label_ids = labels
+            .iter()
+            .map(|label| {
+                self.labels
+                    .iter()
+                    .position(|l| l == label)
+                    .ok_or_else(|| format_err!("Unknown label '{}'", label))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let state_scores = self.state_scores(items);
+        let score = self.sequence_score(&state_scores, &label_ids);
+        let (_, scales) = self.forward(&state_scores);
+        let log_norm = -scales.iter().map(|scale| scale.ln()).sum::<f64>();
+        Ok((score - log_norm).exp())
+    }
+
+    /// Returns, for each item, the marginal probabilities of the labels, which are indexed as in
+    /// `labels()`
+    pub fn marginals(&self, items: &[Vec<(String, String)>]) -> Vec<Vec<f64>> {
+        let state_scores = self.state_scores(items);
+        let (alpha, scales) = self.forward(&state_scores);
+        let beta = self.backward(&state_scores, &scales);
+        alpha
+            .iter()
+            .zip(beta.iter())
+            .zip(scales.iter())
+            .map(|((alpha_t, beta_t), scale)| {
+                alpha_t
+                    .iter()
+                    .zip(beta_t.iter())
+                    .map(|(a, b)| a * b * (1.0 / scale))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl CRFModel {
+    fn state_scores(&self, items: &[Vec<(String, String)>]) -> Vec<Vec<f64>> {
+        items
+            .iter()
+            .map(|item| {
+                let mut scores = vec![0.0; self.labels.len()];
+                for (key, value) in item {
+                    let attribute = format!("{}:{}", key, value);
+                    if let Some(attribute_id) = self.attributes.get(&attribute) {
+                        for (label_id, weight) in &self.state_weights[*attribute_id] {
+                            scores[*label_id] += weight;
+                        }
+                    }
+                }
+                scores
+            })
+            .collect()
+    }
+
+    fn sequence_score(&self, state_scores: &[Vec<f64>], label_ids: &[usize]) -> f64 {
+        let mut score = 0.0;
+        for (t, label_id) in label_ids.iter().enumerate() {
+            if t > 0 {
+                score += self.transition_weights[label_ids[t - 1]][*label_id];
+            }
+            score += state_scores[t][*label_id];
+        }
+        score
+    }
+
+    fn viterbi(&self, state_scores: &[Vec<f64>]) -> Vec<usize> {
+        let nb_labels = self.labels.len();
+        if state_scores.is_empty() || nb_labels == 0 {
+            return vec![];
+        }
+        let mut back_pointers = vec![vec![0; nb_labels]; state_scores.len()];
+        let mut scores = state_scores[0].clone();
+        for (t, state_scores_t) in state_scores.iter().enumerate().skip(1) {
+            scores = state_scores_t
+                .iter()
+                .enumerate()
+                .map(|(j, state_score)| {
+                    // Ties are broken in favor of the first label, as in crfsuite
+                    let mut best_score = std::f64::MIN;
+                    for (i, score) in scores.iter().enumerate() {
+                        let score = score + self.transition_weights[i][j];
+                        if best_score < score {
+                            best_score = score;
+                            back_pointers[t][j] = i;
+                        }
+                    }
+                    best_score + state_score
+                })
+                .collect();
+        }
+        let mut best_label = 0;
+        for (label_id, score) in scores.iter().enumerate() {
+            if scores[best_label] < *score {
+                best_label = label_id;
+            }
+        }
+        let mut label_ids = vec![best_label; state_scores.len()];
+        for t in (1..state_scores.len()).rev() {
+            label_ids[t - 1] = back_pointers[t][label_ids[t]];
+        }
+        label_ids
+    }
+
+    /// Scaled forward algorithm, which returns the scaled forward scores along with the scaling
+    /// factors of each position
+    fn forward(&self, state_scores: &[Vec<f64>]) -> (Vec<Vec<f64>>, Vec<f64>) {
+        let mut alpha: Vec<Vec<f64>> = Vec::with_capacity(state_scores.len());
+        let mut scales = Vec::with_capacity(state_scores.len());
+        for (t, scores) in state_scores.iter().enumerate() {
+            let mut alpha_t: Vec<f64> = if t == 0 {
+                scores.iter().map(|score| score.exp()).collect()
+            } else {
+                let mut alpha_t = vec![0.0; scores.len()];
+                for (i, previous_alpha) in alpha[t - 1].iter().enumerate() {
+                    for (j, alpha_tj) in alpha_t.iter_mut().enumerate() {
+                        *alpha_tj += previous_alpha * self.transition_weights[i][j].exp();
+                    }
+                }
+                alpha_t
+                    .iter()
+                    .zip(scores.iter())
+                    .map(|(a, score)| a * score.exp())
+                    .collect()
+            };
+            let sum: f64 = alpha_t.iter().sum();
+            let scale = if sum != 0.0 { 1.0 / sum } else { 1.0 };
+            alpha_t.iter_mut().for_each(|a| *a *= scale);
+            alpha.push(alpha_t);
+            scales.push(scale);
+        }
+        (alpha, scales)
+    }
+
+    /// Scaled backward algorithm, using the scaling factors of the forward algorithm
+    fn backward(&self, state_scores: &[Vec<f64>], scales: &[f64]) -> Vec<Vec<f64>> {
+        let nb_labels = self.labels.len();
+        let mut beta = vec![vec![0.0; nb_labels]; state_scores.len()];
+        if state_scores.is_empty() {
+            return beta;
+        }
+        let last_position = state_scores.len() - 1;
+        beta[last_position] = vec![scales[last_position]; nb_labels];
+        for t in (1..state_scores.len()).rev() {
+            let weighted_beta: Vec<f64> = beta[t]
+                .iter()
+                .zip(state_scores[t].iter())
+                .map(|(b, score)| b * score.exp())
+                .collect();
+            beta[t - 1] = self
+                .transition_weights
+                .iter()
+                .map(|transitions| {
+                    let beta_i: f64 = transitions
+                        .iter()
+                        .zip(weighted_beta.iter())
+                        .map(|(transition, b)| transition.exp() * b)
+                        .sum();
+                    beta_i * scales[t - 1]
+                })
+                .collect();
+        }
+        beta
+    }
+}
+
+/// Reads the strings of a constant quark database, indexed by their ids, using its backward
+/// array
+fn read_cqdb_strings(data: &[u8], offset: usize) -> Result<Vec<String>> {
+    if read_bytes(data, offset, 4)? != CQDB_CHUNK_ID {
+        bail!("Missing string database at offset {}", offset);
+    }
+    let nb_strings = read_u32(data, offset + 16)? as usize;
+    let backward_array_offset = offset + read_u32(data, offset + 20)? as usize;
+    if backward_array_offset < offset + CQDB_HEADER_SIZE {
+        bail!("Invalid string database at offset {}", offset);
+    }
+    (0..nb_strings)
+        .map(|string_id| {
+            let record_offset = read_u32(data, backward_array_offset + 4 * string_id)? as usize;
+            if record_offset == 0 {
+                bail!(
+                    "Missing string {} in database at offset {}",
+                    string_id,
+                    offset
+                );
+            }
+            let record_offset = offset + record_offset;
+            let key_size = read_u32(data, record_offset + 4)? as usize;
+            let key = read_bytes(data, record_offset + 8, key_size)?;
+            // Keys are null terminated
+            let key = key.split(|b| *b == 0).next().unwrap_or(key);
+            Ok(String::from_utf8(key.to_vec())?)
+        })
+        .collect()
+}
+
+fn read_bytes(data: &[u8], offset: usize, length: usize) -> Result<&[u8]> {
+    data.get(offset..offset + length)
+        .ok_or_else(|| format_err!("Unexpected end of data at offset {}", offset))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    let bytes = read_bytes(data, offset, 4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_f64(data: &[u8], offset: usize) -> Result<f64> {
+    let bytes = read_bytes(data, offset, 8)?;
+    let mut buffer = [0; 8];
+    buffer.copy_from_slice(bytes);
+    Ok(f64::from_le_bytes(buffer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use itertools::Itertools;
+
+    use crate::testutils::epsilon_eq;
+
+    fn load_model() -> CRFModel {
+        let model_path = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage")
+            .join("probabilistic_intent_parser")
+            .join("slot_filler_0")
+            .join("model55imurmx.crfsuite");
+        CRFModel::from_path(model_path).unwrap()
+    }
+
+    fn get_items() -> Vec<Vec<(String, String)>> {
+        vec![
+            vec![
+                ("ngram_1".to_string(), "me".to_string()),
+                ("is_first".to_string(), "1".to_string()),
+            ],
+            vec![
+                ("ngram_1".to_string(), "rare_word".to_string()),
+                ("shape_ngram_1".to_string(), "xxx".to_string()),
+                (
+                    "builtin_entity_match_snips/number".to_string(),
+                    "B-".to_string(),
+                ),
+                ("unknown_feature".to_string(), "1".to_string()),
+            ],
+            vec![
+                ("ngram_1".to_string(), "cup".to_string()),
+                (
+                    "builtin_entity_match_snips/number[-1]".to_string(),
+                    "B-".to_string(),
+                ),
+                ("is_last".to_string(), "1".to_string()),
+            ],
+        ]
+    }
+
+    #[test]
+    fn test_load_labels() {
+        // When
+        let model = load_model().map_labels(|label| Ok(String::from_utf8(base64::decode(label)?)?));
+
+        // Then
+        let expected_labels = vec![
+            "O".to_string(),
+            "B-number_of_cups".to_string(),
+            "I-number_of_cups".to_string(),
+        ];
+        assert_eq!(expected_labels, model.unwrap().labels());
+    }
+
+    #[test]
+    fn test_tag_returns_the_most_probable_sequence() {
+        // Given
+        let model = load_model();
+        let items = get_items();
+
+        // When
+        let tags = model.tag(&items);
+
+        // Then
+        let sequences = (0..items.len())
+            .map(|_| model.labels().iter().cloned())
+            .multi_cartesian_product()
+            .collect_vec();
+        let probabilities = sequences
+            .iter()
+            .map(|sequence| model.sequence_probability(&items, sequence).unwrap())
+            .collect_vec();
+        let tags_probability = model.sequence_probability(&items, &tags).unwrap();
+        assert!(epsilon_eq(
+            1.0,
+            probabilities.iter().sum::<f64>() as f32,
+            1e-6
+        ));
+        assert!(probabilities.iter().all(|p| *p <= tags_probability));
+    }
+
+    #[test]
+    fn test_marginals() {
+        // Given
+        let model = load_model();
+        let items = get_items();
+
+        // When
+        let marginals = model.marginals(&items);
+
+        // Then
+        for (position, position_marginals) in marginals.iter().enumerate() {
+            for (label_id, label) in model.labels().iter().enumerate() {
+                let expected_marginal: f64 = (0..items.len())
+                    .map(|_| model.labels().iter().cloned())
+                    .multi_cartesian_product()
+                    .filter(|sequence| &sequence[position] == label)
+                    .map(|sequence| model.sequence_probability(&items, &sequence).unwrap())
+                    .sum();
+                assert!(epsilon_eq(
+                    expected_marginal as f32,
+                    position_marginals[label_id] as f32,
+                    1e-6
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn test_load_invalid_model() {
+        // When
+        let model =
+            CRFModel::from_bytes(b"not a crfsuite model, but a long enough sequence of bytes");
+
+        // Then
+        assert!(model.is_err());
+    }
+}
@@ -1,11 +1,18 @@
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+#[cfg(feature = "crfsuite")]
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
+#[cfg(feature = "crfsuite")]
+use std::sync::Mutex;
 
+#[cfg(feature = "crfsuite")]
 use crfsuite::Tagger as CRFSuiteTagger;
-use failure::{format_err, ResultExt};
+#[cfg(feature = "crfsuite")]
+use failure::format_err;
+use failure::ResultExt;
 use itertools::Itertools;
 use log::{debug, info};
 use snips_nlu_ontology::Language;
@@ -16,16 +23,17 @@ use crate::errors::*;
 use crate::language::FromLanguage;
 use crate::models::SlotFillerModel;
 use crate::resources::SharedResources;
+use crate::slot_filler::crf_model::CRFModel;
 use crate::slot_filler::crf_utils::*;
 use crate::slot_filler::feature_processor::ProbabilisticFeatureProcessor;
-use crate::slot_filler::SlotFiller;
+use crate::slot_filler::{FeatureCache, SlotFiller};
 use crate::slot_utils::*;
 use crate::utils::{EntityName, SlotName};
 
 pub struct CRFSlotFiller {
     language: Language,
     tagging_scheme: TaggingScheme,
-    tagger: Option<Mutex<CRFSuiteTagger>>,
+    tagger: Option<Box<dyn Tagger>>,
     feature_processor: Option<ProbabilisticFeatureProcessor>,
     slot_name_mapping: HashMap<SlotName, EntityName>,
 }
@@ -50,15 +58,12 @@ impl CRFSlotFiller {
         let slot_name_mapping = model.slot_name_mapping;
         let (tagger, feature_processor) =
             if let Some(crf_model_file) = model.crf_model_file.as_ref() {
-                let crf_path = path.as_ref().join(crf_model_file);
-                let tagger = CRFSuiteTagger::create_from_file(&crf_path).with_context(|_| {
-                    format!("Cannot create CRFSuiteTagger from file '{:?}'", &crf_path)
-                })?;
+                let tagger = load_tagger(path.as_ref().join(crf_model_file))?;
                 let feature_processor = ProbabilisticFeatureProcessor::new(
                     &model.config.feature_factory_configs,
                     shared_resources.clone(),
                 )?;
-                (Some(Mutex::new(tagger)), Some(feature_processor))
+                (Some(tagger), Some(feature_processor))
             } else {
                 (None, None)
             };
@@ -81,7 +86,11 @@ impl SlotFiller for CRFSlotFiller {
         self.tagging_scheme
     }
 
-    fn get_slots(&self, text: &str) -> Result<Vec<InternalSlot>> {
+    fn get_slots_with_feature_cache(
+        &self,
+        text: &str,
+        feature_cache: &FeatureCache,
+    ) -> Result<Vec<InternalSlot>> {
         debug!("Extracting slots...");
         let slots = if let (Some(ref tagger), Some(ref feature_processor)) =
             (self.tagger.as_ref(), self.feature_processor.as_ref())
@@ -90,14 +99,8 @@ impl SlotFiller for CRFSlotFiller {
             if tokens.is_empty() {
                 return Ok(vec![]);
             }
-            let features = feature_processor.compute_features(&&*tokens)?;
-            let tags = tagger
-                .lock()
-                .map_err(|e| format_err!("Poisonous mutex: {}", e))?
-                .tag(&features)?
-                .into_iter()
-                .map(|tag| decode_tag(&*tag))
-                .collect::<Result<Vec<String>>>()?;
+            let features = feature_processor.compute_features(&&*tokens, feature_cache)?;
+            let tags = tagger.tag(&features)?;
 
             tags_to_slots(
                 text,
@@ -117,15 +120,8 @@ impl SlotFiller for CRFSlotFiller {
         if let (Some(ref tagger), Some(ref feature_processor)) =
             (self.tagger.as_ref(), self.feature_processor.as_ref())
         {
-            let features = feature_processor.compute_features(&tokens)?;
-            let tagger = tagger
-                .lock()
-                .map_err(|e| format_err!("poisonous mutex: {}", e))?;
-            let tagger_labels = tagger
-                .labels()?
-                .into_iter()
-                .map(|label| decode_tag(&*label))
-                .collect::<Result<Vec<String>>>()?;
+            let features = feature_processor.compute_features(&tokens, &FeatureCache::default())?;
+            let tagger_labels = tagger.labels()?;
             let tagger_labels_slice = tagger_labels.iter().map(|l| &**l).collect_vec();
             // Substitute tags that were not seen during training
             let cleaned_tags = tags
@@ -137,10 +133,9 @@ impl SlotFiller for CRFSlotFiller {
                         get_substitution_label(&*tagger_labels_slice)
                     }
                 })
-                .map(|t| encode_tag(t))
+                .map(|t| t.to_string())
                 .collect_vec();
-            tagger.set(&features)?;
-            Ok(tagger.probability(&cleaned_tags)?)
+            tagger.sequence_probability(&features, &cleaned_tags)
         } else {
             // No tagger defined corresponds to an intent without slots
             Ok(tags
@@ -160,7 +155,7 @@ impl CRFSlotFiller {
         };
         Ok(
             if let Some(feature_processor) = self.feature_processor.as_ref() {
-                feature_processor.compute_features(&&*tokens)?
+                feature_processor.compute_features(&&*tokens, &FeatureCache::default())?
             } else {
                 tokens.iter().map(|_| vec![]).collect()
             },
@@ -168,6 +163,165 @@ impl CRFSlotFiller {
     }
 }
 
+/// Sequence tagger backing the CRF slot filler, whose tags are the decoded ones
+trait Tagger: Send + Sync {
+    fn tag(&self, features: &[Vec<(String, String)>]) -> Result<Vec<String>>;
+    fn labels(&self) -> Result<Vec<String>>;
+    fn sequence_probability(
+        &self,
+        features: &[Vec<(String, String)>],
+        tags: &[String],
+    ) -> Result<f64>;
+}
+
+#[cfg(feature = "crfsuite")]
+fn load_tagger(model_path: PathBuf) -> Result<Box<dyn Tagger>> {
+    Ok(Box::new(TaggerPool::new(model_path, num_cpus::get())?))
+}
+
+#[cfg(not(feature = "crfsuite"))]
+fn load_tagger(model_path: PathBuf) -> Result<Box<dyn Tagger>> {
+    Ok(Box::new(
+        CRFModel::from_path(model_path)?.map_labels(decode_tag)?,
+    ))
+}
+
+impl Tagger for CRFModel {
+    fn tag(&self, features: &[Vec<(String, String)>]) -> Result<Vec<String>> {
+        Ok(CRFModel::tag(self, features))
+    }
+
+    fn labels(&self) -> Result<Vec<String>> {
+        Ok(CRFModel::labels(self).to_vec())
+    }
+
+    fn sequence_probability(
+        &self,
+        features: &[Vec<(String, String)>],
+        tags: &[String],
+    ) -> Result<f64> {
+        CRFModel::sequence_probability(self, features, tags)
+    }
+}
+
+#[cfg(feature = "crfsuite")]
+/// Pool of crfsuite taggers sharing the same model
+///
+/// A crfsuite tagger cannot be used by several threads at once, so each concurrent call gets its
+/// own tagger from the pool. Taggers are created on demand, and at most `max_nb_taggers` of them
+/// are kept once returned, the other ones being dropped.
+struct TaggerPool {
+    model_path: PathBuf,
+    taggers: Mutex<Vec<CRFSuiteTagger>>,
+    max_nb_taggers: usize,
+}
+
+#[cfg(feature = "crfsuite")]
+impl TaggerPool {
+    fn new(model_path: PathBuf, max_nb_taggers: usize) -> Result<Self> {
+        let tagger = Self::create_tagger(&model_path)?;
+        Ok(Self {
+            model_path,
+            taggers: Mutex::new(vec![tagger]),
+            max_nb_taggers: max_nb_taggers.max(1),
+        })
+    }
+
+    fn get(&self) -> Result<PooledTagger> {
+        let available_tagger = self
+            .taggers
+            .lock()
+            .map_err(|e| format_err!("Cannot get a CRFSuiteTagger from the pool: {}", e))?
+            .pop();
+        let tagger = if let Some(tagger) = available_tagger {
+            tagger
+        } else {
+            debug!("Creating an additional CRFSuiteTagger");
+            Self::create_tagger(&self.model_path)?
+        };
+        Ok(PooledTagger {
+            tagger: Some(tagger),
+            pool: self,
+        })
+    }
+
+    fn create_tagger(model_path: &Path) -> Result<CRFSuiteTagger> {
+        Ok(
+            CRFSuiteTagger::create_from_file(model_path).with_context(|_| {
+                format!("Cannot create CRFSuiteTagger from file '{:?}'", model_path)
+            })?,
+        )
+    }
+}
+
+#[cfg(feature = "crfsuite")]
+/// Tagger borrowed from a `TaggerPool`, to which it is returned when dropped
+struct PooledTagger<'a> {
+    tagger: Option<CRFSuiteTagger>,
+    pool: &'a TaggerPool,
+}
+
+#[cfg(feature = "crfsuite")]
+impl<'a> Deref for PooledTagger<'a> {
+    type Target = CRFSuiteTagger;
+
+    fn deref(&self) -> &CRFSuiteTagger {
+        self.tagger.as_ref().unwrap()
+    }
+}
+
+#[cfg(feature = "crfsuite")]
+impl<'a> DerefMut for PooledTagger<'a> {
+    fn deref_mut(&mut self) -> &mut CRFSuiteTagger {
+        self.tagger.as_mut().unwrap()
+    }
+}
+
+#[cfg(feature = "crfsuite")]
+impl<'a> Drop for PooledTagger<'a> {
+    fn drop(&mut self) {
+        if let Some(tagger) = self.tagger.take() {
+            if let Ok(mut taggers) = self.pool.taggers.lock() {
+                if taggers.len() < self.pool.max_nb_taggers {
+                    taggers.push(tagger);
+                } else {
+                    debug!("Dropping an extra CRFSuiteTagger");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "crfsuite")]
+impl Tagger for TaggerPool {
+    fn tag(&self, features: &[Vec<(String, String)>]) -> Result<Vec<String>> {
+        self.get()?
+            .tag(features)?
+            .into_iter()
+            .map(|tag| decode_tag(&*tag))
+            .collect()
+    }
+
+    fn labels(&self) -> Result<Vec<String>> {
+        self.get()?
+            .labels()?
+            .into_iter()
+            .map(|label| decode_tag(&*label))
+            .collect()
+    }
+
+    fn sequence_probability(
+        &self,
+        features: &[Vec<(String, String)>],
+        tags: &[String],
+    ) -> Result<f64> {
+        let tagger = self.get()?;
+        let encoded_tags = tags.iter().map(|tag| encode_tag(tag)).collect_vec();
+        tagger.set(features)?;
+        Ok(tagger.probability(&encoded_tags)?)
+    }
+}
+
 // We need to use base64 encoding to ensure ascii encoding because of encoding issues in
 // python-crfsuite
 
@@ -176,6 +330,7 @@ fn decode_tag(tag: &str) -> Result<String> {
     Ok(String::from_utf8(bytes)?)
 }
 
+#[cfg(feature = "crfsuite")]
 fn encode_tag(tag: &str) -> String {
     base64::encode(tag)
 }
@@ -213,4 +368,211 @@ mod tests {
         }];
         assert_eq!(expected_slots, slots);
     }
+
+    #[test]
+    fn test_get_slots_concurrently() {
+        // Given
+        let trained_engine_path = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage");
+        let slot_filler_path = trained_engine_path
+            .join("probabilistic_intent_parser")
+            .join("slot_filler_0");
+        let resources = load_engine_shared_resources(trained_engine_path).unwrap();
+        let slot_filler = Arc::new(CRFSlotFiller::from_path(slot_filler_path, resources).unwrap());
+
+        // When
+        let handles = (0..4)
+            .map(|_| {
+                let slot_filler = slot_filler.clone();
+                std::thread::spawn(move || {
+                    (0..10)
+                        .map(|_| slot_filler.get_slots("make me two cups of coffee"))
+                        .collect::<Result<Vec<_>>>()
+                })
+            })
+            .collect_vec();
+        let results = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap().unwrap())
+            .collect_vec();
+
+        // Then
+        let expected_slots = vec![InternalSlot {
+            value: "two".to_string(),
+            char_range: 8..11,
+            entity: "snips/number".to_string(),
+            slot_name: "number_of_cups".to_string(),
+        }];
+        for slots in results.into_iter().flatten() {
+            assert_eq!(expected_slots, slots);
+        }
+    }
+
+    #[cfg(feature = "crfsuite")]
+    #[test]
+    fn test_tagger_pool_reuses_taggers() {
+        // Given
+        let crf_path = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage")
+            .join("probabilistic_intent_parser")
+            .join("slot_filler_0")
+            .join("model55imurmx.crfsuite");
+        let pool = TaggerPool::new(crf_path, 2).unwrap();
+
+        // When
+        {
+            let _first_tagger = pool.get().unwrap();
+            let _second_tagger = pool.get().unwrap();
+        }
+        let _third_tagger = pool.get().unwrap();
+
+        // Then
+        assert_eq!(1, pool.taggers.lock().unwrap().len());
+    }
+
+    #[cfg(feature = "crfsuite")]
+    #[test]
+    fn test_tagger_pool_drops_extra_taggers() {
+        // Given
+        let crf_path = Path::new("data")
+            .join("tests")
+            .join("models")
+            .join("nlu_engine_beverage")
+            .join("probabilistic_intent_parser")
+            .join("slot_filler_0")
+            .join("model55imurmx.crfsuite");
+        let pool = TaggerPool::new(crf_path, 2).unwrap();
+
+        // When
+        {
+            let _first_tagger = pool.get().unwrap();
+            let _second_tagger = pool.get().unwrap();
+            let _third_tagger = pool.get().unwrap();
+        }
+
+        // Then
+        assert_eq!(2, pool.taggers.lock().unwrap().len());
+    }
+
+    #[cfg(feature = "crfsuite")]
+    #[test]
+    fn test_native_tagger_matches_crfsuite_tagger() {
+        // Given
+        let engines_inputs = vec![
+            (
+                "nlu_engine_beverage",
+                vec![
+                    "make me two cups of coffee",
+                    "I want three hot teas please",
+                    "can you prepare 3 cups of iced tea at 80 degrees",
+                    "tea",
+                ],
+            ),
+            (
+                "nlu_engine_game",
+                vec![
+                    "I want to play to invader attack 3",
+                    "let's play the last game of the legend of zelda",
+                    "play",
+                ],
+            ),
+            (
+                "nlu_engine_music",
+                vec![
+                    "play the album thriller by michael jackson",
+                    "I want to listen to my workout playlist",
+                    "play some music",
+                ],
+            ),
+        ];
+
+        for (engine_name, inputs) in engines_inputs {
+            let trained_engine_path = Path::new("data")
+                .join("tests")
+                .join("models")
+                .join(engine_name);
+            let resources = load_engine_shared_resources(&trained_engine_path).unwrap();
+            let slot_filler_paths =
+                fs::read_dir(trained_engine_path.join("probabilistic_intent_parser"))
+                    .unwrap()
+                    .map(|entry| entry.unwrap().path())
+                    .filter(|path| {
+                        path.file_name()
+                            .and_then(|name| name.to_str())
+                            .map_or(false, |name| name.starts_with("slot_filler"))
+                    })
+                    .sorted()
+                    .collect_vec();
+            assert!(!slot_filler_paths.is_empty());
+
+            for slot_filler_path in slot_filler_paths {
+                let slot_filler =
+                    CRFSlotFiller::from_path(&slot_filler_path, resources.clone()).unwrap();
+                let crf_model_path = fs::read_dir(&slot_filler_path)
+                    .unwrap()
+                    .map(|entry| entry.unwrap().path())
+                    .find(|path| path.extension().map_or(false, |ext| ext == "crfsuite"))
+                    .unwrap();
+                let crfsuite_tagger = TaggerPool::new(crf_model_path.clone(), 1).unwrap();
+                let native_tagger = CRFModel::from_path(crf_model_path)
+                    .unwrap()
+                    .map_labels(decode_tag)
+                    .unwrap();
+                let labels = Tagger::labels(&native_tagger).unwrap();
+
+                for input in &inputs {
+                    let features = slot_filler.compute_features(input).unwrap();
+
+                    // When
+                    let crfsuite_tags = Tagger::tag(&crfsuite_tagger, &features).unwrap();
+                    let native_tags = Tagger::tag(&native_tagger, &features).unwrap();
+                    let crfsuite_probability =
+                        Tagger::sequence_probability(&crfsuite_tagger, &features, &crfsuite_tags)
+                            .unwrap();
+                    let native_probability =
+                        Tagger::sequence_probability(&native_tagger, &features, &native_tags)
+                            .unwrap();
+                    let crfsuite_marginals = {
+                        let tagger = crfsuite_tagger.get().unwrap();
+                        tagger.set(&features).unwrap();
+                        (0..features.len())
+                            .map(|position| {
+                                labels
+                                    .iter()
+                                    .map(|label| {
+                                        tagger
+                                            .marginal(&encode_tag(label), position as i32)
+                                            .unwrap()
+                                    })
+                                    .collect_vec()
+                            })
+                            .collect_vec()
+                    };
+                    let native_marginals = native_tagger.marginals(&features);
+
+                    // Then
+                    assert_eq!(labels, Tagger::labels(&crfsuite_tagger).unwrap());
+                    assert_eq!(crfsuite_tags, native_tags);
+                    assert!((crfsuite_probability - native_probability).abs() < 1e-12);
+                    assert_eq!(crfsuite_marginals.len(), native_marginals.len());
+                    for (crfsuite_marginal, native_marginal) in crfsuite_marginals
+                        .iter()
+                        .flatten()
+                        .zip(native_marginals.iter().flatten())
+                    {
+                        assert!(
+                            (crfsuite_marginal - native_marginal).abs() < 1e-9,
+                            "Marginals differ for input '{}' of {:?}",
+                            input,
+                            slot_filler_path
+                        );
+                    }
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+use snips_nlu_utils::token::Token;
+
+use crate::errors::*;
+
+type TokensKey = Vec<(String, Range<usize>)>;
+type FeatureValues = Arc<Vec<Option<String>>>;
+
+/// Cache of the values of the CRF slot filler features, scoped to the parsing of a single input
+///
+/// Values are cached per tokenized input and per feature configuration, so that the slot fillers
+/// of the different intents parsed for a same input compute each common feature only once. The
+/// engine creates a new cache for each parsing call, which is passed down to the slot fillers.
+#[derive(Default)]
+pub struct FeatureCache {
+    values: Mutex<HashMap<TokensKey, HashMap<String, FeatureValues>>>,
+}
+
+impl FeatureCache {
+    /// Returns the values of the feature identified by `feature_key` for each of the `tokens`,
+    /// computing them with `producer` when they are not cached yet
+    pub(crate) fn get_or_compute<F>(
+        &self,
+        tokens: &[Token],
+        feature_key: &str,
+        producer: F,
+    ) -> Result<FeatureValues>
+    where
+        F: Fn() -> Result<Vec<Option<String>>>,
+    {
+        let tokens_key: TokensKey = tokens
+            .iter()
+            .map(|token| (token.value.clone(), token.char_range.clone()))
+            .collect();
+        let cached_values = self
+            .values
+            .lock()
+            .unwrap()
+            .get(&tokens_key)
+            .and_then(|input_values| input_values.get(feature_key))
+            .cloned();
+        if let Some(values) = cached_values {
+            return Ok(values);
+        }
+        let values = Arc::new(producer()?);
+        self.values
+            .lock()
+            .unwrap()
+            .entry(tokens_key)
+            .or_insert_with(HashMap::new)
+            .insert(feature_key.to_string(), values.clone());
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use snips_nlu_utils::language::Language;
+    use snips_nlu_utils::token::tokenize;
+
+    #[test]
+    fn test_feature_values_are_computed_once_per_input_and_feature() {
+        // Given
+        let feature_cache = FeatureCache::default();
+        let nb_computations = AtomicUsize::new(0);
+        let compute_values = || {
+            nb_computations.fetch_add(1, Ordering::Relaxed);
+            Ok(vec![Some("1".to_string()), None])
+        };
+        let tokens = tokenize("make coffee", Language::EN);
+        let other_tokens = tokenize("make tea", Language::EN);
+
+        // When
+        for _ in 0..2 {
+            feature_cache
+                .get_or_compute(&tokens, "is_first", compute_values)
+                .unwrap();
+            feature_cache
+                .get_or_compute(&tokens, "is_last", compute_values)
+                .unwrap();
+            feature_cache
+                .get_or_compute(&other_tokens, "is_first", compute_values)
+                .unwrap();
+        }
+
+        // Then
+        assert_eq!(3, nb_computations.load(Ordering::Relaxed));
+    }
+}
@@ -1,12 +1,15 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::sync::Arc;
 
-use failure::bail;
+use failure::{bail, format_err};
+use itertools::Itertools;
 use snips_nlu_utils::token::Token;
 
 use crate::errors::*;
 use crate::models::FeatureFactory;
 use crate::resources::SharedResources;
+use crate::slot_filler::feature_cache::FeatureCache;
 use crate::slot_filler::features::*;
 
 pub struct ProbabilisticFeatureProcessor {
@@ -34,11 +37,20 @@ impl ProbabilisticFeatureProcessor {
 
 impl ProbabilisticFeatureProcessor {
     #[rustfmt::skip]
-    pub fn compute_features(&self, input: &&[Token]) -> Result<Vec<Vec<(String, String)>>> {
+    pub fn compute_features(
+        &self,
+        input: &&[Token],
+        feature_cache: &FeatureCache,
+    ) -> Result<Vec<Vec<(String, String)>>> {
         let mut features = vec![vec![]; input.len()];
         for offsetter in self.features_offsetters.iter() {
-            for i in 0..input.len() {
-                if let Some(value) = offsetter.feature.compute(input, i)? {
+            let values = feature_cache.get_or_compute(
+                input,
+                &offsetter.cache_key,
+                || (0..input.len()).map(|i| offsetter.feature.compute(input, i)).collect(),
+            )?;
+            for (i, value) in values.iter().enumerate() {
+                if let Some(value) = value {
                     offsetter.offsets_with_name().iter().for_each(|&(offset, ref key)| {
                         if i as i32 - offset >= 0 && i as i32 - offset < input.len() as i32 {
                             features[(i as i32 - offset) as usize].push(
@@ -56,6 +68,8 @@ impl ProbabilisticFeatureProcessor {
 struct FeatureOffsetter {
     feature: Box<dyn Feature>,
     offsets: Vec<i32>,
+    /// Identifies the feature along with its configuration in the feature cache
+    cache_key: String,
 }
 
 impl FeatureOffsetter {
@@ -80,10 +94,13 @@ pub trait FeatureKindRepr {
     fn feature_kind(&self) -> FeatureKind;
 }
 
-pub trait Feature: FeatureKindRepr + Send + Sync {
-    fn name(&self) -> String {
-        self.feature_kind().identifier().to_string()
-    }
+/// Feature computed on each token of the input by the CRF slot filler
+///
+/// Features which are not natively supported can be provided by the application through a
+/// `FeatureRegistry`.
+pub trait Feature: Send + Sync {
+    /// Name of the feature, as found in the CRF model
+    fn name(&self) -> String;
     fn build_features(
         args: &HashMap<String, serde_json::Value>,
         shared_resources: Arc<SharedResources>,
@@ -93,6 +110,78 @@ pub trait Feature: FeatureKindRepr + Send + Sync {
     fn compute(&self, tokens: &[Token], token_index: usize) -> Result<Option<String>>;
 }
 
+type FeatureBuilder = Arc<
+    dyn Fn(
+            &HashMap<String, serde_json::Value>,
+            Arc<SharedResources>,
+        ) -> Result<Vec<Box<dyn Feature>>>
+        + Send
+        + Sync,
+>;
+
+/// Features provided by the application, which are used by the CRF slot filler when the
+/// `feature_factory_configs` of its model reference them by factory name
+#[derive(Clone, Default)]
+pub struct FeatureRegistry {
+    builders: HashMap<String, FeatureBuilder>,
+}
+
+impl FeatureRegistry {
+    /// Registers the feature `F` under the provided factory name, which must not be the name of
+    /// a natively supported feature
+    pub fn register<F: Feature + 'static>(self, factory_name: &str) -> Result<Self> {
+        self.register_builder(factory_name, F::build_features)
+    }
+
+    /// Registers a function building the features of the provided factory name from the
+    /// `args` of the model, which lets the features carry application state
+    pub fn register_builder<B>(mut self, factory_name: &str, builder: B) -> Result<Self>
+    where
+        B: Fn(
+                &HashMap<String, serde_json::Value>,
+                Arc<SharedResources>,
+            ) -> Result<Vec<Box<dyn Feature>>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        if FeatureKind::from_identifier(factory_name).is_some() {
+            bail!(
+                "Cannot register feature '{}' as it is natively supported",
+                factory_name
+            );
+        }
+        self.builders
+            .insert(factory_name.to_string(), Arc::new(builder));
+        Ok(self)
+    }
+
+    pub fn factory_names(&self) -> Vec<&str> {
+        self.builders.keys().map(|name| &**name).sorted().collect()
+    }
+
+    fn build_features(
+        &self,
+        factory_name: &str,
+        args: &HashMap<String, serde_json::Value>,
+        shared_resources: Arc<SharedResources>,
+    ) -> Result<Vec<Box<dyn Feature>>> {
+        let builder = self
+            .builders
+            .get(factory_name)
+            .ok_or_else(|| format_err!("Feature {} not implemented", factory_name))?;
+        builder(args, shared_resources)
+    }
+}
+
+impl fmt::Debug for FeatureRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FeatureRegistry")
+            .field("factory_names", &self.factory_names())
+            .finish()
+    }
+}
+
 get_features!([
     (IsDigitFeature, is_digit),
     (LengthFeature, length),
@@ -111,9 +200,42 @@ get_features!([
 mod tests {
     use super::*;
 
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use maplit::hashmap;
+    use serde_json::json;
     use snips_nlu_utils::language::Language;
     use snips_nlu_utils::token::tokenize;
 
+    use crate::testutils::SharedResourcesBuilder;
+
+    struct LexiconFeature {
+        words: HashSet<String>,
+    }
+
+    impl Feature for LexiconFeature {
+        fn name(&self) -> String {
+            "lexicon".to_string()
+        }
+
+        fn build_features(
+            args: &HashMap<String, serde_json::Value>,
+            _shared_resources: Arc<SharedResources>,
+        ) -> Result<Vec<Box<dyn Feature>>> {
+            let words = serde_json::from_value(args["words"].clone())?;
+            Ok(vec![Box::new(Self { words })])
+        }
+
+        fn compute(&self, tokens: &[Token], token_index: usize) -> Result<Option<String>> {
+            Ok(if self.words.contains(&tokens[token_index].value) {
+                Some("1".to_string())
+            } else {
+                None
+            })
+        }
+    }
+
     #[test]
     fn test_compute_features() {
         // Given
@@ -123,17 +245,21 @@ mod tests {
                 FeatureOffsetter {
                     offsets: vec![0],
                     feature: Box::new(IsDigitFeature {}) as Box<_>,
+                    cache_key: "is_digit".to_string(),
                 },
                 FeatureOffsetter {
                     offsets: vec![0],
                     feature: Box::new(LengthFeature {}) as Box<_>,
+                    cache_key: "length".to_string(),
                 },
             ],
         };
         let tokens = tokenize("I prefer 7 over 777", language);
 
         // When
-        let computed_features = fp.compute_features(&tokens.as_slice()).unwrap();
+        let computed_features = fp
+            .compute_features(&tokens.as_slice(), &FeatureCache::default())
+            .unwrap();
 
         let expected_features = vec![
             vec![("length".to_string(), "1".to_string())],
@@ -162,17 +288,21 @@ mod tests {
                 FeatureOffsetter {
                     offsets: vec![-2, 0, 3],
                     feature: Box::new(IsDigitFeature {}) as Box<_>,
+                    cache_key: "is_digit".to_string(),
                 },
                 FeatureOffsetter {
                     offsets: vec![-1, 1],
                     feature: Box::new(LengthFeature {}) as Box<_>,
+                    cache_key: "length".to_string(),
                 },
             ],
         };
         let tokens = tokenize("I prefer 7 over 777", language);
 
         // When
-        let computed_features = fp.compute_features(&tokens.as_slice()).unwrap();
+        let computed_features = fp
+            .compute_features(&tokens.as_slice(), &FeatureCache::default())
+            .unwrap();
 
         // Then
         let expected_features = vec![
@@ -199,4 +329,206 @@ mod tests {
         ];
         assert_eq!(expected_features, computed_features);
     }
+
+    #[test]
+    fn test_compute_registered_features() {
+        // Given
+        let language = Language::EN;
+        let feature_registry = FeatureRegistry::default()
+            .register::<LexiconFeature>("lexicon")
+            .unwrap();
+        let shared_resources = Arc::new(
+            SharedResourcesBuilder::default()
+                .feature_registry(feature_registry)
+                .build(),
+        );
+        let feature_factories = vec![
+            FeatureFactory {
+                factory_name: "is_digit".to_string(),
+                offsets: vec![0],
+                args: hashmap![],
+            },
+            FeatureFactory {
+                factory_name: "lexicon".to_string(),
+                offsets: vec![0, 1],
+                args: hashmap! {"words".to_string() => json!(["coffee", "tea"])},
+            },
+        ];
+        let fp = ProbabilisticFeatureProcessor::new(&feature_factories, shared_resources).unwrap();
+        let tokens = tokenize("2 coffee", language);
+
+        // When
+        let computed_features = fp
+            .compute_features(&tokens.as_slice(), &FeatureCache::default())
+            .unwrap();
+
+        // Then
+        let expected_features = vec![
+            vec![
+                ("is_digit".to_string(), "1".to_string()),
+                ("lexicon[+1]".to_string(), "1".to_string()),
+            ],
+            vec![("lexicon".to_string(), "1".to_string())],
+        ];
+        assert_eq!(expected_features, computed_features);
+    }
+
+    #[test]
+    fn test_compute_features_registered_with_builder() {
+        // Given
+        let language = Language::EN;
+        let application_words: HashSet<String> = vec!["latte".to_string()].into_iter().collect();
+        let feature_registry = FeatureRegistry::default()
+            .register_builder("lexicon", move |_, _| {
+                let words = application_words.clone();
+                Ok(vec![Box::new(LexiconFeature { words }) as Box<dyn Feature>])
+            })
+            .unwrap();
+        let shared_resources = Arc::new(
+            SharedResourcesBuilder::default()
+                .feature_registry(feature_registry)
+                .build(),
+        );
+        let feature_factories = vec![FeatureFactory {
+            factory_name: "lexicon".to_string(),
+            offsets: vec![0],
+            args: hashmap![],
+        }];
+        let fp = ProbabilisticFeatureProcessor::new(&feature_factories, shared_resources).unwrap();
+        let tokens = tokenize("a latte please", language);
+
+        // When
+        let computed_features = fp
+            .compute_features(&tokens.as_slice(), &FeatureCache::default())
+            .unwrap();
+
+        // Then
+        let expected_features = vec![
+            vec![],
+            vec![("lexicon".to_string(), "1".to_string())],
+            vec![],
+        ];
+        assert_eq!(expected_features, computed_features);
+    }
+
+    #[test]
+    fn test_feature_cache_is_shared_by_processors() {
+        // Given
+        struct CountingFeature {
+            nb_computations: Arc<AtomicUsize>,
+        }
+
+        impl Feature for CountingFeature {
+            fn name(&self) -> String {
+                "counting".to_string()
+            }
+
+            fn build_features(
+                _args: &HashMap<String, serde_json::Value>,
+                _shared_resources: Arc<SharedResources>,
+            ) -> Result<Vec<Box<dyn Feature>>> {
+                bail!("CountingFeature must be registered with a builder")
+            }
+
+            fn compute(&self, _tokens: &[Token], _token_index: usize) -> Result<Option<String>> {
+                self.nb_computations.fetch_add(1, Ordering::Relaxed);
+                Ok(None)
+            }
+        }
+
+        let language = Language::EN;
+        let nb_computations = Arc::new(AtomicUsize::new(0));
+        let feature_nb_computations = nb_computations.clone();
+        let feature_registry = FeatureRegistry::default()
+            .register::<LexiconFeature>("lexicon")
+            .unwrap()
+            .register_builder("counting", move |_, _| {
+                let nb_computations = feature_nb_computations.clone();
+                Ok(vec![
+                    Box::new(CountingFeature { nb_computations }) as Box<dyn Feature>
+                ])
+            })
+            .unwrap();
+        let shared_resources = Arc::new(
+            SharedResourcesBuilder::default()
+                .feature_registry(feature_registry)
+                .build(),
+        );
+        let build_feature_factories = |words: serde_json::Value| {
+            vec![
+                FeatureFactory {
+                    factory_name: "counting".to_string(),
+                    offsets: vec![0],
+                    args: hashmap![],
+                },
+                FeatureFactory {
+                    factory_name: "lexicon".to_string(),
+                    offsets: vec![0],
+                    args: hashmap! {"words".to_string() => words},
+                },
+            ]
+        };
+        let coffee_fp = ProbabilisticFeatureProcessor::new(
+            &build_feature_factories(json!(["coffee"])),
+            shared_resources.clone(),
+        )
+        .unwrap();
+        let tea_fp = ProbabilisticFeatureProcessor::new(
+            &build_feature_factories(json!(["tea"])),
+            shared_resources,
+        )
+        .unwrap();
+        let tokens = tokenize("2 coffee or tea", language);
+        let feature_cache = FeatureCache::default();
+
+        // When
+        let coffee_features = coffee_fp
+            .compute_features(&tokens.as_slice(), &feature_cache)
+            .unwrap();
+        let tea_features = tea_fp
+            .compute_features(&tokens.as_slice(), &feature_cache)
+            .unwrap();
+        let uncached_tea_features = tea_fp
+            .compute_features(&tokens.as_slice(), &FeatureCache::default())
+            .unwrap();
+
+        // Then
+        let expected_coffee_features = vec![
+            vec![],
+            vec![("lexicon".to_string(), "1".to_string())],
+            vec![],
+            vec![],
+        ];
+        let expected_tea_features = vec![
+            vec![],
+            vec![],
+            vec![],
+            vec![("lexicon".to_string(), "1".to_string())],
+        ];
+        assert_eq!(expected_coffee_features, coffee_features);
+        assert_eq!(expected_tea_features, tea_features);
+        assert_eq!(expected_tea_features, uncached_tea_features);
+        // The counting feature is computed once with the shared cache, and once without
+        assert_eq!(2 * tokens.len(), nb_computations.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_unregistered_features_are_not_implemented() {
+        // Given
+        let shared_resources = Arc::new(SharedResourcesBuilder::default().build());
+        let feature_factories = vec![FeatureFactory {
+            factory_name: "lexicon".to_string(),
+            offsets: vec![0],
+            args: hashmap! {"words".to_string() => json!(["coffee"])},
+        }];
+
+        // When
+        let fp = ProbabilisticFeatureProcessor::new(&feature_factories, shared_resources);
+        let native_feature_registration =
+            FeatureRegistry::default().register::<LexiconFeature>("ngram");
+
+        // Then
+        assert!(fp.is_err());
+        assert!(native_feature_registration.is_err());
+    }
 }
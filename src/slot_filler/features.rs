@@ -22,6 +22,10 @@ use super::features_utils::{get_word_chunk, initial_string_from_tokens};
 pub struct IsDigitFeature {}
 
 impl Feature for IsDigitFeature {
+    fn name(&self) -> String {
+        self.feature_kind().identifier().to_string()
+    }
+
     fn build_features(
         _args: &HashMap<String, serde_json::Value>,
         _shared_resources: Arc<SharedResources>,
@@ -43,6 +47,10 @@ impl Feature for IsDigitFeature {
 pub struct LengthFeature {}
 
 impl Feature for LengthFeature {
+    fn name(&self) -> String {
+        self.feature_kind().identifier().to_string()
+    }
+
     fn build_features(
         _args: &HashMap<String, serde_json::Value>,
         _shared_resources: Arc<SharedResources>,
@@ -61,6 +69,10 @@ impl Feature for LengthFeature {
 pub struct IsFirstFeature {}
 
 impl Feature for IsFirstFeature {
+    fn name(&self) -> String {
+        self.feature_kind().identifier().to_string()
+    }
+
     fn build_features(
         _args: &HashMap<String, serde_json::Value>,
         _shared_resources: Arc<SharedResources>,
@@ -80,6 +92,10 @@ impl Feature for IsFirstFeature {
 pub struct IsLastFeature {}
 
 impl Feature for IsLastFeature {
+    fn name(&self) -> String {
+        self.feature_kind().identifier().to_string()
+    }
+
     fn build_features(
         _args: &HashMap<String, serde_json::Value>,
         _shared_resources: Arc<SharedResources>,
@@ -715,6 +731,7 @@ mod tests {
                 alternative_resolved_values: vec![],
                 range: 12..31,
                 entity_identifier: entity_name.to_string(),
+                match_score: None,
             }],
         )]);
         let tagging_scheme = TaggingScheme::BILOU;
@@ -757,6 +774,7 @@ mod tests {
                 alternative_resolved_values: vec![],
                 range: 7..16,
                 entity_identifier: "bird_type".to_string(),
+                match_score: None,
             }],
         )]);
 
@@ -14,6 +14,15 @@ macro_rules! get_features {
                     )*
                 }
             }
+
+            pub fn from_identifier(identifier: &str) -> Option<Self> {
+                match identifier {
+                    $(
+                        stringify!($feature_name) => Some(FeatureKind::$feature_type),
+                    )*
+                    _ => None,
+                }
+            }
         }
 
         $(
@@ -32,11 +41,21 @@ macro_rules! get_features {
                 $(
                     stringify!($feature_name) => $feature_type::build_features(&f.args, shared_resources),
                 )*
-                _ => bail!("Feature {} not implemented", f.factory_name),
+                factory_name => shared_resources.feature_registry.build_features(
+                    factory_name,
+                    &f.args,
+                    shared_resources.clone(),
+                ),
             };
+            // Arguments are sorted so that identical configurations share the same cache key
+            let args: ::std::collections::BTreeMap<_, _> = f.args.iter().collect();
+            let args = serde_json::to_string(&args)?;
             Ok(features?
                 .into_iter()
-                .map(|feature| FeatureOffsetter { feature, offsets: f.offsets.clone() })
+                .map(|feature| {
+                    let cache_key = format!("{}:{}:{}", f.factory_name, feature.name(), args);
+                    FeatureOffsetter { feature, offsets: f.offsets.clone(), cache_key }
+                })
                 .collect())
         }
     }
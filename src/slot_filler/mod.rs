@@ -1,7 +1,9 @@
 #[macro_use]
 mod macros;
+mod crf_model;
 pub mod crf_slot_filler;
 mod crf_utils;
+mod feature_cache;
 mod feature_processor;
 mod features;
 mod features_utils;
@@ -18,12 +20,24 @@ use crate::models::ProcessingUnitMetadata;
 use crate::resources::SharedResources;
 use crate::slot_utils::InternalSlot;
 
+pub use self::crf_model::CRFModel;
 pub use self::crf_slot_filler::*;
+pub use self::feature_cache::FeatureCache;
+pub use self::feature_processor::{Feature, FeatureRegistry};
 use self::crf_utils::TaggingScheme;
 
 pub trait SlotFiller: Send + Sync {
     fn get_tagging_scheme(&self) -> TaggingScheme;
-    fn get_slots(&self, text: &str) -> Result<Vec<InternalSlot>>;
+    fn get_slots(&self, text: &str) -> Result<Vec<InternalSlot>> {
+        self.get_slots_with_feature_cache(text, &FeatureCache::default())
+    }
+    /// Same as `get_slots`, but reuses the feature values computed by the other slot fillers
+    /// while parsing the same input
+    fn get_slots_with_feature_cache(
+        &self,
+        text: &str,
+        feature_cache: &FeatureCache,
+    ) -> Result<Vec<InternalSlot>>;
     fn get_sequence_probability(&self, tokens: &[Token], tags: Vec<String>) -> Result<f64>;
 }
 
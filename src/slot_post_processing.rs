@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use itertools::Itertools;
+use serde::Serialize;
+use snips_nlu_ontology::{IntentClassifierResult, Slot};
+
+use crate::utils::{EntityName, SlotName};
+
+/// Outcome of the post processing of a resolved slot
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlotPostProcessing {
+    /// The slot, possibly transformed, is kept in the parsing result
+    Accept(Slot),
+    /// The slot is rejected for the provided reason
+    ///
+    /// `SnipsNluEngine::parse_with_slot_validation` removes the rejected slot from the parsing
+    /// result and reports it along with the reason. The other parsing methods, which cannot report
+    /// the rejection, keep the slot as it was provided to the rejecting post processor.
+    Reject(String),
+}
+
+/// Hook which transforms, validates or rejects the slots once they have been resolved
+///
+/// Post processors are registered per entity or per slot name in a `SlotPostProcessors` which
+/// is provided to the engine through `NluEngineConfig`.
+pub trait SlotPostProcessor: Send + Sync {
+    fn process(&self, slot: Slot) -> SlotPostProcessing;
+}
+
+/// Slot which has been rejected by a `SlotPostProcessor`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlotValidationError {
+    /// Slot as it was provided to the rejecting post processor
+    pub slot: Slot,
+    pub reason: String,
+}
+
+/// Parsing result along with the slots which have been rejected by the post processors, and the
+/// slots which do not comply with the intent schema of the engine
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatedIntentParserResult {
+    pub input: String,
+    pub intent: IntentClassifierResult,
+    pub slots: Vec<Slot>,
+    pub slot_validation_errors: Vec<SlotValidationError>,
+    /// Required slots of the intent schema which have not been extracted
+    pub missing_slots: Vec<SlotName>,
+    /// Slots of the intent schema which have been extracted more times than allowed
+    pub duplicated_slots: Vec<SlotName>,
+}
+
+/// Post processors applied to the resolved slots, by entity and by slot name
+///
+/// The post processors of the slot entity run first, followed by the ones of the slot name, each
+/// of them in registration order and receiving the slot returned by the previous one. The first
+/// rejection stops the processing of the slot.
+#[derive(Clone, Default)]
+pub struct SlotPostProcessors {
+    entity_processors: HashMap<EntityName, Vec<Arc<dyn SlotPostProcessor>>>,
+    slot_name_processors: HashMap<SlotName, Vec<Arc<dyn SlotPostProcessor>>>,
+}
+
+impl SlotPostProcessors {
+    /// Registers a post processor for the slots of the provided entity, e.g. `snips/number`
+    pub fn register_for_entity<P: SlotPostProcessor + 'static>(
+        mut self,
+        entity: &str,
+        processor: P,
+    ) -> Self {
+        self.entity_processors
+            .entry(entity.to_string())
+            .or_insert_with(Vec::new)
+            .push(Arc::new(processor));
+        self
+    }
+
+    /// Registers a post processor for the slots having the provided name, in all intents
+    pub fn register_for_slot_name<P: SlotPostProcessor + 'static>(
+        mut self,
+        slot_name: &str,
+        processor: P,
+    ) -> Self {
+        self.slot_name_processors
+            .entry(slot_name.to_string())
+            .or_insert_with(Vec::new)
+            .push(Arc::new(processor));
+        self
+    }
+
+    pub fn entities(&self) -> Vec<&str> {
+        self.entity_processors
+            .keys()
+            .map(|entity| &**entity)
+            .sorted()
+            .collect()
+    }
+
+    pub fn slot_names(&self) -> Vec<&str> {
+        self.slot_name_processors
+            .keys()
+            .map(|slot_name| &**slot_name)
+            .sorted()
+            .collect()
+    }
+
+    /// Runs the post processors on each slot, splitting the accepted slots from the rejected ones
+    pub(crate) fn process_slots(&self, slots: Vec<Slot>) -> (Vec<Slot>, Vec<SlotValidationError>) {
+        let mut accepted_slots = Vec::with_capacity(slots.len());
+        let mut validation_errors = vec![];
+        for slot in slots {
+            match self.process_slot(slot) {
+                Ok(slot) => accepted_slots.push(slot),
+                Err(validation_error) => validation_errors.push(validation_error),
+            }
+        }
+        (accepted_slots, validation_errors)
+    }
+
+    /// Runs the post processors on each slot, keeping the rejected slots as they were provided to
+    /// the rejecting post processor, for the parsing methods which cannot report the rejections
+    pub(crate) fn transform_slots(&self, slots: Vec<Slot>) -> Vec<Slot> {
+        slots
+            .into_iter()
+            .map(|slot| self.transform_slot(slot))
+            .collect()
+    }
+
+    pub(crate) fn transform_slot(&self, slot: Slot) -> Slot {
+        self.process_slot(slot)
+            .unwrap_or_else(|validation_error| validation_error.slot)
+    }
+
+    pub(crate) fn process_slot(&self, slot: Slot) -> Result<Slot, SlotValidationError> {
+        let entity_processors = self.entity_processors.get(&slot.entity);
+        let slot_name_processors = self.slot_name_processors.get(&slot.slot_name);
+        let processors = entity_processors
+            .into_iter()
+            .chain(slot_name_processors)
+            .flatten();
+        let mut processed_slot = slot;
+        for processor in processors {
+            match processor.process(processed_slot.clone()) {
+                SlotPostProcessing::Accept(slot) => processed_slot = slot,
+                SlotPostProcessing::Reject(reason) => {
+                    return Err(SlotValidationError {
+                        slot: processed_slot,
+                        reason,
+                    });
+                }
+            }
+        }
+        Ok(processed_slot)
+    }
+}
+
+impl fmt::Debug for SlotPostProcessors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SlotPostProcessors")
+            .field("entities", &self.entities())
+            .field("slot_names", &self.slot_names())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use snips_nlu_ontology::{NumberValue, SlotValue};
+
+    struct ClampNumber {
+        max: f64,
+    }
+
+    impl SlotPostProcessor for ClampNumber {
+        fn process(&self, mut slot: Slot) -> SlotPostProcessing {
+            if let SlotValue::Number(NumberValue { value }) = slot.value {
+                slot.value = SlotValue::Number(NumberValue {
+                    value: value.min(self.max),
+                });
+            }
+            SlotPostProcessing::Accept(slot)
+        }
+    }
+
+    struct RejectAbove {
+        max: f64,
+    }
+
+    impl SlotPostProcessor for RejectAbove {
+        fn process(&self, slot: Slot) -> SlotPostProcessing {
+            match slot.value {
+                SlotValue::Number(NumberValue { value }) if value > self.max => {
+                    SlotPostProcessing::Reject(format!("{} is above {}", value, self.max))
+                }
+                _ => SlotPostProcessing::Accept(slot),
+            }
+        }
+    }
+
+    fn number_slot(value: f64, slot_name: &str) -> Slot {
+        Slot {
+            raw_value: value.to_string(),
+            value: SlotValue::Number(NumberValue { value }),
+            alternatives: vec![],
+            range: 0..2,
+            entity: "snips/number".to_string(),
+            slot_name: slot_name.to_string(),
+            confidence_score: None,
+        }
+    }
+
+    #[test]
+    fn test_process_slots() {
+        // Given
+        let post_processors = SlotPostProcessors::default()
+            .register_for_slot_name("number_of_cups", RejectAbove { max: 5.0 })
+            .register_for_entity("snips/number", ClampNumber { max: 10.0 });
+        let slots = vec![
+            number_slot(3.0, "number_of_cups"),
+            number_slot(12.0, "number_of_cups"),
+            number_slot(12.0, "temperature"),
+        ];
+
+        // When
+        let (accepted_slots, validation_errors) = post_processors.process_slots(slots);
+
+        // Then
+        let expected_accepted_slots = vec![
+            number_slot(3.0, "number_of_cups"),
+            Slot {
+                value: SlotValue::Number(NumberValue { value: 10.0 }),
+                ..number_slot(12.0, "temperature")
+            },
+        ];
+        let expected_validation_errors = vec![SlotValidationError {
+            slot: Slot {
+                value: SlotValue::Number(NumberValue { value: 10.0 }),
+                ..number_slot(12.0, "number_of_cups")
+            },
+            reason: "10 is above 5".to_string(),
+        }];
+        assert_eq!(expected_accepted_slots, accepted_slots);
+        assert_eq!(expected_validation_errors, validation_errors);
+    }
+
+    #[test]
+    fn test_transform_slots() {
+        // Given
+        let post_processors = SlotPostProcessors::default()
+            .register_for_slot_name("number_of_cups", RejectAbove { max: 5.0 })
+            .register_for_entity("snips/number", ClampNumber { max: 10.0 });
+        let slots = vec![
+            number_slot(3.0, "number_of_cups"),
+            number_slot(12.0, "number_of_cups"),
+        ];
+
+        // When
+        let transformed_slots = post_processors.transform_slots(slots);
+
+        // Then
+        let expected_slots = vec![
+            number_slot(3.0, "number_of_cups"),
+            Slot {
+                value: SlotValue::Number(NumberValue { value: 10.0 }),
+                ..number_slot(12.0, "number_of_cups")
+            },
+        ];
+        assert_eq!(expected_slots, transformed_slots);
+    }
+}
@@ -1,7 +1,11 @@
+use std::cmp::Reverse;
 use std::ops::Range;
 use std::sync::Arc;
 
-use snips_nlu_ontology::{BuiltinEntity, BuiltinEntityKind, Slot, SlotValue};
+use serde::Serialize;
+use snips_nlu_ontology::{
+    BuiltinEntity, BuiltinEntityKind, IntentClassifierResult, Slot, SlotValue,
+};
 
 use crate::entity_parser::{BuiltinEntityParser, CustomEntity, CustomEntityParser};
 use crate::errors::*;
@@ -16,6 +20,82 @@ pub struct InternalSlot {
     pub slot_name: SlotName,
 }
 
+/// Slot along with the slots nested in its value
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NestedSlot {
+    #[serde(flatten)]
+    pub slot: Slot,
+    pub sub_slots: Vec<NestedSlot>,
+}
+
+/// Parsing result whose slots may contain other slots
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NestedIntentParserResult {
+    pub input: String,
+    pub intent: IntentClassifierResult,
+    pub slots: Vec<NestedSlot>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InternalNestedSlot {
+    pub slot: InternalSlot,
+    pub sub_slots: Vec<InternalNestedSlot>,
+}
+
+pub fn range_contains(outer_range: &Range<usize>, inner_range: &Range<usize>) -> bool {
+    outer_range.start <= inner_range.start && inner_range.end <= outer_range.end
+}
+
+/// Builds the trees of slots, each slot being nested in the smallest slot which contains it
+pub fn nest_slots(mut slots: Vec<InternalSlot>) -> Vec<InternalNestedSlot> {
+    fn close_last_slot(
+        open_slots: &mut Vec<InternalNestedSlot>,
+        top_level_slots: &mut Vec<InternalNestedSlot>,
+    ) {
+        if let Some(closed_slot) = open_slots.pop() {
+            if let Some(parent_slot) = open_slots.last_mut() {
+                parent_slot.sub_slots.push(closed_slot);
+            } else {
+                top_level_slots.push(closed_slot);
+            }
+        }
+    }
+
+    slots.sort_by_key(|slot| (slot.char_range.start, Reverse(slot.char_range.end)));
+    let mut top_level_slots = vec![];
+    let mut open_slots: Vec<InternalNestedSlot> = vec![];
+    for slot in slots {
+        while open_slots
+            .last()
+            .map(|open_slot| !range_contains(&open_slot.slot.char_range, &slot.char_range))
+            .unwrap_or(false)
+        {
+            close_last_slot(&mut open_slots, &mut top_level_slots);
+        }
+        open_slots.push(InternalNestedSlot {
+            slot,
+            sub_slots: vec![],
+        });
+    }
+    while !open_slots.is_empty() {
+        close_last_slot(&mut open_slots, &mut top_level_slots);
+    }
+    top_level_slots
+}
+
+/// Keeps only the slots which are not nested in other slots, preserving their order
+pub fn top_level_slots(slots: Vec<InternalSlot>) -> Vec<InternalSlot> {
+    let top_level_slots: Vec<InternalSlot> = nest_slots(slots.clone())
+        .into_iter()
+        .map(|nested_slot| nested_slot.slot)
+        .collect();
+    slots
+        .into_iter()
+        .filter(|slot| top_level_slots.contains(slot))
+        .collect()
+}
+
 pub fn resolve_builtin_slot(
     internal_slot: InternalSlot,
     builtin_entities: &[BuiltinEntity],
@@ -71,17 +151,18 @@ pub fn resolve_custom_slot(
             Some((
                 matching_entity.resolved_value,
                 matching_entity.alternative_resolved_values,
+                matching_entity.match_score,
             ))
         })
         .unwrap_or_else(|| {
             if entity.automatically_extensible {
-                Some((internal_slot.value.clone(), vec![]))
+                Some((internal_slot.value.clone(), vec![], None))
             } else {
                 None
             }
         })
-        .map(|(resolved_value, alternatives)| {
-            convert_to_custom_slot(internal_slot, resolved_value, alternatives)
+        .map(|(resolved_value, alternatives, match_score)| {
+            convert_to_custom_slot(internal_slot, resolved_value, alternatives, match_score)
         });
     Ok(resolved_slot)
 }
@@ -90,6 +171,7 @@ fn convert_to_custom_slot(
     slot: InternalSlot,
     resolved_value: String,
     alternatives: Vec<String>,
+    match_score: Option<f32>,
 ) -> Slot {
     let value = SlotValue::Custom(resolved_value.into());
     let alternatives = alternatives
@@ -103,7 +185,7 @@ fn convert_to_custom_slot(
         range: slot.char_range,
         entity: slot.entity,
         slot_name: slot.slot_name,
-        confidence_score: None,
+        confidence_score: match_score,
     }
 }
 
@@ -129,6 +211,42 @@ mod tests {
     use std::iter::FromIterator;
     use std::path::Path;
 
+    #[test]
+    fn test_nest_slots() {
+        // Given
+        let internal_slot = |value: &str, char_range: Range<usize>, entity: &str| InternalSlot {
+            value: value.to_string(),
+            char_range,
+            entity: entity.to_string(),
+            slot_name: entity.to_string(),
+        };
+        let location = internal_slot("the office of mom", 23..40, "location");
+        let contact = internal_slot("mom", 37..40, "contact");
+        let time = internal_slot("tomorrow", 41..49, "snips/datetime");
+        let slots = vec![time.clone(), contact.clone(), location.clone()];
+
+        // When
+        let nested_slots = nest_slots(slots.clone());
+        let top_slots = top_level_slots(slots);
+
+        // Then
+        let expected_nested_slots = vec![
+            InternalNestedSlot {
+                slot: location.clone(),
+                sub_slots: vec![InternalNestedSlot {
+                    slot: contact,
+                    sub_slots: vec![],
+                }],
+            },
+            InternalNestedSlot {
+                slot: time.clone(),
+                sub_slots: vec![],
+            },
+        ];
+        assert_eq!(expected_nested_slots, nested_slots);
+        assert_eq!(vec![time, location], top_slots);
+    }
+
     #[test]
     fn test_resolve_builtin_slot() {
         // Given
@@ -288,6 +406,7 @@ mod tests {
                 resolved_value: "Publisher".to_string(),
                 alternative_resolved_values: vec![],
                 entity_identifier: "userType".to_string(),
+                match_score: None,
             },
             CustomEntity {
                 value: "subscriber".to_string(),
@@ -295,6 +414,7 @@ mod tests {
                 resolved_value: "Subscriber".to_string(),
                 alternative_resolved_values: vec![],
                 entity_identifier: "userType".to_string(),
+                match_score: None,
             },
         ];
         let mocked_entity_parser = Arc::new(MockedCustomEntityParser::from_iter(vec![]));
@@ -322,6 +442,51 @@ mod tests {
         assert_eq!(expected_result, resolved_slot);
     }
 
+    #[test]
+    fn test_resolve_custom_slot_with_fuzzy_match_score() {
+        // Given
+        let entity = Entity {
+            automatically_extensible: false,
+        };
+        let internal_slot = InternalSlot {
+            value: "subscribr".to_string(),
+            char_range: 27..36,
+            entity: "userType".to_string(),
+            slot_name: "userType".to_string(),
+        };
+        let custom_entities = vec![CustomEntity {
+            value: "subscribr".to_string(),
+            range: 27..36,
+            resolved_value: "Subscriber".to_string(),
+            alternative_resolved_values: vec![],
+            entity_identifier: "userType".to_string(),
+            match_score: Some(0.9),
+        }];
+        let mocked_entity_parser = Arc::new(MockedCustomEntityParser::from_iter(vec![]));
+
+        // When
+        let resolved_slot = resolve_custom_slot(
+            internal_slot,
+            &entity,
+            &custom_entities,
+            mocked_entity_parser,
+            0,
+        )
+        .unwrap();
+
+        // Then
+        let expected_result = Some(Slot {
+            raw_value: "subscribr".to_string(),
+            value: SlotValue::Custom("Subscriber".into()),
+            alternatives: vec![],
+            range: 27..36,
+            entity: "userType".to_string(),
+            slot_name: "userType".to_string(),
+            confidence_score: Some(0.9),
+        });
+        assert_eq!(expected_result, resolved_slot);
+    }
+
     #[test]
     fn test_resolve_custom_slot_when_no_entities_found_on_whole_input() {
         // Given
@@ -343,6 +508,7 @@ mod tests {
                 resolved_value: "Subscriber".to_string(),
                 alternative_resolved_values: vec![],
                 entity_identifier: "userType".to_string(),
+                match_score: None,
             }],
         )]));
 
@@ -456,6 +622,7 @@ mod tests {
                 resolved_value: "Publisher".to_string(),
                 alternative_resolved_values: vec![],
                 entity_identifier: "userType".to_string(),
+                match_score: None,
             },
             CustomEntity {
                 value: "subscriber".to_string(),
@@ -463,6 +630,7 @@ mod tests {
                 resolved_value: "Subscriber".to_string(),
                 alternative_resolved_values: vec!["Alternative Subscriber".to_string()],
                 entity_identifier: "userType".to_string(),
+                match_score: None,
             },
         ];
         let mocked_entity_parser = Arc::new(MockedCustomEntityParser::from_iter(vec![]));
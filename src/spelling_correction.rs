@@ -0,0 +1,562 @@
+use std::cmp::min;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Range;
+use std::sync::Arc;
+
+use itertools::Itertools;
+use serde::Serialize;
+use snips_nlu_ontology::{IntentClassifierResult, Slot};
+use snips_nlu_utils::string::{normalize, substring_with_char_range};
+
+use crate::errors::*;
+use crate::normalization::NormalizedText;
+use crate::resources::SharedResources;
+
+/// Words shorter than this are never corrected, as they are close to too many other words
+const MIN_CORRECTED_WORD_LENGTH: usize = 4;
+/// Words shorter than this are corrected with at most one edit
+const MIN_WORD_LENGTH_FOR_SEVERAL_EDITS: usize = 8;
+
+/// Options of the spelling correction of the inputs
+#[derive(Debug, Clone)]
+pub struct SpellingCorrectionConfig {
+    max_edit_distance: usize,
+    max_proposals: usize,
+    additional_words: Vec<String>,
+}
+
+impl Default for SpellingCorrectionConfig {
+    fn default() -> Self {
+        Self {
+            max_edit_distance: 2,
+            max_proposals: 3,
+            additional_words: vec![],
+        }
+    }
+}
+
+impl SpellingCorrectionConfig {
+    /// Maximum number of character insertions, deletions, substitutions and transpositions
+    /// between a misspelled word and its correction, words of less than 8 characters being
+    /// corrected with at most one edit
+    pub fn max_edit_distance(mut self, max_edit_distance: usize) -> Self {
+        self.max_edit_distance = max_edit_distance;
+        self
+    }
+
+    /// Maximum number of corrected inputs which are parsed in addition to the input
+    pub fn max_proposals(mut self, max_proposals: usize) -> Self {
+        self.max_proposals = max_proposals;
+        self
+    }
+
+    /// Words which the inputs can be corrected to in addition to the vocabulary of the intent
+    /// parsers, the gazetteers and the values of the fuzzy matched custom entities
+    ///
+    /// The values of the other custom entities cannot be listed from the gazetteer parser of the
+    /// engine, which only recognizes them, and are typically provided here. Words matched by the
+    /// custom entity parser are never corrected.
+    pub fn additional_words(mut self, additional_words: Vec<String>) -> Self {
+        self.additional_words = additional_words;
+        self
+    }
+}
+
+/// Words known by an intent parser, which are used to correct the spelling of the inputs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Vocabulary {
+    pub words: Vec<String>,
+    /// Stems known by the parsers which use stemming, which are replaced by the words reduced to
+    /// them before being used as corrections
+    pub stems: Vec<String>,
+}
+
+impl Vocabulary {
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty() && self.stems.is_empty()
+    }
+
+    pub fn extend(&mut self, other: Vocabulary) {
+        self.words.extend(other.words);
+        self.stems.extend(other.stems);
+    }
+
+    /// Words of the vocabulary, each stem being replaced by the words which are reduced to it, or
+    /// kept when no word is reduced to it as it is then a word as well
+    pub(crate) fn into_words(self, inflections: &HashMap<String, Vec<String>>) -> Vec<String> {
+        let unstemmed_words = self.stems.into_iter().flat_map(|stem| {
+            inflections
+                .get(&stem)
+                .filter(|words| !words.is_empty())
+                .cloned()
+                .unwrap_or_else(|| vec![stem])
+        });
+        self.words.into_iter().chain(unstemmed_words).collect()
+    }
+}
+
+/// Correction of a misspelled word of an input
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpellingCorrection {
+    /// Word as written in the input
+    pub raw_value: String,
+    pub corrected_value: String,
+    /// Range of characters of the word in the input
+    pub range: Range<usize>,
+}
+
+/// Parsing result of an input along with the spelling corrections which have been applied to it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CorrectedIntentParserResult {
+    pub input: String,
+    /// Normalized and corrected input which has been parsed, `None` when the input yields a better
+    /// result than its corrections
+    pub corrected_input: Option<String>,
+    pub corrections: Vec<SpellingCorrection>,
+    pub intent: IntentClassifierResult,
+    /// Slots extracted from the corrected input, whose ranges and raw values refer to the input
+    pub slots: Vec<Slot>,
+}
+
+/// Normalized input in which misspelled words have been replaced
+pub(crate) struct CorrectedInput {
+    pub text: NormalizedText,
+    pub corrections: Vec<SpellingCorrection>,
+}
+
+/// Proposes corrections of the words of the inputs which are unknown to the engine
+///
+/// Words are known when they belong to the vocabulary or to one of the gazetteers of the engine,
+/// or when they are part of an entity matched by the builtin or custom entity parsers. Unknown
+/// words are replaced by the closest words of the vocabulary.
+pub(crate) struct SpellingCorrector {
+    vocabulary: HashSet<String>,
+    /// Vocabulary words indexed by their number of characters, so that only the words whose
+    /// length is within the maximum edit distance of a misspelled word are compared to it
+    words_by_length: BTreeMap<usize, Vec<String>>,
+    shared_resources: Arc<SharedResources>,
+    max_edit_distance: usize,
+    max_proposals: usize,
+}
+
+impl SpellingCorrector {
+    pub fn new<I>(
+        vocabulary: I,
+        config: SpellingCorrectionConfig,
+        shared_resources: Arc<SharedResources>,
+    ) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let vocabulary: HashSet<String> = vocabulary
+            .into_iter()
+            .chain(config.additional_words)
+            .flat_map(|value| value.split_whitespace().map(normalize).collect::<Vec<_>>())
+            .filter(|word| !word.is_empty() && word.chars().all(char::is_alphabetic))
+            .collect();
+        let mut words_by_length = BTreeMap::new();
+        for word in vocabulary.iter() {
+            words_by_length
+                .entry(word.chars().count())
+                .or_insert_with(Vec::new)
+                .push(word.clone());
+        }
+        Self {
+            vocabulary,
+            words_by_length,
+            shared_resources,
+            max_edit_distance: config.max_edit_distance,
+            max_proposals: config.max_proposals,
+        }
+    }
+
+    /// Proposes corrected versions of the normalized input, sorted by increasing number of edits
+    ///
+    /// The first proposal replaces each misspelled word with its closest correction, while the
+    /// following ones use another correction for one of the words. No proposal is returned when
+    /// all the words are known.
+    pub fn propose_corrections(
+        &self,
+        input: &str,
+        normalized_input: &NormalizedText,
+    ) -> Result<Vec<CorrectedInput>> {
+        if self.max_proposals == 0 || self.max_edit_distance == 0 {
+            return Ok(vec![]);
+        }
+        let chars: Vec<char> = normalized_input.text.chars().collect();
+        let entity_ranges = self.entity_ranges(&normalized_input.text)?;
+        let misspelled_words: Vec<(Range<usize>, Vec<(usize, &str)>)> = word_ranges(&chars)
+            .into_iter()
+            .filter(|range| {
+                !entity_ranges.iter().any(|entity_range| {
+                    entity_range.start < range.end && range.start < entity_range.end
+                })
+            })
+            .filter_map(|range| {
+                let word: String = chars[range.clone()].iter().collect();
+                let candidates = self.find_candidates(&word);
+                if candidates.is_empty() {
+                    None
+                } else {
+                    Some((range, candidates))
+                }
+            })
+            .collect();
+        if misspelled_words.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let best_choice = vec![0; misspelled_words.len()];
+        let mut choices = vec![best_choice.clone()];
+        for (word_index, (_, candidates)) in misspelled_words.iter().enumerate() {
+            for candidate_index in 1..candidates.len() {
+                let mut choice = best_choice.clone();
+                choice[word_index] = candidate_index;
+                choices.push(choice);
+            }
+        }
+        let nb_edits = |choice: &Vec<usize>| -> usize {
+            choice
+                .iter()
+                .zip(misspelled_words.iter())
+                .map(|(candidate_index, (_, candidates))| candidates[*candidate_index].0)
+                .sum()
+        };
+        choices.sort_by_key(nb_edits);
+
+        Ok(choices
+            .into_iter()
+            .take(self.max_proposals)
+            .map(|choice| {
+                let replacements: Vec<(Range<usize>, String)> = misspelled_words
+                    .iter()
+                    .zip(choice)
+                    .map(|((range, candidates), candidate_index)| {
+                        let raw_word: String = chars[range.clone()].iter().collect();
+                        let correction = match_case(&raw_word, candidates[candidate_index].1);
+                        (range.clone(), correction)
+                    })
+                    .collect();
+                let text = normalized_input.replace_ranges(&replacements);
+                let corrections = replacements
+                    .into_iter()
+                    .map(|(range, corrected_value)| {
+                        let range = normalized_input.original_range(&range);
+                        SpellingCorrection {
+                            raw_value: substring_with_char_range(input.to_string(), &range),
+                            corrected_value,
+                            range,
+                        }
+                    })
+                    .collect();
+                CorrectedInput { text, corrections }
+            })
+            .collect())
+    }
+
+    /// Ranges of the builtin and custom entities matched in the text
+    fn entity_ranges(&self, text: &str) -> Result<Vec<Range<usize>>> {
+        let builtin_entities = self
+            .shared_resources
+            .builtin_entity_parser
+            .extract_entities(text, None, true, 0)?;
+        let custom_entities = self
+            .shared_resources
+            .custom_entity_parser
+            .extract_entities(text, None, 0)?;
+        Ok(builtin_entities
+            .into_iter()
+            .map(|entity| entity.range)
+            .chain(custom_entities.into_iter().map(|entity| entity.range))
+            .collect())
+    }
+
+    fn is_known_word(&self, word: &str) -> bool {
+        if self.vocabulary.contains(word) {
+            return true;
+        }
+        let stemmed_word = self
+            .shared_resources
+            .stemmer
+            .as_ref()
+            .map(|stemmer| stemmer.stem(word));
+        self.shared_resources.gazetteers.values().any(|gazetteer| {
+            gazetteer.contains(word)
+                || stemmed_word
+                    .as_ref()
+                    .map(|stemmed_word| gazetteer.contains(stemmed_word))
+                    .unwrap_or(false)
+        })
+    }
+
+    /// Closest vocabulary words along with their edit distance to the word, which are empty when
+    /// the word is known or too short to be corrected
+    fn find_candidates(&self, word: &str) -> Vec<(usize, &str)> {
+        let normalized_word = normalize(word);
+        let word_chars: Vec<char> = normalized_word.chars().collect();
+        if word_chars.len() < MIN_CORRECTED_WORD_LENGTH || self.is_known_word(&normalized_word) {
+            return vec![];
+        }
+        let max_edit_distance = if word_chars.len() < MIN_WORD_LENGTH_FOR_SEVERAL_EDITS {
+            min(1, self.max_edit_distance)
+        } else {
+            self.max_edit_distance
+        };
+        let min_length = word_chars.len().saturating_sub(max_edit_distance);
+        let max_length = word_chars.len() + max_edit_distance;
+        self.words_by_length
+            .range(min_length..=max_length)
+            .flat_map(|(_, words)| words)
+            .filter_map(|candidate| {
+                edit_distance(&word_chars, candidate, max_edit_distance)
+                    .map(|distance| (distance, &**candidate))
+            })
+            .sorted()
+            .take(self.max_proposals)
+            .collect()
+    }
+}
+
+/// Ranges of the sequences of alphabetic characters
+fn word_ranges(chars: &[char]) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut word_start = None;
+    for (index, c) in chars.iter().enumerate() {
+        match (c.is_alphabetic(), word_start) {
+            (true, None) => word_start = Some(index),
+            (false, Some(start)) => {
+                ranges.push(start..index);
+                word_start = None;
+            }
+            _ => (),
+        }
+    }
+    if let Some(start) = word_start {
+        ranges.push(start..chars.len());
+    }
+    ranges
+}
+
+/// Optimal string alignment distance between the two words, `None` when it is above
+/// `max_distance`
+fn edit_distance(word: &[char], other_word: &str, max_distance: usize) -> Option<usize> {
+    let other_word: Vec<char> = other_word.chars().collect();
+    let (length, other_length) = (word.len(), other_word.len());
+    if length.max(other_length) - length.min(other_length) > max_distance {
+        return None;
+    }
+    let mut distances = vec![vec![0; other_length + 1]; length + 1];
+    distances[0] = (0..=other_length).collect();
+    for i in 1..=length {
+        distances[i][0] = i;
+        for j in 1..=other_length {
+            let substitution_cost = if word[i - 1] == other_word[j - 1] {
+                0
+            } else {
+                1
+            };
+            let mut distance = min(
+                min(distances[i - 1][j] + 1, distances[i][j - 1] + 1),
+                distances[i - 1][j - 1] + substitution_cost,
+            );
+            if i > 1
+                && j > 1
+                && word[i - 1] == other_word[j - 2]
+                && word[i - 2] == other_word[j - 1]
+            {
+                distance = min(distance, distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+        if distances[i].iter().all(|distance| *distance > max_distance) {
+            return None;
+        }
+    }
+    Some(distances[length][other_length]).filter(|distance| *distance <= max_distance)
+}
+
+/// Applies the case of the misspelled word to its correction
+fn match_case(raw_word: &str, correction: &str) -> String {
+    if raw_word.chars().all(char::is_uppercase) {
+        return correction.to_uppercase();
+    }
+    let mut correction_chars = correction.chars();
+    match (raw_word.chars().next(), correction_chars.next()) {
+        (Some(first_raw_char), Some(first_char)) if first_raw_char.is_uppercase() => {
+            first_char.to_uppercase().chain(correction_chars).collect()
+        }
+        _ => correction.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::iter::FromIterator;
+
+    use maplit::hashmap;
+    use snips_nlu_ontology::Language;
+
+    use crate::entity_parser::custom_entity_parser::CustomEntity;
+    use crate::normalization::{NormalizationStep, TextNormalizer};
+    use crate::resources::gazetteer::HashSetGazetteer;
+    use crate::testutils::{MockedCustomEntityParser, SharedResourcesBuilder};
+
+    fn build_corrector(shared_resources: SharedResources) -> SpellingCorrector {
+        let vocabulary = vec!["make", "coffee", "cups", "prepare", "please"]
+            .into_iter()
+            .map(|word| word.to_string());
+        SpellingCorrector::new(
+            vocabulary,
+            SpellingCorrectionConfig::default().additional_words(vec!["boiling hot".to_string()]),
+            Arc::new(shared_resources),
+        )
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        // Given
+        let word: Vec<char> = "cofee".chars().collect();
+
+        // When
+        let distances = vec![
+            edit_distance(&word, "coffee", 2),
+            edit_distance(&word, "ocfee", 2),
+            edit_distance(&word, "cofee", 2),
+            edit_distance(&word, "tea", 2),
+        ];
+
+        // Then
+        assert_eq!(vec![Some(1), Some(1), Some(0), None], distances);
+    }
+
+    #[test]
+    fn test_propose_corrections() {
+        // Given
+        let shared_resources = SharedResourcesBuilder::default()
+            .gazetteer(
+                "common_words",
+                HashSetGazetteer::from_iter(vec!["some".to_string()]),
+            )
+            .build();
+        let corrector = build_corrector(shared_resources);
+        let normalizer = TextNormalizer::new(vec![NormalizationStep::UnicodeNfkc], Language::EN);
+        let input = "Prepar some Cofee, boilling hot pleese";
+        let normalized_input = normalizer.normalize(input);
+
+        // When
+        let proposals = corrector
+            .propose_corrections(input, &normalized_input)
+            .unwrap();
+
+        // Then
+        let expected_corrections = vec![
+            SpellingCorrection {
+                raw_value: "Prepar".to_string(),
+                corrected_value: "Prepare".to_string(),
+                range: 0..6,
+            },
+            SpellingCorrection {
+                raw_value: "Cofee".to_string(),
+                corrected_value: "Coffee".to_string(),
+                range: 12..17,
+            },
+            SpellingCorrection {
+                raw_value: "boilling".to_string(),
+                corrected_value: "boiling".to_string(),
+                range: 19..27,
+            },
+            SpellingCorrection {
+                raw_value: "pleese".to_string(),
+                corrected_value: "please".to_string(),
+                range: 32..38,
+            },
+        ];
+        assert_eq!(1, proposals.len());
+        assert_eq!(
+            "Prepare some Coffee, boiling hot please",
+            proposals[0].text.text
+        );
+        assert_eq!(expected_corrections, proposals[0].corrections);
+        assert_eq!(19..27, proposals[0].text.original_range(&(21..28)));
+    }
+
+    #[test]
+    fn test_entities_are_not_corrected() {
+        // Given
+        let input = "make a cup of hott tea";
+        let custom_entity_parser = MockedCustomEntityParser::from_iter(vec![(
+            input.to_string(),
+            vec![CustomEntity {
+                value: "hott".to_string(),
+                resolved_value: "hot".to_string(),
+                alternative_resolved_values: vec![],
+                range: 14..18,
+                entity_identifier: "Temperature".to_string(),
+                match_score: None,
+            }],
+        )]);
+        let shared_resources = SharedResourcesBuilder::default()
+            .custom_entity_parser(custom_entity_parser)
+            .build();
+        let corrector = build_corrector(shared_resources);
+        let normalized_input = TextNormalizer::new(vec![], Language::EN).normalize(input);
+
+        // When
+        let proposals = corrector
+            .propose_corrections(input, &normalized_input)
+            .unwrap();
+
+        // Then
+        assert!(proposals.is_empty());
+    }
+
+    #[test]
+    fn test_propose_alternative_corrections() {
+        // Given
+        let vocabulary = vec!["cold", "bold", "coffee"]
+            .into_iter()
+            .map(|word| word.to_string());
+        let corrector = SpellingCorrector::new(
+            vocabulary,
+            SpellingCorrectionConfig::default(),
+            Arc::new(SharedResourcesBuilder::default().build()),
+        );
+        let input = "cofee xold";
+        let normalized_input = TextNormalizer::new(vec![], Language::EN).normalize(input);
+
+        // When
+        let proposals = corrector
+            .propose_corrections(input, &normalized_input)
+            .unwrap();
+
+        // Then
+        let proposed_texts: Vec<&str> = proposals
+            .iter()
+            .map(|proposal| &*proposal.text.text)
+            .collect();
+        assert_eq!(vec!["coffee bold", "coffee cold"], proposed_texts);
+    }
+
+    #[test]
+    fn test_vocabulary_into_words() {
+        // Given
+        let vocabulary = Vocabulary {
+            words: vec!["coffee".to_string()],
+            stems: vec!["prepar".to_string(), "tea".to_string()],
+        };
+        let inflections = hashmap! {
+            "prepar".to_string() => vec!["prepare".to_string(), "preparing".to_string()],
+        };
+
+        // When
+        let words = vocabulary.into_words(&inflections);
+
+        // Then
+        let expected_words = vec!["coffee", "prepare", "preparing", "tea"];
+        assert_eq!(expected_words, words);
+    }
+}
@@ -11,6 +11,7 @@ use crate::resources::gazetteer::Gazetteer;
 use crate::resources::stemmer::Stemmer;
 use crate::resources::word_clusterer::WordClusterer;
 use crate::resources::SharedResources;
+use crate::slot_filler::FeatureRegistry;
 
 pub fn assert_epsilon_eq_array1(a: &Array1<f32>, b: &Array1<f32>, epsilon: f32) {
     assert_eq!(a.dim(), b.dim());
@@ -31,6 +32,7 @@ pub struct SharedResourcesBuilder {
     stemmer: Option<Arc<dyn Stemmer>>,
     word_clusterers: HashMap<String, Arc<dyn WordClusterer>>,
     stop_words: HashSet<String>,
+    feature_registry: FeatureRegistry,
 }
 
 impl Default for SharedResourcesBuilder {
@@ -42,6 +44,7 @@ impl Default for SharedResourcesBuilder {
             stemmer: None,
             word_clusterers: HashMap::default(),
             stop_words: HashSet::default(),
+            feature_registry: FeatureRegistry::default(),
         }
     }
 }
@@ -57,11 +60,22 @@ impl SharedResourcesBuilder {
         self
     }
 
+    pub fn gazetteer<G: Gazetteer + 'static>(mut self, name: &str, gazetteer: G) -> Self {
+        self.gazetteers
+            .insert(name.to_string(), Arc::new(gazetteer) as _);
+        self
+    }
+
     pub fn stop_words(mut self, stop_words: HashSet<String>) -> Self {
         self.stop_words = stop_words;
         self
     }
 
+    pub fn feature_registry(mut self, feature_registry: FeatureRegistry) -> Self {
+        self.feature_registry = feature_registry;
+        self
+    }
+
     pub fn build(self) -> SharedResources {
         SharedResources {
             builtin_entity_parser: self.builtin_entity_parser,
@@ -70,6 +84,7 @@ impl SharedResourcesBuilder {
             stemmer: self.stemmer,
             word_clusterers: self.word_clusterers,
             stop_words: self.stop_words,
+            feature_registry: self.feature_registry,
         }
     }
 }
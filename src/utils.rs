@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fs;
 use std::io;
@@ -61,6 +62,28 @@ where
     deduplicated_items
 }
 
+/// Total ordering of scores in which NaN values are lower than any other score, so that sorting
+/// scores never panics
+pub fn cmp_scores(lhs: f32, rhs: f32) -> Ordering {
+    match (lhs.is_nan(), rhs.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => lhs.partial_cmp(&rhs).unwrap(), // Both values are comparable
+    }
+}
+
+/// Checks that all the values of a model parameter are finite
+pub fn check_finite_values<'a, I>(values: I, parameter: &str) -> Result<()>
+where
+    I: IntoIterator<Item = &'a f32>,
+{
+    if values.into_iter().any(|value| !value.is_finite()) {
+        return Err(SnipsNluError::NonFiniteModelValues(parameter.to_string()).into());
+    }
+    Ok(())
+}
+
 pub fn extract_nlu_engine_zip_archive<R: io::Read + io::Seek>(
     zip_reader: R,
     dest_path: &Path,
@@ -142,27 +165,26 @@ where
 
     let mut range_mapping: HashMap<Range<usize>, Range<usize>> = HashMap::new();
     let mut processed_text = "".to_string();
-    let mut offset = 0;
+    // Ranges are computed from the length of the processed text, in chars, rather than from a
+    // signed offset, so that the mapping is correct whatever the length of the input
+    let mut processed_text_length = 0;
     let mut current_ix = 0;
 
     for matched_entity in dedup_matches {
-        let range_start = (matched_entity.range.start as i16 + offset) as usize;
         let prefix_text =
             substring_with_char_range(text.to_string(), &(current_ix..matched_entity.range.start));
         let entity_text = placeholder_fn(&*matched_entity.entity_name);
-        processed_text = format!("{}{}{}", processed_text, prefix_text, entity_text);
-        offset += entity_text.chars().count() as i16 - matched_entity.range.clone().count() as i16;
-        let range_end = (matched_entity.range.end as i16 + offset) as usize;
-        let new_range = range_start..range_end;
+        processed_text.push_str(&prefix_text);
+        processed_text_length += prefix_text.chars().count();
+        let range_start = processed_text_length;
+        processed_text.push_str(&entity_text);
+        processed_text_length += entity_text.chars().count();
+        let new_range = range_start..processed_text_length;
         current_ix = matched_entity.range.end;
         range_mapping.insert(new_range, matched_entity.range);
     }
 
-    processed_text = format!(
-        "{}{}",
-        processed_text,
-        suffix_from_char_index(text.to_string(), current_ix)
-    );
+    processed_text.push_str(&suffix_from_char_index(text.to_string(), current_ix));
     (range_mapping, processed_text)
 }
 
@@ -199,4 +221,49 @@ mod tests {
         let expected_items = vec![0..8, 9..13];
         assert_eq!(expected_items, dedup_items);
     }
+
+    #[test]
+    fn test_cmp_scores() {
+        // Given
+        let mut scores = vec![0.2, std::f32::NAN, 0.7, 0.1];
+
+        // When
+        scores.sort_by(|a, b| cmp_scores(*b, *a));
+
+        // Then
+        assert_eq!(vec![0.7, 0.2, 0.1], scores[..3].to_vec());
+        assert!(scores[3].is_nan());
+    }
+
+    #[test]
+    fn test_replace_entities_in_long_input() {
+        // Given
+        let long_prefix = "a".repeat(40_000);
+        let text = format!("{} b {} c", long_prefix, long_prefix);
+        let entities = vec![
+            MatchedEntity {
+                range: 40_001..40_002,
+                entity_name: "entity_b".to_string(),
+            },
+            MatchedEntity {
+                range: 80_004..80_005,
+                entity_name: "entity_c".to_string(),
+            },
+        ];
+
+        // When
+        let (range_mapping, processed_text) =
+            replace_entities(&text, entities, |entity| format!("%{}%", entity));
+
+        // Then
+        let expected_text = format!("{} %entity_b% {} %entity_c%", long_prefix, long_prefix);
+        let expected_mapping: HashMap<Range<usize>, Range<usize>> = vec![
+            (40_001..40_011, 40_001..40_002),
+            (80_013..80_023, 80_004..80_005),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(expected_text, processed_text);
+        assert_eq!(expected_mapping, range_mapping);
+    }
 }